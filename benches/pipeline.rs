@@ -2,9 +2,12 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use rust_challenge::factories::defaults::generator;
 use rust_challenge::services::pipeline_orig;
 use rust_challenge::services::stats::pipeline;
+use rust_challenge::utils::time::FixedClock;
 
 fn bench_pipelines(c: &mut Criterion) {
     let transfers = generator()
+        .with_seed(42)
+        .with_clock(FixedClock(1_700_000_000))
         .build()
         .generate(1_000_000)
         .expect("Failed to generate transfers for benchmark"); // Ok to use expect here as in benchmark it's idiomatic to fail in setup phase
@@ -0,0 +1,84 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use tracing::error;
+
+use crate::errors::Error;
+
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound(String),
+    BadRequest(String),
+    Internal(Error),
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: &'static str,
+    message: String,
+}
+
+impl From<Error> for ApiError {
+    fn from(error: Error) -> Self {
+        ApiError::Internal(error)
+    }
+}
+
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Internal(error) => match error {
+                Error::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                Error::StorageUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+                Error::SchemaMismatch { .. }
+                | Error::QueryFailed { .. }
+                | Error::ConfigurationMissing { .. }
+                | Error::InvalidConfiguration(_)
+                | Error::Io { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+
+        let body = match self {
+            ApiError::NotFound(message) => ErrorBody {
+                error: "not_found",
+                message,
+            },
+            ApiError::BadRequest(message) => ErrorBody {
+                error: "bad_request",
+                message,
+            },
+            ApiError::Internal(error) => {
+                if status.is_server_error() {
+                    error!(error = %error, kind = error.kind(), "request failed");
+                }
+
+                // Server side details stay in the logs, clients only learn what went wrong
+                let message = match &error {
+                    Error::ValidationFailed(_) => error.to_string(),
+                    _ => status
+                        .canonical_reason()
+                        .unwrap_or("Internal Server Error")
+                        .to_string(),
+                };
+
+                ErrorBody {
+                    error: error.kind(),
+                    message,
+                }
+            }
+        };
+
+        (status, Json(body)).into_response()
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::debug;
+
+use crate::{models::user_stats::UserStats, services::live::LiveFeed};
+
+// A client that takes longer than this to accept a message is dropped
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Sent as JSON text at any time to replace the current subscription
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Subscription {
+    // Every address when empty
+    pub addresses: HashSet<String>,
+    pub min_volume: Option<f64>,
+}
+
+impl Subscription {
+    pub fn matches(&self, stats: &UserStats) -> bool {
+        (self.addresses.is_empty() || self.addresses.contains(&stats.address))
+            && self.min_volume.is_none_or(|min| stats.total_volume >= min)
+    }
+}
+
+// The initial subscription, `addresses` is comma separated
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LiveQuery {
+    addresses: Option<String>,
+    min_volume: Option<f64>,
+}
+
+impl From<LiveQuery> for Subscription {
+    fn from(query: LiveQuery) -> Self {
+        Subscription {
+            addresses: query
+                .addresses
+                .iter()
+                .flat_map(|addresses| addresses.split(','))
+                .map(str::trim)
+                .filter(|address| !address.is_empty())
+                .map(str::to_string)
+                .collect(),
+            min_volume: query.min_volume,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveMessage {
+    // Everything matching the subscription, on connect, on resubscribe and after lagging
+    Snapshot { stats: Vec<UserStats> },
+    // Current stats of matching addresses that just changed
+    Update { stats: Vec<UserStats> },
+    // This many updates were dropped because the client read too slowly, a snapshot follows
+    Lagged { skipped: u64 },
+    Error { message: String },
+}
+
+pub async fn session(mut socket: WebSocket, feed: Arc<LiveFeed>, mut subscription: Subscription) {
+    // Subscribed before the snapshot, so nothing falls in between
+    let mut updates = feed.subscribe();
+
+    if !send(&mut socket, snapshot(&feed, &subscription)).await {
+        return;
+    }
+
+    loop {
+        let messages = tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    let stats: Vec<UserStats> = update
+                        .iter()
+                        .filter(|stats| subscription.matches(stats))
+                        .cloned()
+                        .collect();
+                    if stats.is_empty() {
+                        continue;
+                    }
+
+                    vec![LiveMessage::Update { stats }]
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!(skipped, "live stats subscriber lagged");
+                    vec![
+                        LiveMessage::Lagged { skipped },
+                        snapshot(&feed, &subscription),
+                    ]
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                    Ok(resubscribed) => {
+                        subscription = resubscribed;
+                        vec![snapshot(&feed, &subscription)]
+                    }
+                    Err(e) => vec![LiveMessage::Error {
+                        message: format!("Invalid subscription: {}", e),
+                    }],
+                },
+                // Pings are answered by axum
+                Some(Ok(Message::Binary(_) | Message::Ping(_) | Message::Pong(_))) => continue,
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+            },
+        };
+
+        for message in messages {
+            if !send(&mut socket, message).await {
+                return;
+            }
+        }
+    }
+}
+
+fn snapshot(feed: &LiveFeed, subscription: &Subscription) -> LiveMessage {
+    LiveMessage::Snapshot {
+        stats: feed.snapshot(|stats| subscription.matches(stats)),
+    }
+}
+
+// False once the client is gone or too slow to keep
+async fn send(socket: &mut WebSocket, message: LiveMessage) -> bool {
+    let Ok(text) = serde_json::to_string(&message) else {
+        return false;
+    };
+
+    match tokio::time::timeout(SEND_TIMEOUT, socket.send(Message::Text(text.into()))).await {
+        Ok(sent) => sent.is_ok(),
+        Err(_) => {
+            debug!("dropping live stats subscriber that stopped reading");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscriptions_filter_by_address_and_volume() {
+        let stats = |address: &str, total_volume: f64| UserStats {
+            address: address.to_string(),
+            total_volume,
+            ..Default::default()
+        };
+        let subscription = Subscription::from(LiveQuery {
+            addresses: Some("0xA, 0xB,".to_string()),
+            min_volume: Some(10.0),
+        });
+
+        assert!(subscription.matches(&stats("0xA", 10.0)));
+        assert!(!subscription.matches(&stats("0xB", 9.0)));
+        assert!(!subscription.matches(&stats("0xC", 100.0)));
+        assert!(Subscription::default().matches(&stats("0xC", 0.0)));
+    }
+}
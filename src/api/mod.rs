@@ -0,0 +1,311 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State, WebSocketUpgrade},
+    http::StatusCode,
+    response::Response,
+    routing::get,
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    errors::{Error, Result},
+    metrics::{self, metrics},
+    models::{
+        transfer::{Transfer, TransferFilter},
+        user_stats::UserStats,
+    },
+    repositories::{shared::SharedStorage, storage::Storage},
+    services::{
+        analytics::Analytics,
+        ingest::validate_transfers,
+        live::LiveFeed,
+        stats::{
+            calculator::CalculatesStats,
+            ranking::{Direction, RankBy, Ranking},
+        },
+    },
+    utils::shutdown::shutdown_signal,
+};
+
+pub mod error;
+pub mod live;
+
+use error::ApiError;
+use live::LiveQuery;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1_000;
+
+pub struct ApiState<S: Storage, C> {
+    storage: SharedStorage<S>,
+    calculator: Arc<C>,
+    feed: Arc<LiveFeed>,
+}
+
+impl<S: Storage, C> Clone for ApiState<S, C> {
+    fn clone(&self) -> Self {
+        ApiState {
+            storage: self.storage.clone(),
+            calculator: self.calculator.clone(),
+            feed: self.feed.clone(),
+        }
+    }
+}
+
+impl<S: Storage, C> ApiState<S, C> {
+    pub fn new(storage: S, calculator: C) -> Self {
+        Self::shared(SharedStorage::new(storage), Arc::new(calculator))
+    }
+
+    // Serves storage and calculator that other servers use as well
+    pub fn shared(storage: SharedStorage<S>, calculator: Arc<C>) -> Self {
+        ApiState {
+            storage,
+            calculator,
+            feed: Arc::default(),
+        }
+    }
+
+    // Live subscribers see transfers ingested through this feed, by any server sharing it
+    pub fn with_feed(mut self, feed: Arc<LiveFeed>) -> Self {
+        self.feed = feed;
+        self
+    }
+}
+
+// Also serves `/metrics`
+pub fn router<S, C>(state: ApiState<S, C>) -> Router
+where
+    S: Storage + Send + Sync + 'static,
+    C: CalculatesStats + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/stats", get(list_stats::<S, C>))
+        .route("/stats/live", get(live_stats::<S, C>))
+        .route("/stats/{address}", get(get_user_stats::<S, C>))
+        .route(
+            "/transfers",
+            get(list_transfers::<S, C>).post(ingest_transfers::<S, C>),
+        )
+        .with_state(state)
+        .merge(metrics::server::router())
+}
+
+pub async fn serve(addr: SocketAddr, router: Router) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| Error::io(format!("Could not bind {}", addr), e))?;
+
+    info!(%addr, "serving the HTTP API");
+
+    axum::serve(listener, router)
+        .with_graceful_shutdown(shutdown_signal())
+        .await
+        .map_err(|e| Error::io("HTTP server failed", e))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    pub items: Vec<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ingested {
+    pub ingested: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct StatsQuery {
+    from: Option<u64>,
+    to: Option<u64>,
+    sort: RankBy,
+    order: Direction,
+    limit: usize,
+    offset: usize,
+}
+
+impl Default for StatsQuery {
+    fn default() -> Self {
+        StatsQuery {
+            from: None,
+            to: None,
+            sort: RankBy::default(),
+            order: Direction::default(),
+            limit: DEFAULT_PAGE_SIZE,
+            offset: 0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TimeRange {
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct TransfersQuery {
+    address: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+    limit: usize,
+    offset: usize,
+}
+
+impl Default for TransfersQuery {
+    fn default() -> Self {
+        TransfersQuery {
+            address: None,
+            from: None,
+            to: None,
+            limit: DEFAULT_PAGE_SIZE,
+            offset: 0,
+        }
+    }
+}
+
+fn check_page_size(limit: usize) -> Result<(), ApiError> {
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(ApiError::BadRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    Ok(())
+}
+
+async fn list_stats<S, C>(
+    State(state): State<ApiState<S, C>>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Page<UserStats>>, ApiError>
+where
+    S: Storage + Send + Sync,
+    C: CalculatesStats,
+{
+    check_page_size(query.limit)?;
+
+    let filter = TransferFilter {
+        address: None,
+        from_ts: query.from,
+        to_ts: query.to,
+    };
+    let stats = Analytics::new(state.storage, state.calculator)
+        .get_filtered_stats(&filter)
+        .await?;
+
+    let total = stats.len();
+    let items = Ranking::new(query.sort, query.order)
+        .top_n(stats, query.offset.saturating_add(query.limit))
+        .into_iter()
+        .skip(query.offset)
+        .collect();
+
+    Ok(Json(Page {
+        total,
+        offset: query.offset,
+        limit: query.limit,
+        items,
+    }))
+}
+
+async fn get_user_stats<S, C>(
+    State(state): State<ApiState<S, C>>,
+    Path(address): Path<String>,
+    Query(range): Query<TimeRange>,
+) -> Result<Json<UserStats>, ApiError>
+where
+    S: Storage + Send + Sync,
+    C: CalculatesStats,
+{
+    let filter = TransferFilter {
+        address: Some(address.clone()),
+        from_ts: range.from,
+        to_ts: range.to,
+    };
+
+    Analytics::new(state.storage, state.calculator)
+        .get_filtered_stats(&filter)
+        .await?
+        .into_iter()
+        .next()
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("No transfers for {}", address)))
+}
+
+async fn live_stats<S, C>(
+    State(state): State<ApiState<S, C>>,
+    Query(query): Query<LiveQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Response
+where
+    S: Storage,
+{
+    let feed = state.feed.clone();
+
+    upgrade.on_upgrade(move |socket| live::session(socket, feed, query.into()))
+}
+
+async fn list_transfers<S, C>(
+    State(state): State<ApiState<S, C>>,
+    Query(query): Query<TransfersQuery>,
+) -> Result<Json<Page<Transfer>>, ApiError>
+where
+    S: Storage + Send + Sync,
+{
+    check_page_size(query.limit)?;
+
+    let filter = TransferFilter {
+        address: query.address,
+        from_ts: query.from,
+        to_ts: query.to,
+    };
+    let transfers = state.storage.get_filtered(&filter).await?;
+
+    Ok(Json(Page {
+        total: transfers.len(),
+        offset: query.offset,
+        limit: query.limit,
+        items: transfers
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit)
+            .collect(),
+    }))
+}
+
+async fn ingest_transfers<S, C>(
+    State(state): State<ApiState<S, C>>,
+    Json(transfers): Json<Vec<Transfer>>,
+) -> Result<(StatusCode, Json<Ingested>), ApiError>
+where
+    S: Storage + Send + Sync,
+{
+    validate_transfers(&transfers)?;
+
+    let mut storage = state.storage.write().await;
+    state.feed.insert(&mut *storage, &transfers).await?;
+    drop(storage);
+
+    // Best effort, failing now would have clients retry a stored batch
+    if let Ok(metrics) = metrics() {
+        metrics.transfers_ingested.inc_by(transfers.len() as u64);
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(Ingested {
+            ingested: transfers.len(),
+        }),
+    ))
+}
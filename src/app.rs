@@ -1,14 +1,21 @@
+use std::time::Instant;
+
+use futures::{StreamExt, TryStreamExt};
+use tracing::{info, instrument};
+
 use crate::{
-    factories::generator::TransferGenerator,
-    models::user_stats::UserStats,
+    errors::Result,
+    metrics::metrics,
+    models::{transfer::Transfer, user_stats::UserStats},
     repositories::storage::Storage,
     services::{analytics::Analytics, stats::calculator::CalculatesStats},
+    sources::TransferSource,
 };
 
 pub struct App<S: Storage, C: CalculatesStats> {
     pub storage: S,
     pub calculator: C,
-    pub generator: Box<dyn TransferGenerator>,
+    pub source: Box<dyn TransferSource>,
 }
 
 impl<S, C> App<S, C>
@@ -16,10 +23,39 @@ where
     S: Storage + Send + Sync,
     C: CalculatesStats,
 {
-    pub async fn run(mut self, transfer_count: usize) -> anyhow::Result<Vec<UserStats>> {
-        let transfers = self.generator.generate(transfer_count)?;
+    #[instrument(skip(self), err)]
+    pub async fn run(mut self, transfer_count: usize) -> Result<Vec<UserStats>> {
+        let started = Instant::now();
+        // Storage failures are counted by the storage itself
+        let transfers: Vec<Transfer> = self
+            .source
+            .open(None)
+            .await?
+            .take(transfer_count)
+            .map_ok(|positioned| positioned.transfer)
+            .try_collect()
+            .await
+            .inspect_err(|e| {
+                if let Ok(metrics) = metrics() {
+                    metrics.record_error(e);
+                }
+            })?;
+        info!(
+            transfers = transfers.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "read transfers"
+        );
 
+        let started = Instant::now();
         self.storage.insert_all(&transfers).await?;
+        if let Ok(metrics) = metrics() {
+            metrics.transfers_ingested.inc_by(transfers.len() as u64);
+        }
+        info!(
+            batch_size = transfers.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "inserted transfers"
+        );
 
         Analytics::new(self.storage, self.calculator)
             .get_stats()
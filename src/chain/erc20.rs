@@ -0,0 +1,184 @@
+use crate::{
+    errors::{Error, Result},
+    models::transfer::Transfer,
+};
+
+use super::{parse_bytes, parse_quantity, Log};
+
+// keccak256("Transfer(address,address,uint256)")
+pub const TRANSFER_TOPIC: &str =
+    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+// uint256 holds at most 78 decimal digits
+const MAX_DECIMALS: u8 = 77;
+
+// A decoded transfer with where it happened on chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferLog {
+    pub transfer: Transfer,
+    pub token: String,
+    pub block_number: u64,
+    pub tx_hash: String,
+    pub log_index: u64,
+}
+
+// Decodes `Transfer` events of one token. Logs carry no prices, every transfer gets `usd_price`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Erc20Decoder {
+    // Lowercase, logs of other contracts are ignored
+    token: String,
+    decimals: u8,
+    usd_price: f64,
+}
+
+impl Erc20Decoder {
+    pub fn new(token: &str, decimals: u8) -> Result<Self> {
+        if decimals > MAX_DECIMALS {
+            return Err(Error::InvalidConfiguration(format!(
+                "Token decimals must be at most {}, got {}",
+                MAX_DECIMALS, decimals
+            )));
+        }
+        parse_bytes::<20>("token address", token)
+            .map_err(|e| Error::InvalidConfiguration(e.to_string()))?;
+
+        Ok(Erc20Decoder {
+            token: token.to_ascii_lowercase(),
+            decimals,
+            usd_price: 1.0,
+        })
+    }
+
+    pub fn with_usd_price(mut self, usd_price: f64) -> Self {
+        self.usd_price = usd_price;
+        self
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    // `None` for logs that are not ERC-20 transfers of this token or were dropped by a reorg.
+    // `timestamp` of the log's block is needed when the node did not include `blockTimestamp`
+    pub fn decode(&self, log: &Log, timestamp: Option<u64>) -> Result<Option<TransferLog>> {
+        if log.removed || !log.address.eq_ignore_ascii_case(&self.token) {
+            return Ok(None);
+        }
+
+        // ERC-721 shares the signature but indexes the token id as a fourth topic
+        let [signature, from, to] = log.topics.as_slice() else {
+            return Ok(None);
+        };
+        if !signature.eq_ignore_ascii_case(TRANSFER_TOPIC) {
+            return Ok(None);
+        }
+
+        let block_number = required(log.block_number.as_deref(), "blockNumber")
+            .and_then(|block| parse_quantity("blockNumber", block))?;
+        let log_index = required(log.log_index.as_deref(), "logIndex")
+            .and_then(|index| parse_quantity("logIndex", index))?;
+        let tx_hash = required(log.transaction_hash.as_deref(), "transactionHash")?;
+
+        let ts = match (&log.block_timestamp, timestamp) {
+            (Some(ts), _) => parse_quantity("blockTimestamp", ts)?,
+            (None, Some(ts)) => ts,
+            (None, None) => {
+                return Err(Error::ValidationFailed(format!(
+                    "No timestamp for block {}",
+                    block_number
+                )))
+            }
+        };
+
+        let amount = parse_bytes::<32>("data", &log.data)?;
+
+        Ok(Some(TransferLog {
+            transfer: Transfer {
+                ts,
+                from: topic_address(from)?,
+                to: topic_address(to)?,
+                amount: scale(&amount, self.decimals),
+                usd_price: self.usd_price,
+            },
+            token: self.token.clone(),
+            block_number,
+            tx_hash: tx_hash.to_ascii_lowercase(),
+            log_index,
+        }))
+    }
+}
+
+fn required<'a>(value: Option<&'a str>, field: &str) -> Result<&'a str> {
+    value.ok_or_else(|| Error::ValidationFailed(format!("Log has no {}", field)))
+}
+
+// Indexed addresses are left padded to 32 bytes
+fn topic_address(topic: &str) -> Result<String> {
+    let word = parse_bytes::<32>("address topic", topic)?;
+    let (padding, address) = word.split_at(12);
+
+    if padding.iter().any(|byte| *byte != 0) {
+        return Err(Error::ValidationFailed(format!(
+            "Topic `{}` is not an address",
+            topic
+        )));
+    }
+
+    let hex: String = address.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    Ok(format!("0x{}", hex))
+}
+
+// Exact for amounts that fit in u128, which covers any realistic supply
+fn scale(raw: &[u8; 32], decimals: u8) -> f64 {
+    let (high, low) = raw.split_at(16);
+
+    if high.iter().all(|byte| *byte == 0) {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(low);
+        let value = u128::from_be_bytes(bytes);
+
+        if let Some(unit) = 10u128.checked_pow(decimals.into()) {
+            return (value / unit) as f64 + (value % unit) as f64 / unit as f64;
+        }
+    }
+
+    let value = raw
+        .iter()
+        .fold(0.0, |value, byte| value * 256.0 + f64::from(*byte));
+
+    value / 10f64.powi(decimals.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(value: u128) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[16..].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    #[test]
+    fn scales_amounts_by_decimals() {
+        assert_eq!(scale(&word(1_500_000), 6), 1.5);
+        assert_eq!(scale(&word(1), 18), 1e-18);
+        assert_eq!(scale(&word(42), 0), 42.0);
+        // 2^256 - 1 is past u128, only approximately representable
+        assert!((scale(&[0xff; 32], 77) - 1.157920892373162).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rejects_padded_garbage_as_address() {
+        let topic = format!("0x01{}", "0".repeat(62));
+
+        assert!(topic_address(&topic).is_err());
+    }
+
+    #[test]
+    fn validates_the_token() {
+        assert!(Erc20Decoder::new("0x1234", 6).is_err());
+        assert!(Erc20Decoder::new(&format!("0x{}", "a".repeat(40)), 78).is_err());
+    }
+}
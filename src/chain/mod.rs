@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{Error, Result};
+
+pub mod erc20;
+pub mod rpc;
+
+// One entry of an `eth_getLogs` result, hex strings as the node sends them
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+    pub address: String,
+    pub topics: Vec<String>,
+    pub data: String,
+    // Missing on pending logs
+    pub block_number: Option<String>,
+    pub block_hash: Option<String>,
+    pub transaction_hash: Option<String>,
+    pub log_index: Option<String>,
+    // Only some nodes include it
+    #[serde(default)]
+    pub block_timestamp: Option<String>,
+    // Set when a reorg dropped the block the log was in
+    #[serde(default)]
+    pub removed: bool,
+}
+
+// Either a bare array of logs or the whole JSON-RPC response around it
+pub fn parse_logs(json: &str) -> Result<Vec<Log>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Saved {
+        Response { result: Vec<Log> },
+        Logs(Vec<Log>),
+    }
+
+    match serde_json::from_str(json) {
+        Ok(Saved::Response { result }) => Ok(result),
+        Ok(Saved::Logs(logs)) => Ok(logs),
+        Err(e) => Err(Error::ValidationFailed(format!("Invalid logs: {}", e))),
+    }
+}
+
+// `0x` prefixed big-endian hex without leading zeros, such as block numbers
+pub fn parse_quantity(field: &str, value: &str) -> Result<u64> {
+    let digits = strip_hex_prefix(value).ok_or_else(|| {
+        Error::ValidationFailed(format!("{} `{}` is not 0x prefixed", field, value))
+    })?;
+
+    u64::from_str_radix(digits, 16)
+        .map_err(|e| Error::ValidationFailed(format!("Invalid {} `{}`: {}", field, value, e)))
+}
+
+pub fn format_quantity(value: u64) -> String {
+    format!("{:#x}", value)
+}
+
+// Exactly `N` bytes of `0x` prefixed hex
+pub fn parse_bytes<const N: usize>(field: &str, value: &str) -> Result<[u8; N]> {
+    let invalid = |reason: &str| {
+        Error::ValidationFailed(format!("Invalid {} `{}`: {}", field, value, reason))
+    };
+
+    let digits = strip_hex_prefix(value).ok_or_else(|| invalid("not 0x prefixed"))?;
+    if digits.len() != N * 2 {
+        return Err(invalid(&format!("expected {} bytes", N)));
+    }
+
+    let mut bytes = [0u8; N];
+    for (byte, pair) in bytes.iter_mut().zip(digits.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid("not hex"))?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid("not hex"))?;
+    }
+
+    Ok(bytes)
+}
+
+fn strip_hex_prefix(value: &str) -> Option<&str> {
+    value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quantities_and_bytes() -> Result<()> {
+        assert_eq!(parse_quantity("block", "0x12a05f200")?, 5_000_000_000);
+        assert_eq!(parse_quantity("block", "0x0")?, 0);
+        assert!(parse_quantity("block", "12").is_err());
+        assert_eq!(format_quantity(255), "0xff");
+
+        assert_eq!(parse_bytes::<2>("data", "0x0aFF")?, [0x0a, 0xff]);
+        assert!(parse_bytes::<2>("data", "0x0aF").is_err());
+        assert!(parse_bytes::<2>("data", "0x0aFg").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn accepts_saved_responses_and_bare_arrays() -> Result<()> {
+        let log = r#"{"address":"0x1","topics":[],"data":"0x","blockNumber":"0x1","blockHash":null,"transactionHash":null,"logIndex":"0x0"}"#;
+
+        assert_eq!(parse_logs(&format!("[{}]", log))?.len(), 1);
+        assert_eq!(
+            parse_logs(&format!(r#"{{"jsonrpc":"2.0","id":1,"result":[{}]}}"#, log))?.len(),
+            1
+        );
+        assert!(parse_logs(r#"{"error":{"code":-32005}}"#).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tokio::time::sleep;
+use tracing::{debug, warn};
+
+use super::{format_quantity, parse_quantity, Log};
+use crate::{
+    errors::{BoxError, Error, Result},
+    repositories::retry::RetryPolicy,
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// `eth_getLogs` error messages of nodes that refuse to return that many logs at once, or
+// ranges that wide. Codes don't tell, Infura answers -32005 for rate limits as well
+const LIMIT_MESSAGES: [&str; 6] = [
+    "more than",
+    "too many results",
+    "too large",
+    "too wide",
+    "response size",
+    "maximum block range",
+];
+
+// Throttled requests, worth asking again after a while. Infura answers -32005 and
+// Alchemy 429 in the body, each with its own wording
+const RATE_LIMIT_CODE: i64 = 429;
+const RATE_LIMIT_MESSAGES: [&str; 4] = ["rate", "compute units", "capacity", "too many requests"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    pub address: String,
+    pub topic: String,
+    pub from_block: u64,
+    pub to_block: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Logs {
+    Found(Vec<Log>),
+    // The node limits responses and this range is over the limit, a narrower one may not be
+    TooMany,
+}
+
+// The few Ethereum JSON-RPC methods needed to follow token transfers, over HTTP.
+// Unreachable, overloaded and timed out nodes are asked again as `retry` allows
+pub struct RpcClient {
+    url: reqwest::Url,
+    http: reqwest::Client,
+    retry: RetryPolicy,
+    ids: AtomicU64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, thiserror::Error)]
+#[error("JSON-RPC error {code}: {message}")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn is_limit(&self) -> bool {
+        let message = self.message.to_ascii_lowercase();
+
+        !self.is_rate_limit() && LIMIT_MESSAGES.iter().any(|m| message.contains(m))
+    }
+
+    fn is_rate_limit(&self) -> bool {
+        let message = self.message.to_ascii_lowercase();
+
+        self.code == RATE_LIMIT_CODE || RATE_LIMIT_MESSAGES.iter().any(|m| message.contains(m))
+    }
+}
+
+enum CallError {
+    Transient(BoxError),
+    Rpc(RpcError),
+    Invalid(BoxError),
+}
+
+#[derive(Deserialize)]
+struct Block {
+    timestamp: String,
+}
+
+impl RpcClient {
+    pub fn new(url: &str) -> Result<Self> {
+        let invalid = |reason: &dyn std::fmt::Display| {
+            Error::InvalidConfiguration(format!("Invalid JSON-RPC url `{}`: {}", url, reason))
+        };
+        let url = reqwest::Url::parse(url).map_err(|e| invalid(&e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(invalid(&"only http and https are supported"));
+        }
+        let client_error = |e: &dyn std::fmt::Display| {
+            Error::InvalidConfiguration(format!("Could not create HTTP client: {}", e))
+        };
+
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .use_preconfigured_tls(tls().map_err(|e| client_error(&e))?)
+            .build()
+            .map_err(|e| client_error(&e))?;
+
+        Ok(RpcClient {
+            url,
+            http,
+            retry: RetryPolicy::default(),
+            ids: AtomicU64::new(1),
+        })
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub async fn block_number(&self) -> Result<u64> {
+        let number: String = self.request("eth_blockNumber", json!([])).await?;
+
+        parse_quantity("block number", &number)
+    }
+
+    pub async fn block_timestamp(&self, number: u64) -> Result<u64> {
+        let params = json!([format_quantity(number), false]);
+
+        match self.call::<Block>("eth_getBlockByNumber", params).await {
+            Ok(Some(block)) => parse_quantity("block timestamp", &block.timestamp),
+            Ok(None) => Err(failed(
+                "eth_getBlockByNumber",
+                CallError::Invalid(format!("no block {}", number).into()),
+            )),
+            Err(e) => Err(failed("eth_getBlockByNumber", e)),
+        }
+    }
+
+    pub async fn logs(&self, filter: &LogFilter) -> Result<Logs> {
+        let params = json!([{
+            "address": filter.address,
+            "topics": [filter.topic],
+            "fromBlock": format_quantity(filter.from_block),
+            "toBlock": format_quantity(filter.to_block),
+        }]);
+
+        match self.call("eth_getLogs", params).await {
+            Ok(Some(logs)) => Ok(Logs::Found(logs)),
+            Ok(None) => Ok(Logs::Found(vec![])),
+            Err(CallError::Rpc(e)) if e.is_limit() => Ok(Logs::TooMany),
+            Err(e) => Err(failed("eth_getLogs", e)),
+        }
+    }
+
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        match self.call(method, params).await {
+            Ok(Some(result)) => Ok(result),
+            Ok(None) => Err(failed(method, CallError::Invalid("no result".into()))),
+            Err(e) => Err(failed(method, e)),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<Option<T>, CallError> {
+        let mut attempt = 1;
+
+        loop {
+            match self.send(method, &params).await {
+                Err(CallError::Transient(e)) if attempt < self.retry.max_attempts => {
+                    let backoff = self.retry.backoff(attempt, &mut rand::thread_rng());
+                    warn!(method, attempt, error = %e, ?backoff, "retrying JSON-RPC call");
+                    sleep(backoff).await;
+                    attempt += 1;
+                }
+                outcome => return outcome,
+            }
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &Value,
+    ) -> Result<Option<T>, CallError> {
+        let id = self.ids.fetch_add(1, Ordering::Relaxed);
+        debug!(method, id, "JSON-RPC call");
+
+        let response = self
+            .http
+            .post(self.url.clone())
+            .json(&json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+            .send()
+            .await
+            .map_err(|e| CallError::Transient(e.into()))?;
+
+        let status = response.status();
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(CallError::Transient(format!("HTTP {}", status).into()));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| CallError::Transient(e.into()))?;
+        // Some nodes send JSON-RPC errors with a 4xx status
+        let response: Response<T> = serde_json::from_slice(&body).map_err(|e| {
+            if status.is_success() {
+                CallError::Invalid(e.into())
+            } else {
+                CallError::Invalid(format!("HTTP {}", status).into())
+            }
+        })?;
+
+        match response.error {
+            Some(error) if error.is_rate_limit() => Err(CallError::Transient(error.into())),
+            Some(error) => Err(CallError::Rpc(error)),
+            None => Ok(response.result),
+        }
+    }
+}
+
+// ClickHouse already pulls in the aws-lc-rs provider, rustls has no default once there are two
+fn tls() -> Result<rustls::ClientConfig, rustls::Error> {
+    let roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+
+    Ok(rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::aws_lc_rs::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth())
+}
+
+fn failed(method: &str, error: CallError) -> Error {
+    let source = match error {
+        CallError::Transient(e) | CallError::Invalid(e) => e,
+        CallError::Rpc(e) => e.into(),
+    };
+
+    Error::io(format!("{} failed", method), std::io::Error::other(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(code: i64, message: &str) -> RpcError {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn recognizes_response_limits() {
+        assert!(error(-32005, "query returned more than 10000 results").is_limit());
+        assert!(error(
+            -32602,
+            "Log response size exceeded. You can make eth_getLogs requests with up to a 2K block range"
+        )
+        .is_limit());
+        assert!(error(-32000, "block range is too wide").is_limit());
+
+        assert!(error(-32000, "exceed maximum block range: 5000").is_limit());
+
+        assert!(!error(-32601, "the method eth_getLogs does not exist").is_limit());
+        assert!(!error(-32000, "header not found").is_limit());
+        assert!(!error(-32602, "invalid block range").is_limit());
+    }
+
+    #[test]
+    fn recognizes_rate_limits() {
+        let throttled = [
+            error(-32005, "project ID request rate exceeded"),
+            error(
+                429,
+                "Your app has exceeded its compute units per second capacity",
+            ),
+            error(-32000, "Too Many Requests"),
+        ];
+
+        for error in throttled {
+            assert!(error.is_rate_limit(), "{}", error);
+            assert!(!error.is_limit(), "{}", error);
+        }
+        assert!(!error(-32005, "query returned more than 10000 results").is_rate_limit());
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        assert!(matches!(
+            RpcClient::new("localhost:8545"),
+            Err(Error::InvalidConfiguration(_))
+        ));
+        assert!(RpcClient::new("http://localhost:8545").is_ok());
+    }
+}
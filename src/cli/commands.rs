@@ -0,0 +1,374 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio::io::BufReader;
+use tracing::info;
+
+use super::{Cli, Command, ConfigCommand, IngestArgs, LeaderboardArgs, StatsArgs};
+use crate::{
+    api::{self, ApiState},
+    config::{ClickhouseConfig, StorageBackend, StorageConfig},
+    daemon::Daemon,
+    errors::{Error, Result},
+    factories::{
+        clickhouse::ClickhouseFactory,
+        generator::{DefaultTransferGenerator, TransferGenConfig},
+        stream::StreamsTransfers,
+    },
+    grpc::{self, GrpcService},
+    models::{
+        transfer::{TransferFilter, TransferOrdering},
+        user_stats::UserStats,
+    },
+    output::write_stats,
+    repositories::{
+        clickhouse::ClickhouseStorage,
+        layers::{DynStorage, StorageLayers},
+        mock::MockStorage,
+        retry::{CircuitBreakerConfig, RetryPolicy},
+        shared::SharedStorage,
+        storage::Storage,
+    },
+    services::{
+        analytics::Analytics,
+        ingest::ingest_stream,
+        live::LiveFeed,
+        stats::{
+            calculator::StatsCalculator,
+            ranking::{Direction, Leaderboard, RankBy, Ranking},
+        },
+    },
+    sources::jsonl,
+    utils::{
+        shutdown::shutdown_signal,
+        time::{Now, SystemNow},
+    },
+};
+
+pub async fn run(cli: Cli) -> Result<()> {
+    let config = cli.effective_config()?;
+
+    match cli.command {
+        Command::Generate(args) => match &args.output {
+            Some(path) => generate(
+                &config.generator,
+                args.count,
+                &mut BufWriter::new(create(path)?),
+            ),
+            None => generate(&config.generator, args.count, &mut io::stdout().lock()),
+        },
+        Command::Ingest(args) => {
+            let mut storage = open_storage(&config.storage).await?;
+            let ingested = ingest(&mut storage, &args).await?;
+            info!(ingested, "ingest finished");
+            Ok(())
+        }
+        Command::Stats(args) => {
+            let storage = open_storage(&config.storage).await?;
+            let stats = stats(storage, config.calculator.calculator(), &args).await?;
+
+            let options = config.output.options();
+            match &args.output {
+                Some(path) => write_stats(BufWriter::new(create(path)?), options, &stats),
+                None => write_stats(BufWriter::new(io::stdout()), options, &stats),
+            }
+        }
+        Command::Leaderboard(args) => {
+            let storage = open_storage(&config.storage).await?;
+            let leaderboard = leaderboard(storage, config.calculator.calculator(), &args).await?;
+
+            write!(io::stdout(), "{}", leaderboard)
+                .map_err(|e| Error::io("Could not write leaderboard", e))
+        }
+        Command::Serve(_) => {
+            let storage = SharedStorage::new(open_storage(&config.storage).await?);
+            let calculator = Arc::new(config.calculator.calculator());
+
+            // Live stats pick up from everything already stored
+            let mut incremental = config.calculator.incremental();
+            incremental.apply(&storage.get_sorted(TransferOrdering::Chronological).await?);
+            let feed = Arc::new(LiveFeed::new(incremental));
+
+            let state =
+                ApiState::shared(storage.clone(), calculator.clone()).with_feed(feed.clone());
+            let http = api::serve(config.server.addr, api::router(state));
+
+            match config.server.grpc_addr {
+                Some(addr) => {
+                    let service = GrpcService::shared(storage, calculator).with_feed(feed);
+                    tokio::try_join!(http, grpc::serve(addr, service)).map(|_| ())
+                }
+                None => http.await,
+            }
+        }
+        Command::Daemon(_) => {
+            let storage = open_storage(&config.storage).await?;
+
+            let mut stats = config.calculator.incremental();
+            stats.apply(&storage.get_sorted(TransferOrdering::Chronological).await?);
+
+            let source = config.daemon.source.source(&config.generator)?;
+            let report = Daemon::new(storage, stats, config.daemon, config.output.options())
+                .run_source(source.as_ref(), shutdown_signal())
+                .await?;
+            info!(
+                ingested = report.ingested,
+                skipped = report.skipped,
+                "daemon finished"
+            );
+            Ok(())
+        }
+        Command::Migrate => match config.storage.backend {
+            StorageBackend::Clickhouse => clickhouse(&config.storage.clickhouse)?.migrate().await,
+            StorageBackend::Memory => {
+                info!("memory storage has no schema to migrate");
+                Ok(())
+            }
+        },
+        Command::Reset => match config.storage.backend {
+            StorageBackend::Clickhouse => clickhouse(&config.storage.clickhouse)?.reset().await,
+            StorageBackend::Memory => {
+                info!("memory storage has nothing to reset");
+                Ok(())
+            }
+        },
+        Command::Config(ConfigCommand::Print) => write!(io::stdout(), "{}", config.to_toml()?)
+            .map_err(|e| Error::io("Could not print configuration", e)),
+    }
+}
+
+fn create(path: &Path) -> Result<File> {
+    File::create(path).map_err(|e| Error::io(format!("Could not create {}", path.display()), e))
+}
+
+fn clickhouse(config: &ClickhouseConfig) -> Result<ClickhouseStorage> {
+    Ok(ClickhouseFactory::connect(&config.client_config()?))
+}
+
+async fn open_storage(config: &StorageConfig) -> Result<DynStorage> {
+    let layers = StorageLayers::default()
+        .with_retry(RetryPolicy::default(), CircuitBreakerConfig::default())
+        .with_tracing();
+
+    match config.backend {
+        StorageBackend::Clickhouse => {
+            let storage = clickhouse(&config.clickhouse)?;
+            storage.migrate().await?;
+            Ok(layers.apply(storage))
+        }
+        StorageBackend::Memory => Ok(layers.apply(MockStorage::appending())),
+    }
+}
+
+// Streamed rather than built whole. Spread over the last `max_age_secs` like batch generation,
+// but in chronological order whatever the price model
+pub fn generate(config: &TransferGenConfig, count: usize, out: &mut impl Write) -> Result<()> {
+    let mut config = config.clone();
+    if config.max_age_secs > 0 {
+        config.stream_rate = count.max(1) as f64 / config.max_age_secs as f64;
+    }
+    let start = SystemNow.now_unix()?.saturating_sub(config.max_age_secs);
+    let generator = DefaultTransferGenerator {
+        config,
+        clock: Arc::new(SystemNow),
+    };
+
+    jsonl::write_transfers(out, generator.stream()?.starting_at(start).take(count))
+}
+
+pub async fn ingest<S: Storage + Send>(storage: &mut S, args: &IngestArgs) -> Result<usize> {
+    if args.input == Path::new("-") {
+        let transfers = jsonl::read_transfers(BufReader::new(tokio::io::stdin()));
+        return ingest_stream(storage, transfers, args.batch_size).await;
+    }
+
+    let file = tokio::fs::File::open(&args.input)
+        .await
+        .map_err(|e| Error::io(format!("Could not open {}", args.input.display()), e))?;
+    let transfers = jsonl::read_transfers(BufReader::new(file));
+
+    ingest_stream(storage, transfers, args.batch_size).await
+}
+
+pub async fn stats<S: Storage + Send + Sync>(
+    storage: S,
+    calculator: StatsCalculator,
+    args: &StatsArgs,
+) -> Result<Vec<UserStats>> {
+    let filter = TransferFilter {
+        address: args.address.clone(),
+        from_ts: args.from,
+        to_ts: args.to,
+    };
+
+    let mut stats = Analytics::new(storage, calculator)
+        .get_filtered_stats(&filter)
+        .await?;
+
+    let Some(by) = args.sort.metric() else {
+        stats.sort_unstable_by(|a, b| a.address.cmp(&b.address));
+        stats.truncate(args.limit.unwrap_or(stats.len()));
+        return Ok(stats);
+    };
+
+    let ranking = ranking(by, args.ascending);
+    match args.limit {
+        Some(limit) => Ok(ranking.top_n(stats, limit)),
+        None => {
+            ranking.sort(&mut stats);
+            Ok(stats)
+        }
+    }
+}
+
+pub async fn leaderboard<S: Storage + Send + Sync>(
+    storage: S,
+    calculator: StatsCalculator,
+    args: &LeaderboardArgs,
+) -> Result<Leaderboard> {
+    let filter = TransferFilter {
+        address: None,
+        from_ts: args.from,
+        to_ts: args.to,
+    };
+
+    let stats = Analytics::new(storage, calculator)
+        .get_filtered_stats(&filter)
+        .await?;
+
+    Ok(ranking(args.by, args.ascending).leaderboard(stats, Some(args.top)))
+}
+
+fn ranking(by: RankBy, ascending: bool) -> Ranking {
+    let direction = if ascending {
+        Direction::Ascending
+    } else {
+        Direction::Descending
+    };
+
+    Ranking::new(by, direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::SortKey;
+    use crate::factories::addresses::AddressPoolConfig;
+    use crate::models::transfer::Transfer;
+
+    fn stats_args() -> StatsArgs {
+        StatsArgs {
+            from: None,
+            to: None,
+            address: None,
+            sort: SortKey::Volume,
+            ascending: false,
+            limit: None,
+            format: None,
+            precision: None,
+            output: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn generated_files_ingest_back() -> Result<()> {
+        let dir = tempfile::tempdir().map_err(|e| Error::io("tempdir", e))?;
+        let path = dir.path().join("transfers.jsonl");
+
+        let mut file = File::create(&path).map_err(|e| Error::io("create", e))?;
+        let config = TransferGenConfig {
+            seed: Some(7),
+            address_pool: Some(AddressPoolConfig {
+                size: 5,
+                zipf_exponent: 1.0,
+            }),
+            ..Default::default()
+        };
+        generate(&config, 50, &mut file)?;
+
+        let mut storage = MockStorage::appending();
+        let ingested = ingest(
+            &mut storage,
+            &IngestArgs {
+                input: path,
+                batch_size: 8,
+            },
+        )
+        .await?;
+
+        assert_eq!(ingested, 50);
+        assert_eq!(storage.transfers.len(), 50);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ranks_stats() -> Result<()> {
+        let transfer = |to: &str, amount: f64| Transfer {
+            ts: 1,
+            from: "0xMint".to_string(),
+            to: to.to_string(),
+            amount,
+            usd_price: 1.0,
+        };
+        let storage_with_sales = || MockStorage {
+            transfers: vec![
+                transfer("0xB", 5.0),
+                transfer("0xA", 5.0),
+                transfer("0xC", 1.0),
+            ],
+            ..Default::default()
+        };
+
+        let by_volume = stats(
+            storage_with_sales(),
+            StatsCalculator::new().with_excluded(["0xMint".to_string()]),
+            &StatsArgs {
+                limit: Some(3),
+                ..stats_args()
+            },
+        )
+        .await?;
+
+        let addresses: Vec<&str> = by_volume.iter().map(|s| s.address.as_str()).collect();
+        assert_eq!(addresses, ["0xA", "0xB", "0xC"]);
+
+        let by_address = stats(
+            storage_with_sales(),
+            StatsCalculator::new().with_excluded(["0xMint".to_string()]),
+            &StatsArgs {
+                sort: SortKey::Address,
+                limit: Some(2),
+                ..stats_args()
+            },
+        )
+        .await?;
+
+        let addresses: Vec<&str> = by_address.iter().map(|s| s.address.as_str()).collect();
+        assert_eq!(addresses, ["0xA", "0xB"]);
+
+        let leaderboard = leaderboard(
+            storage_with_sales(),
+            StatsCalculator::new(),
+            &LeaderboardArgs {
+                from: None,
+                to: None,
+                by: RankBy::Volume,
+                ascending: true,
+                top: 2,
+            },
+        )
+        .await?;
+
+        let ranks: Vec<(usize, &str)> = leaderboard
+            .entries
+            .iter()
+            .map(|e| (e.rank, e.stats.address.as_str()))
+            .collect();
+        assert_eq!(ranks, [(1, "0xC"), (2, "0xA")]);
+
+        Ok(())
+    }
+}
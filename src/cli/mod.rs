@@ -0,0 +1,603 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+
+use crate::{
+    config::{AppConfig, OutputConfig, StorageBackend},
+    daemon::DaemonConfig,
+    errors::{Error, Result},
+    factories::{
+        addresses::AddressPoolConfig, balances::BalanceConfig, generator::TransferGenConfig,
+    },
+    output::OutputFormat,
+    services::stats::ranking::RankBy,
+    sources::{ChainConfig, SourceConfig},
+};
+
+pub mod commands;
+
+#[derive(Debug, Parser)]
+#[command(name = "rust_challenge", version, about = "Token transfer analytics")]
+pub struct Cli {
+    /// TOML configuration file, overridden by environment variables and flags
+    #[arg(long, global = true, env = "APP_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Storage backend, `memory` keeps nothing between runs [default: clickhouse]
+    #[arg(long, global = true, value_enum)]
+    pub storage: Option<StorageBackend>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Generate fake transfers as JSON lines
+    Generate(GenerateArgs),
+    /// Load JSON lines transfers into storage
+    Ingest(IngestArgs),
+    /// Calculate per address stats from stored transfers
+    Stats(StatsArgs),
+    /// Rank addresses by a metric
+    Leaderboard(LeaderboardArgs),
+    /// Serve stats and transfers over HTTP and, when configured, gRPC
+    Serve(ServeArgs),
+    /// Keep ingesting transfers from a source and publishing stats until stopped
+    Daemon(DaemonArgs),
+    /// Create missing tables
+    Migrate,
+    /// Drop and recreate all tables
+    Reset,
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective configuration after merging file, environment and flags
+    Print,
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateArgs {
+    #[arg(short, long, default_value_t = 20)]
+    pub count: usize,
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[arg(long)]
+    pub min_amount: Option<f64>,
+    #[arg(long)]
+    pub max_amount: Option<f64>,
+    #[arg(long)]
+    pub min_price: Option<f64>,
+    #[arg(long)]
+    pub max_price: Option<f64>,
+    #[arg(long)]
+    pub max_age_secs: Option<u64>,
+    /// Draw addresses from a fixed pool of this size
+    #[arg(long)]
+    pub address_pool: Option<usize>,
+    /// Zipf exponent of activity within the address pool
+    #[arg(long)]
+    pub zipf_exponent: Option<f64>,
+    /// Only let addresses send what they received, seeded by airdrops
+    #[arg(long)]
+    pub balances: bool,
+    /// Defaults to stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct IngestArgs {
+    /// JSON lines file, `-` for stdin
+    #[arg(short, long, default_value = "-")]
+    pub input: PathBuf,
+    #[arg(long, default_value_t = 10_000)]
+    pub batch_size: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    /// Inclusive lower bound on the transfer timestamp
+    #[arg(long)]
+    pub from: Option<u64>,
+    /// Inclusive upper bound on the transfer timestamp
+    #[arg(long)]
+    pub to: Option<u64>,
+    #[arg(long)]
+    pub address: Option<String>,
+    #[arg(long, value_enum, default_value = "volume")]
+    pub sort: SortKey,
+    /// Lowest metric first. Addresses are always listed A to Z
+    #[arg(long)]
+    pub ascending: bool,
+    /// Keep only the first stats in sort order
+    #[arg(long)]
+    pub limit: Option<usize>,
+    /// Defaults to table
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+    /// Digits after the decimal point, full precision by default
+    #[arg(long)]
+    pub precision: Option<usize>,
+    /// Defaults to stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+// Any ranking metric, or the address which only makes sense for listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    Address,
+    Volume,
+    MaxBalance,
+    Pnl,
+    AvgBuyPrice,
+    AvgSellPrice,
+}
+
+impl SortKey {
+    pub fn metric(self) -> Option<RankBy> {
+        match self {
+            SortKey::Address => None,
+            SortKey::Volume => Some(RankBy::Volume),
+            SortKey::MaxBalance => Some(RankBy::MaxBalance),
+            SortKey::Pnl => Some(RankBy::Pnl),
+            SortKey::AvgBuyPrice => Some(RankBy::AvgBuyPrice),
+            SortKey::AvgSellPrice => Some(RankBy::AvgSellPrice),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct LeaderboardArgs {
+    /// Inclusive lower bound on the transfer timestamp
+    #[arg(long)]
+    pub from: Option<u64>,
+    /// Inclusive upper bound on the transfer timestamp
+    #[arg(long)]
+    pub to: Option<u64>,
+    #[arg(long, value_enum, default_value = "volume")]
+    pub by: RankBy,
+    #[arg(long)]
+    pub ascending: bool,
+    #[arg(long, default_value_t = 10)]
+    pub top: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Defaults to 127.0.0.1:8080
+    #[arg(long)]
+    pub addr: Option<SocketAddr>,
+    /// Also serve gRPC on this address
+    #[arg(long)]
+    pub grpc_addr: Option<SocketAddr>,
+}
+
+// Sources the chain flags make no sense with
+const NON_CHAIN_SOURCES: [&str; 5] = ["generator", "stdin", "file", "dir", "listen"];
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("source").args(["generator", "stdin", "file", "dir", "listen", "rpc_url"])))]
+pub struct DaemonArgs {
+    /// Read generated transfers [default source]
+    #[arg(long)]
+    pub generator: bool,
+    /// Read JSON lines from stdin until it closes
+    #[arg(long)]
+    pub stdin: bool,
+    /// Read a JSON lines file, or CSV with a header when it ends in `.csv`
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+    /// Read `*.jsonl` and `*.csv` files from a directory, including ones added later
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+    /// Accept JSON lines over TCP on this address
+    #[arg(long)]
+    pub listen: Option<SocketAddr>,
+    /// Read ERC-20 transfer logs from this Ethereum JSON-RPC endpoint
+    #[arg(long)]
+    pub rpc_url: Option<String>,
+    /// Contract address of the token read from the chain
+    #[arg(long, conflicts_with_all = NON_CHAIN_SOURCES)]
+    pub token: Option<String>,
+    /// Decimals of the token read from the chain, defaults to 18
+    #[arg(long, conflicts_with_all = NON_CHAIN_SOURCES)]
+    pub decimals: Option<u8>,
+    /// First block read from the chain
+    #[arg(long, conflicts_with_all = NON_CHAIN_SOURCES)]
+    pub from_block: Option<u64>,
+    /// Last block read from the chain, follows the head when unset
+    #[arg(long, conflicts_with_all = NON_CHAIN_SOURCES)]
+    pub to_block: Option<u64>,
+    #[arg(long)]
+    pub batch_size: Option<usize>,
+    #[arg(long)]
+    pub snapshot_interval_secs: Option<u64>,
+    /// Replace this file with the current stats on every snapshot
+    #[arg(long)]
+    pub snapshot_path: Option<PathBuf>,
+    /// Save the source position here after every batch and resume from it on start
+    #[arg(long)]
+    pub checkpoint_path: Option<PathBuf>,
+    /// Snapshot format, defaults to table
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+}
+
+impl Cli {
+    // Configuration file and environment with this invocation's flags on top, validated
+    pub fn effective_config(&self) -> Result<AppConfig> {
+        let mut config = AppConfig::load(self.config.as_deref())?;
+
+        if let Some(backend) = self.storage {
+            config.storage.backend = backend;
+        }
+
+        match &self.command {
+            Command::Generate(args) => args.apply(&mut config.generator),
+            Command::Stats(args) => args.apply(&mut config.output),
+            Command::Daemon(args) => {
+                args.apply(&mut config.daemon)?;
+                if let Some(format) = args.format {
+                    config.output.format = format;
+                }
+            }
+            Command::Serve(args) => {
+                if let Some(addr) = args.addr {
+                    config.server.addr = addr;
+                }
+                if let Some(addr) = args.grpc_addr {
+                    config.server.grpc_addr = Some(addr);
+                }
+            }
+            _ => {}
+        }
+
+        config.validate()?;
+
+        Ok(config)
+    }
+}
+
+impl GenerateArgs {
+    fn apply(&self, config: &mut TransferGenConfig) {
+        if let Some(seed) = self.seed {
+            config.seed = Some(seed);
+        }
+        if let Some(min_amount) = self.min_amount {
+            config.min_amount = min_amount;
+        }
+        if let Some(max_amount) = self.max_amount {
+            config.max_amount = max_amount;
+        }
+        if let Some(min_price) = self.min_price {
+            config.min_price = min_price;
+        }
+        if let Some(max_price) = self.max_price {
+            config.max_price = max_price;
+        }
+        if let Some(max_age_secs) = self.max_age_secs {
+            config.max_age_secs = max_age_secs;
+        }
+        if let Some(size) = self.address_pool {
+            config
+                .address_pool
+                .get_or_insert_with(AddressPoolConfig::default)
+                .size = size;
+        }
+        if let Some(zipf_exponent) = self.zipf_exponent {
+            config
+                .address_pool
+                .get_or_insert_with(AddressPoolConfig::default)
+                .zipf_exponent = zipf_exponent;
+        }
+        if self.balances && config.balances.is_none() {
+            config.balances = Some(BalanceConfig::default());
+        }
+    }
+}
+
+impl StatsArgs {
+    fn apply(&self, config: &mut OutputConfig) {
+        if let Some(format) = self.format {
+            config.format = format;
+        }
+        if let Some(precision) = self.precision {
+            config.precision = Some(precision);
+        }
+    }
+}
+
+impl DaemonArgs {
+    fn chain_flags(&self) -> bool {
+        self.token.is_some()
+            || self.decimals.is_some()
+            || self.from_block.is_some()
+            || self.to_block.is_some()
+    }
+
+    fn apply(&self, config: &mut DaemonConfig) -> Result<()> {
+        if self.generator {
+            config.source = SourceConfig::Generator;
+        }
+        if self.stdin {
+            config.source = SourceConfig::Stdin;
+        }
+        if let Some(path) = &self.file {
+            config.source = SourceConfig::File { path: path.clone() };
+        }
+        if let Some(path) = &self.dir {
+            let poll_interval_ms = match config.source {
+                SourceConfig::Directory {
+                    poll_interval_ms, ..
+                } => poll_interval_ms,
+                _ => SourceConfig::DEFAULT_POLL_INTERVAL_MS,
+            };
+            config.source = SourceConfig::Directory {
+                path: path.clone(),
+                poll_interval_ms,
+            };
+        }
+        if let Some(addr) = self.listen {
+            config.source = SourceConfig::Tcp { addr };
+        }
+        if let Some(url) = &self.rpc_url {
+            let mut chain = match &config.source {
+                SourceConfig::Chain(chain) => chain.clone(),
+                _ => ChainConfig::default(),
+            };
+            chain.url = url.clone();
+            config.source = SourceConfig::Chain(chain);
+        }
+        // Also when the source comes from the configuration rather than a flag
+        if self.chain_flags() && !matches!(config.source, SourceConfig::Chain(_)) {
+            return Err(Error::InvalidConfiguration(
+                "--token, --decimals, --from-block and --to-block need a chain source".to_string(),
+            ));
+        }
+        if let SourceConfig::Chain(chain) = &mut config.source {
+            if let Some(token) = &self.token {
+                chain.token = token.clone();
+            }
+            if let Some(decimals) = self.decimals {
+                chain.decimals = decimals;
+            }
+            if let Some(block) = self.from_block {
+                chain.from_block = block;
+            }
+            if let Some(block) = self.to_block {
+                chain.to_block = Some(block);
+            }
+        }
+        if let Some(batch_size) = self.batch_size {
+            config.batch_size = batch_size;
+        }
+        if let Some(secs) = self.snapshot_interval_secs {
+            config.snapshot_interval_secs = secs;
+        }
+        if let Some(path) = &self.snapshot_path {
+            config.snapshot_path = Some(path.clone());
+        }
+        if let Some(path) = &self.checkpoint_path {
+            config.checkpoint_path = Some(path.clone());
+        }
+
+        Ok(())
+    }
+}
+
+// sysexits(3) codes, so scripts can tell bad input from an unreachable backend
+pub fn exit_code(error: &Error) -> ExitCode {
+    let code = match error {
+        Error::ValidationFailed(_) => 65,
+        Error::StorageUnavailable { .. } => 69,
+        Error::SchemaMismatch { .. } | Error::QueryFailed { .. } => 70,
+        Error::Io { .. } => 74,
+        Error::ConfigurationMissing { .. } | Error::InvalidConfiguration(_) => 78,
+    };
+
+    ExitCode::from(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subcommands_with_global_storage_flag() -> anyhow::Result<()> {
+        let cli = Cli::try_parse_from([
+            "rust_challenge",
+            "stats",
+            "--storage",
+            "memory",
+            "--from",
+            "10",
+            "--sort",
+            "max-balance",
+            "--limit",
+            "5",
+        ])?;
+
+        assert_eq!(cli.storage, Some(StorageBackend::Memory));
+        let Command::Stats(args) = cli.command else {
+            anyhow::bail!("expected the stats command");
+        };
+        assert_eq!(args.from, Some(10));
+        assert_eq!(args.sort, SortKey::MaxBalance);
+        assert_eq!(args.limit, Some(5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn daemon_sources_are_exclusive() -> anyhow::Result<()> {
+        let cli = Cli::try_parse_from([
+            "rust_challenge",
+            "--storage",
+            "memory",
+            "daemon",
+            "--dir",
+            "incoming",
+            "--batch-size",
+            "50",
+        ])?;
+        let config = cli.effective_config()?;
+
+        assert_eq!(
+            config.daemon.source,
+            SourceConfig::Directory {
+                path: PathBuf::from("incoming"),
+                poll_interval_ms: SourceConfig::DEFAULT_POLL_INTERVAL_MS,
+            }
+        );
+        assert_eq!(config.daemon.batch_size, 50);
+
+        assert!(
+            Cli::try_parse_from(["rust_challenge", "daemon", "--stdin", "--dir", "x"]).is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn daemon_reads_chains_from_flags() -> anyhow::Result<()> {
+        let usdc = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+        let cli = Cli::try_parse_from([
+            "rust_challenge",
+            "--storage",
+            "memory",
+            "daemon",
+            "--rpc-url",
+            "http://localhost:8545",
+            "--token",
+            usdc,
+            "--decimals",
+            "6",
+            "--from-block",
+            "19000000",
+        ])?;
+        let config = cli.effective_config()?;
+
+        assert_eq!(
+            config.daemon.source,
+            SourceConfig::Chain(ChainConfig {
+                url: "http://localhost:8545".to_string(),
+                token: usdc.to_string(),
+                decimals: 6,
+                from_block: 19_000_000,
+                ..ChainConfig::default()
+            })
+        );
+
+        let no_token = Cli::try_parse_from([
+            "rust_challenge",
+            "--storage",
+            "memory",
+            "daemon",
+            "--rpc-url",
+            "http://localhost:8545",
+        ])?;
+        assert!(matches!(
+            no_token.effective_config(),
+            Err(Error::InvalidConfiguration(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_chain_flags_without_a_chain_source() -> anyhow::Result<()> {
+        let token = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+
+        assert!(Cli::try_parse_from([
+            "rust_challenge",
+            "daemon",
+            "--file",
+            "transfers.jsonl",
+            "--token",
+            token,
+        ])
+        .is_err());
+
+        let cli = Cli::try_parse_from([
+            "rust_challenge",
+            "--storage",
+            "memory",
+            "daemon",
+            "--from-block",
+            "19000000",
+        ])?;
+        assert!(matches!(
+            cli.effective_config(),
+            Err(Error::InvalidConfiguration(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unknown_backends() {
+        assert!(Cli::try_parse_from(["rust_challenge", "--storage", "s3", "migrate"]).is_err());
+    }
+
+    #[test]
+    fn flags_override_the_configuration_file() -> anyhow::Result<()> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        std::io::Write::write_all(
+            &mut file,
+            b"[generator]\nseed = 1\nmax_amount = 10.0\n\n[generator.address_pool]\nsize = 50\n",
+        )?;
+        let path = file.path().to_string_lossy().to_string();
+
+        let cli = Cli::try_parse_from([
+            "rust_challenge",
+            "--config",
+            &path,
+            "--storage",
+            "memory",
+            "generate",
+            "--seed",
+            "9",
+            "--zipf-exponent",
+            "0.5",
+        ])?;
+        let config = cli.effective_config()?;
+
+        assert_eq!(config.storage.backend, StorageBackend::Memory);
+        assert_eq!(config.generator.seed, Some(9));
+        assert_eq!(config.generator.max_amount, 10.0);
+        let pool = config
+            .generator
+            .address_pool
+            .ok_or_else(|| anyhow::anyhow!("pool from the file"))?;
+        assert_eq!((pool.size, pool.zipf_exponent), (50, 0.5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_merged_configuration() -> anyhow::Result<()> {
+        let cli = Cli::try_parse_from([
+            "rust_challenge",
+            "generate",
+            "--min-amount",
+            "5",
+            "--max-amount",
+            "1",
+        ])?;
+
+        assert!(matches!(
+            cli.effective_config(),
+            Err(Error::InvalidConfiguration(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    daemon::DaemonConfig,
+    errors::{Error, Result},
+    factories::{
+        clickhouse::{ClickhouseClientConfig, ClickhouseCompression},
+        generator::TransferGenConfig,
+    },
+    output::{OutputFormat, OutputOptions},
+    services::stats::{calculator::StatsCalculator, incremental::IncrementalStats},
+    sources::SourceConfig,
+};
+
+// f64 has 17 significant digits at most, anything beyond is noise
+const MAX_PRECISION: usize = 17;
+
+// Built-in defaults, then the TOML file, then environment variables, then CLI flags.
+// Every section may be left out of the file
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub storage: StorageConfig,
+    pub generator: TransferGenConfig,
+    pub calculator: CalculatorConfig,
+    pub output: OutputConfig,
+    pub server: ServerConfig,
+    pub daemon: DaemonConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    pub clickhouse: ClickhouseConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Clickhouse,
+    // Keeps nothing between runs
+    Memory,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClickhouseConfig {
+    // Only required when ClickHouse is actually used
+    pub url: Option<String>,
+    pub user: String,
+    pub password: String,
+    pub database: String,
+    pub query_timeout_secs: Option<u64>,
+    pub compression: ClickhouseCompression,
+}
+
+impl Default for ClickhouseConfig {
+    fn default() -> Self {
+        ClickhouseConfig {
+            url: None,
+            user: "default".to_string(),
+            password: String::new(),
+            database: "default".to_string(),
+            query_timeout_secs: None,
+            compression: ClickhouseCompression::default(),
+        }
+    }
+}
+
+impl ClickhouseConfig {
+    pub fn client_config(&self) -> Result<ClickhouseClientConfig> {
+        let host = self
+            .url
+            .clone()
+            .ok_or_else(|| Error::ConfigurationMissing {
+                key: "storage.clickhouse.url".to_string(),
+                source: None,
+            })?;
+
+        Ok(ClickhouseClientConfig {
+            host,
+            user: self.user.clone(),
+            password: self.password.clone(),
+            database: self.database.clone(),
+            query_timeout_secs: self.query_timeout_secs,
+            compression: self.compression,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalculatorConfig {
+    pub excluded_addresses: Vec<String>,
+}
+
+impl CalculatorConfig {
+    pub fn calculator(&self) -> StatsCalculator {
+        StatsCalculator::new().with_excluded(self.excluded_addresses.iter().cloned())
+    }
+
+    pub fn incremental(&self) -> IncrementalStats {
+        IncrementalStats::new().with_excluded(self.excluded_addresses.iter().cloned())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub format: OutputFormat,
+    pub precision: Option<usize>,
+}
+
+impl OutputConfig {
+    pub fn options(&self) -> OutputOptions {
+        OutputOptions {
+            format: self.format,
+            precision: self.precision,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub addr: SocketAddr,
+    // gRPC is only served when set
+    pub grpc_addr: Option<SocketAddr>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            addr: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            grpc_addr: None,
+        }
+    }
+}
+
+impl AppConfig {
+    // Defaults overlaid with the file, if any, and the process environment. Not yet validated,
+    // CLI flags still go on top
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+
+        config.apply_env(|key| std::env::var(key).ok())?;
+
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| Error::io(format!("Could not read {}", path.display()), e))?;
+
+        Self::from_toml(&contents)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        toml::from_str(contents)
+            .map_err(|e| Error::InvalidConfiguration(format!("Invalid configuration file: {}", e)))
+    }
+
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        let clickhouse = &mut self.storage.clickhouse;
+
+        if let Some(backend) = var("STORAGE_BACKEND") {
+            self.storage.backend = parse_enum("STORAGE_BACKEND", &backend)?;
+        }
+        if let Some(url) = var("CLICKHOUSE_URL") {
+            clickhouse.url = Some(url);
+        }
+        if let Some(user) = var("CLICKHOUSE_USER") {
+            clickhouse.user = user;
+        }
+        if let Some(password) = var("CLICKHOUSE_PASSWORD") {
+            clickhouse.password = password;
+        }
+        if let Some(database) = var("CLICKHOUSE_DB") {
+            clickhouse.database = database;
+        }
+        if let Some(timeout) = var("CLICKHOUSE_QUERY_TIMEOUT_SECS") {
+            clickhouse.query_timeout_secs = Some(parse("CLICKHOUSE_QUERY_TIMEOUT_SECS", &timeout)?);
+        }
+        if let Some(compression) = var("CLICKHOUSE_COMPRESSION") {
+            clickhouse.compression = parse_enum("CLICKHOUSE_COMPRESSION", &compression)?;
+        }
+        if let Some(seed) = var("GENERATOR_SEED") {
+            self.generator.seed = Some(parse("GENERATOR_SEED", &seed)?);
+        }
+        if let Some(excluded) = var("CALCULATOR_EXCLUDED_ADDRESSES") {
+            self.calculator.excluded_addresses = excluded
+                .split(',')
+                .map(str::trim)
+                .filter(|address| !address.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if let Some(format) = var("OUTPUT_FORMAT") {
+            self.output.format = parse_enum("OUTPUT_FORMAT", &format)?;
+        }
+        if let Some(precision) = var("OUTPUT_PRECISION") {
+            self.output.precision = Some(parse("OUTPUT_PRECISION", &precision)?);
+        }
+        if let Some(addr) = var("SERVER_ADDR") {
+            self.server.addr = parse("SERVER_ADDR", &addr)?;
+        }
+        if let Some(addr) = var("SERVER_GRPC_ADDR") {
+            self.server.grpc_addr = Some(parse("SERVER_GRPC_ADDR", &addr)?);
+        }
+
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let generator = &self.generator;
+        let invalid = |message: &str| Err(Error::InvalidConfiguration(message.to_string()));
+
+        if !(generator.min_amount > 0.0 && generator.min_amount <= generator.max_amount) {
+            return invalid("generator.min_amount must be positive and at most max_amount");
+        }
+        if !(generator.min_price > 0.0 && generator.min_price <= generator.max_price) {
+            return invalid("generator.min_price must be positive and at most max_price");
+        }
+        if !(generator.stream_rate > 0.0 && generator.stream_rate.is_finite()) {
+            return invalid("generator.stream_rate must be positive");
+        }
+        if let Some(pool) = &generator.address_pool {
+            if pool.size < 2 || pool.zipf_exponent.is_nan() || pool.zipf_exponent < 0.0 {
+                return invalid(
+                    "generator.address_pool needs at least 2 addresses and a non-negative zipf_exponent",
+                );
+            }
+        }
+        if self.storage.clickhouse.query_timeout_secs == Some(0) {
+            return invalid("storage.clickhouse.query_timeout_secs must be positive");
+        }
+        let daemon = &self.daemon;
+        if daemon.batch_size == 0 {
+            return invalid("daemon.batch_size must be positive");
+        }
+        if daemon.flush_interval_ms == 0 || daemon.snapshot_interval_secs == 0 {
+            return invalid("daemon.flush_interval_ms and snapshot_interval_secs must be positive");
+        }
+        if let SourceConfig::Directory {
+            poll_interval_ms: 0,
+            ..
+        } = daemon.source
+        {
+            return invalid("daemon.source.directory.poll_interval_ms must be positive");
+        }
+        if let SourceConfig::Chain(chain) = &daemon.source {
+            if chain.url.is_empty() {
+                return invalid("daemon.source.chain.url must be set");
+            }
+            if chain.max_block_range == 0 || chain.poll_interval_ms == 0 {
+                return invalid(
+                    "daemon.source.chain.max_block_range and poll_interval_ms must be positive",
+                );
+            }
+            if !(chain.usd_price > 0.0 && chain.usd_price.is_finite()) {
+                return invalid("daemon.source.chain.usd_price must be positive");
+            }
+            chain.decoder()?;
+        }
+        if self.output.precision.is_some_and(|p| p > MAX_PRECISION) {
+            return Err(Error::InvalidConfiguration(format!(
+                "output.precision must be at most {}",
+                MAX_PRECISION
+            )));
+        }
+
+        Ok(())
+    }
+
+    // The effective configuration as TOML, with the password masked
+    pub fn to_toml(&self) -> Result<String> {
+        let mut printable = self.clone();
+        if !printable.storage.clickhouse.password.is_empty() {
+            printable.storage.clickhouse.password = "***".to_string();
+        }
+
+        toml::to_string_pretty(&printable).map_err(|e| {
+            Error::InvalidConfiguration(format!("Could not print configuration: {}", e))
+        })
+    }
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| Error::InvalidConfiguration(format!("Invalid {} `{}`: {}", key, value, e)))
+}
+
+fn parse_enum<T: ValueEnum>(key: &str, value: &str) -> Result<T> {
+    T::from_str(value.trim(), true)
+        .map_err(|e| Error::InvalidConfiguration(format!("Invalid {} `{}`: {}", key, value, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::factories::prices::PriceModel;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn layers_env_over_file_over_defaults() -> Result<()> {
+        let mut config = AppConfig::from_toml(
+            r#"
+            [storage]
+            backend = "memory"
+
+            [storage.clickhouse]
+            url = "http://file:8123"
+            query_timeout_secs = 30
+
+            [generator]
+            seed = 1
+            max_amount = 50.0
+            price_model = { geometric_brownian = { initial = 1.0, drift = 0.0, volatility = 0.3 } }
+
+            [calculator]
+            excluded_addresses = ["0xMint"]
+            "#,
+        )?;
+
+        config.apply_env(env(&[
+            ("CLICKHOUSE_URL", "http://env:8123"),
+            ("GENERATOR_SEED", "2"),
+            ("OUTPUT_FORMAT", "CSV"),
+        ]))?;
+        config.validate()?;
+
+        assert_eq!(config.storage.backend, StorageBackend::Memory);
+        assert_eq!(
+            config.storage.clickhouse.url.as_deref(),
+            Some("http://env:8123")
+        );
+        assert_eq!(config.storage.clickhouse.query_timeout_secs, Some(30));
+        assert_eq!(config.storage.clickhouse.user, "default");
+        assert_eq!(config.generator.seed, Some(2));
+        assert_eq!(config.generator.max_amount, 50.0);
+        assert_eq!(config.generator.min_amount, 1.0);
+        assert!(matches!(
+            config.generator.price_model,
+            PriceModel::GeometricBrownian { .. }
+        ));
+        assert_eq!(config.calculator.excluded_addresses, ["0xMint"]);
+        assert_eq!(config.output.format, OutputFormat::Csv);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() -> Result<()> {
+        assert!(AppConfig::from_toml("[storage]\nbackend = \"s3\"").is_err());
+        assert!(AppConfig::from_toml("[output]\nprecison = 2").is_err());
+
+        let mut config = AppConfig::default();
+        assert!(config
+            .apply_env(env(&[("OUTPUT_PRECISION", "two")]))
+            .is_err());
+
+        config.generator.min_amount = 10.0;
+        config.generator.max_amount = 1.0;
+        assert!(config.validate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn requires_a_url_only_to_connect() {
+        let config = AppConfig::default();
+
+        assert!(config.validate().is_ok());
+        assert!(matches!(
+            config.storage.clickhouse.client_config(),
+            Err(Error::ConfigurationMissing { key, .. }) if key == "storage.clickhouse.url"
+        ));
+    }
+
+    #[test]
+    fn prints_round_trippable_toml_without_the_password() -> Result<()> {
+        let mut config = AppConfig::default();
+        config.storage.clickhouse.password = "secret".to_string();
+        config.generator.price_model = PriceModel::MeanReverting {
+            initial: 1.0,
+            mean: 1.0,
+            reversion_speed: 2.0,
+            volatility: 0.1,
+        };
+
+        let printed = config.to_toml()?;
+
+        assert!(!printed.contains("secret"));
+        let parsed = AppConfig::from_toml(&printed)?;
+        assert_eq!(parsed.generator, config.generator);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,576 @@
+use std::fs::File;
+use std::future::Future;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::time::{self, MissedTickBehavior};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    errors::{Error, Result},
+    metrics::metrics,
+    models::transfer::{Transfer, TransferOrdering},
+    output::{write_stats, OutputOptions},
+    repositories::storage::Storage,
+    services::{
+        ingest::validate_transfers,
+        stats::{incremental::IncrementalStats, ranking::Ranking},
+    },
+    sources::{load_checkpoint, save_checkpoint, Cursor, Positioned, SourceConfig, TransferSource},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    pub source: SourceConfig,
+    pub batch_size: usize,
+    // Partial batches are stored once they are this old
+    pub flush_interval_ms: u64,
+    pub snapshot_interval_secs: u64,
+    // Snapshots are written here in the output format, replacing the previous one.
+    // Only logged when unset
+    pub snapshot_path: Option<PathBuf>,
+    // The source cursor is saved here after every stored batch and resumed from on start
+    pub checkpoint_path: Option<PathBuf>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        DaemonConfig {
+            source: SourceConfig::default(),
+            batch_size: 1_000,
+            flush_interval_ms: 1_000,
+            snapshot_interval_secs: 60,
+            snapshot_path: None,
+            checkpoint_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DaemonReport {
+    pub ingested: usize,
+    // Malformed or invalid transfers, logged and dropped
+    pub skipped: usize,
+    pub snapshots: usize,
+}
+
+// Long running counterpart of `App`: stores transfers from a source as they come and keeps
+// stats up to date without recalculating them
+pub struct Daemon<S: Storage> {
+    storage: S,
+    stats: IncrementalStats,
+    config: DaemonConfig,
+    output: OutputOptions,
+    pending: Vec<Transfer>,
+    // Past the last transfer read, and past the last one stored or skipped as of the checkpoint
+    cursor: Option<Cursor>,
+    checkpointed: Option<Cursor>,
+    report: DaemonReport,
+}
+
+impl<S: Storage + Send> Daemon<S> {
+    // `stats` should already reflect what the storage holds
+    pub fn new(
+        storage: S,
+        stats: IncrementalStats,
+        config: DaemonConfig,
+        output: OutputOptions,
+    ) -> Self {
+        Daemon {
+            storage,
+            stats,
+            pending: Vec::with_capacity(config.batch_size),
+            cursor: None,
+            checkpointed: None,
+            config,
+            output,
+            report: DaemonReport::default(),
+        }
+    }
+
+    // Opens the source where the checkpoint, if any, left off
+    pub async fn run_source(
+        mut self,
+        source: &dyn TransferSource,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<DaemonReport> {
+        let from = match &self.config.checkpoint_path {
+            Some(path) => load_checkpoint(path)?,
+            None => None,
+        };
+        if let Some(cursor) = &from {
+            info!(?cursor, "resuming from checkpoint");
+        }
+        self.checkpointed = from.clone();
+
+        let transfers = source.open(from).await?;
+
+        self.run(transfers, shutdown).await
+    }
+
+    // Runs until the source ends, fails, or `shutdown` resolves. Transfers read by then are
+    // stored and a final snapshot is published either way
+    #[instrument(skip_all, err)]
+    pub async fn run<T>(
+        mut self,
+        transfers: T,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<DaemonReport>
+    where
+        T: Stream<Item = Result<Positioned>>,
+    {
+        let mut transfers = std::pin::pin!(transfers);
+        let mut shutdown = std::pin::pin!(shutdown);
+
+        let mut flushes = interval(Duration::from_millis(self.config.flush_interval_ms)).await;
+        let mut snapshots = interval(Duration::from_secs(self.config.snapshot_interval_secs)).await;
+
+        // Pushing only fails when it flushes
+        let mut flush_failed = false;
+        let outcome = loop {
+            tokio::select! {
+                biased;
+                _ = &mut shutdown => {
+                    info!("shutting down");
+                    break Ok(());
+                }
+                _ = flushes.tick() => {
+                    if let Err(e) = self.flush().await {
+                        flush_failed = true;
+                        break Err(e);
+                    }
+                }
+                _ = snapshots.tick() => {
+                    if let Err(e) = self.snapshot() {
+                        break Err(e);
+                    }
+                }
+                transfer = transfers.next() => match transfer {
+                    Some(Ok(transfer)) => {
+                        if let Err(e) = self.push(transfer).await {
+                            flush_failed = true;
+                            break Err(e);
+                        }
+                    }
+                    Some(Err(e @ Error::ValidationFailed(_))) => self.skip(&e),
+                    Some(Err(e)) => break Err(e),
+                    None => {
+                        info!("source exhausted");
+                        break Ok(());
+                    }
+                },
+            }
+        };
+
+        // Inserts aren't idempotent, a failed batch may have been stored anyway. It is not retried,
+        // the checkpoint stays before it. Anything else still has what was read stored
+        if flush_failed {
+            outcome?;
+        } else {
+            let flushed = self.flush().await;
+            outcome.and(flushed)?;
+        }
+        self.snapshot()?;
+
+        info!(
+            ingested = self.report.ingested,
+            skipped = self.report.skipped,
+            "daemon stopped"
+        );
+
+        Ok(self.report)
+    }
+
+    async fn push(&mut self, positioned: Positioned) -> Result<()> {
+        let Positioned { transfer, cursor } = positioned;
+        self.cursor = Some(cursor);
+
+        if let Err(e) = validate_transfers(std::slice::from_ref(&transfer)) {
+            self.skip(&e);
+            return Ok(());
+        }
+
+        self.pending.push(transfer);
+        if self.pending.len() >= self.config.batch_size {
+            self.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    fn skip(&mut self, error: &Error) {
+        warn!(error = %error, "skipping transfer");
+        if let Ok(metrics) = metrics() {
+            metrics.record_error(error);
+        }
+        self.report.skipped += 1;
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if !self.pending.is_empty() {
+            // Stats of a batch older than what was applied are rebuilt from the stored history,
+            // read before the insert so that a failed read leaves nothing stored
+            let stored = if self.stats.is_behind(&self.pending) {
+                Some(
+                    self.storage
+                        .get_sorted(TransferOrdering::Chronological)
+                        .await?,
+                )
+            } else {
+                None
+            };
+
+            self.storage.insert_all(&self.pending).await?;
+            match stored {
+                Some(stored) => self.stats.rebuild(&stored, &self.pending),
+                None => self.stats.apply(&self.pending),
+            };
+
+            // The batch is stored, a broken registry must not get it stored again
+            if let Ok(metrics) = metrics() {
+                metrics.transfers_ingested.inc_by(self.pending.len() as u64);
+            }
+            self.report.ingested += self.pending.len();
+            self.pending.clear();
+        }
+
+        // Nothing read past the cursor is unstored now
+        if let (Some(path), Some(cursor)) = (&self.config.checkpoint_path, &self.cursor) {
+            if self.checkpointed.as_ref() != Some(cursor) {
+                save_checkpoint(path, cursor)?;
+                self.checkpointed = Some(cursor.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn snapshot(&mut self) -> Result<()> {
+        let mut stats = self.stats.snapshot();
+        Ranking::default().sort(&mut stats);
+        metrics()?.addresses_tracked.set(stats.len() as i64);
+
+        if let Some(path) = &self.config.snapshot_path {
+            // Written aside and renamed over, readers never see half a snapshot
+            let partial = path.with_extension("partial");
+            let file = File::create(&partial)
+                .map_err(|e| Error::io(format!("Could not create {}", partial.display()), e))?;
+            write_stats(BufWriter::new(file), self.output, &stats)?;
+            std::fs::rename(&partial, path)
+                .map_err(|e| Error::io(format!("Could not replace {}", path.display()), e))?;
+        }
+
+        info!(
+            addresses = stats.len(),
+            ingested = self.report.ingested,
+            "published stats snapshot"
+        );
+        self.report.snapshots += 1;
+
+        Ok(())
+    }
+}
+
+// Ticks every `period`, starting one period from now
+async fn interval(period: Duration) -> time::Interval {
+    let mut interval = time::interval(period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    interval.tick().await;
+
+    interval
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use futures::stream;
+
+    use super::*;
+    use crate::models::{
+        balance_checkpoint::BalanceCheckpoint, transfer::TransferFilter, user_stats::UserStats,
+    };
+    use crate::output::OutputFormat;
+    use crate::repositories::{mock::MockStorage, shared::SharedStorage};
+    use crate::sources::FileSource;
+
+    // Positioned right after itself in a stream ordered by timestamp
+    fn transfer(ts: u64, from: &str, to: &str) -> Positioned {
+        Positioned {
+            transfer: Transfer {
+                ts,
+                from: from.to_string(),
+                to: to.to_string(),
+                amount: 1.0,
+                usd_price: 1.0,
+            },
+            cursor: Cursor::Offset(ts + 1),
+        }
+    }
+
+    // Stores every batch and then reports a failure, as when the response is lost
+    struct FailsAfterStoring(SharedStorage<MockStorage>);
+
+    #[async_trait]
+    impl Storage for FailsAfterStoring {
+        async fn get_sorted(&self, ordering: TransferOrdering) -> Result<Vec<Transfer>> {
+            self.0.get_sorted(ordering).await
+        }
+
+        async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+            self.0.get_filtered(filter).await
+        }
+
+        async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
+            self.0.insert_all(transfers).await?;
+            Err(Error::io("insert", std::io::Error::other("timed out")))
+        }
+
+        async fn get_checkpoint(
+            &self,
+            address: &str,
+            ts: u64,
+        ) -> Result<Option<BalanceCheckpoint>> {
+            self.0.get_checkpoint(address, ts).await
+        }
+
+        async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+            self.0.insert_checkpoints(checkpoints).await
+        }
+    }
+
+    fn daemon(
+        storage: &SharedStorage<MockStorage>,
+        config: DaemonConfig,
+    ) -> Daemon<SharedStorage<MockStorage>> {
+        Daemon::new(
+            storage.clone(),
+            IncrementalStats::new(),
+            config,
+            OutputOptions::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn stores_everything_once_the_source_ends() -> Result<()> {
+        let storage = SharedStorage::new(MockStorage::appending());
+        let transfers = (0..25).map(|ts| Ok(transfer(ts, "0xA", "0xB")));
+
+        let report = daemon(
+            &storage,
+            DaemonConfig {
+                batch_size: 10,
+                ..Default::default()
+            },
+        )
+        .run(stream::iter(transfers), std::future::pending())
+        .await?;
+
+        assert_eq!(report.ingested, 25);
+        assert_eq!(report.snapshots, 1);
+        assert_eq!(storage.get_sorted(TransferOrdering::Raw).await?.len(), 25);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skips_invalid_transfers() -> Result<()> {
+        let storage = SharedStorage::new(MockStorage::appending());
+        let transfers = vec![
+            Ok(transfer(1, "0xA", "0xB")),
+            Err(Error::ValidationFailed("not json".to_string())),
+            Ok(transfer(2, "0xA", "0xA")),
+            Ok(transfer(3, "0xB", "0xA")),
+        ];
+
+        let report = daemon(&storage, DaemonConfig::default())
+            .run(stream::iter(transfers), std::future::pending())
+            .await?;
+
+        assert_eq!((report.ingested, report.skipped), (2, 2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fails_on_source_errors_after_storing_what_was_read() -> Result<()> {
+        let storage = SharedStorage::new(MockStorage::appending());
+        let transfers = vec![
+            Ok(transfer(1, "0xA", "0xB")),
+            Err(Error::io("read", std::io::Error::other("disk gone"))),
+        ];
+
+        let res = daemon(&storage, DaemonConfig::default())
+            .run(stream::iter(transfers), std::future::pending())
+            .await;
+
+        assert!(matches!(res, Err(Error::Io { .. })));
+        assert_eq!(storage.get_sorted(TransferOrdering::Raw).await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn does_not_store_a_failed_batch_again() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let checkpoint = dir.path().join("checkpoint.json");
+        let storage = SharedStorage::new(MockStorage::appending());
+        let transfers = (0..3).map(|ts| Ok(transfer(ts, "0xA", "0xB")));
+
+        let res = Daemon::new(
+            FailsAfterStoring(storage.clone()),
+            IncrementalStats::new(),
+            DaemonConfig {
+                batch_size: 2,
+                checkpoint_path: Some(checkpoint.clone()),
+                ..Default::default()
+            },
+            OutputOptions::default(),
+        )
+        .run(stream::iter(transfers), std::future::pending())
+        .await;
+
+        assert!(matches!(res, Err(Error::Io { .. })));
+        assert_eq!(storage.get_sorted(TransferOrdering::Raw).await?.len(), 2);
+        assert_eq!(load_checkpoint(&checkpoint)?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn snapshots_match_a_full_calculation_when_batches_arrive_out_of_order(
+    ) -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("stats.jsonl");
+        let storage = SharedStorage::new(MockStorage::appending());
+        let mut transfers = vec![transfer(10, "0xA", "0xB"), transfer(5, "0xMint", "0xA")];
+        transfers[1].transfer.amount = 8.0;
+
+        Daemon::new(
+            storage.clone(),
+            IncrementalStats::new(),
+            DaemonConfig {
+                batch_size: 1,
+                snapshot_path: Some(path.clone()),
+                ..Default::default()
+            },
+            OutputOptions {
+                format: OutputFormat::Jsonl,
+                precision: None,
+            },
+        )
+        .run(
+            stream::iter(transfers.into_iter().map(Ok)),
+            std::future::pending(),
+        )
+        .await?;
+
+        let snapshot = std::fs::read_to_string(&path)?;
+        let stats: Vec<UserStats> = snapshot
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        let a = stats.iter().find(|s| s.address == "0xA");
+        assert_eq!(a.map(|s| s.max_balance), Some(8.0));
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flushes_pending_transfers_on_shutdown() -> Result<()> {
+        let storage = SharedStorage::new(MockStorage::appending());
+        let transfers =
+            stream::iter((0..3).map(|ts| Ok(transfer(ts, "0xA", "0xB")))).chain(stream::pending());
+
+        let report = daemon(
+            &storage,
+            DaemonConfig {
+                flush_interval_ms: 3_600_000,
+                ..Default::default()
+            },
+        )
+        .run(transfers, time::sleep(Duration::from_secs(1)))
+        .await?;
+
+        assert_eq!(report.ingested, 3);
+        assert_eq!(storage.get_sorted(TransferOrdering::Raw).await?.len(), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn publishes_periodic_snapshots() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("stats.jsonl");
+        let storage = SharedStorage::new(MockStorage::appending());
+
+        // One transfer every 10 seconds for a minute, snapshots every 25
+        let transfers = stream::iter(0..6).then(|ts| async move {
+            time::sleep(Duration::from_secs(10)).await;
+            Ok(transfer(ts, "0xA", &format!("0x{}", ts)))
+        });
+
+        let report = Daemon::new(
+            storage,
+            IncrementalStats::new(),
+            DaemonConfig {
+                snapshot_interval_secs: 25,
+                snapshot_path: Some(path.clone()),
+                ..Default::default()
+            },
+            OutputOptions {
+                format: OutputFormat::Jsonl,
+                precision: None,
+            },
+        )
+        .run(transfers, std::future::pending())
+        .await?;
+
+        assert_eq!(report.snapshots, 3);
+        assert_eq!(std::fs::read_to_string(&path)?.lines().count(), 7);
+        assert!(!path.with_extension("partial").exists());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_checkpoint() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("transfers.jsonl");
+        let line = |ts: u64| {
+            format!(
+                "{{\"ts\":{},\"from\":\"0xA\",\"to\":\"0xB\",\"amount\":1.0,\"usd_price\":1.0}}\n",
+                ts
+            )
+        };
+        std::fs::write(&input, (0..5).map(line).collect::<String>())?;
+
+        let config = DaemonConfig {
+            batch_size: 2,
+            checkpoint_path: Some(dir.path().join("checkpoint.json")),
+            ..Default::default()
+        };
+        let run = |config: DaemonConfig| async {
+            let storage = SharedStorage::new(MockStorage::appending());
+            daemon(&storage, config)
+                .run_source(&FileSource::new(input.clone()), std::future::pending())
+                .await
+        };
+
+        assert_eq!(run(config.clone()).await?.ingested, 5);
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&input)?;
+        std::io::Write::write_all(&mut file, (5..7).map(line).collect::<String>().as_bytes())?;
+
+        assert_eq!(run(config.clone()).await?.ingested, 2);
+        assert_eq!(
+            load_checkpoint(dir.path().join("checkpoint.json").as_path())?,
+            Some(Cursor::Offset(7))
+        );
+
+        Ok(())
+    }
+}
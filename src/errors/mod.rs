@@ -1,12 +1,198 @@
-use anyhow::anyhow;
-use clickhouse::error::{Error, Result};
+use clickhouse::error::Error as ClickhouseError;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{context}: storage is unavailable")]
+    StorageUnavailable {
+        context: String,
+        #[source]
+        source: BoxError,
+    },
+    #[error("{context}: stored data does not match the expected schema")]
+    SchemaMismatch {
+        context: String,
+        #[source]
+        source: BoxError,
+    },
+    #[error("{context}: query failed")]
+    QueryFailed {
+        context: String,
+        #[source]
+        source: BoxError,
+    },
+    #[error("Validation failed: {0}")]
+    ValidationFailed(String),
+    #[error("Configuration `{key}` is missing")]
+    ConfigurationMissing {
+        key: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+    #[error("Invalid configuration: {0}")]
+    InvalidConfiguration(String),
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+// ClickHouse exception names that mean our tables or rows don't line up with the server's schema
+const SCHEMA_EXCEPTIONS: [&str; 7] = [
+    "UNKNOWN_TABLE",
+    "UNKNOWN_DATABASE",
+    "NO_SUCH_COLUMN_IN_TABLE",
+    "UNKNOWN_IDENTIFIER",
+    "THERE_IS_NO_COLUMN",
+    "TYPE_MISMATCH",
+    "CANNOT_PARSE_INPUT_ASSERTION_FAILED",
+];
+
+// Responses of an overloaded or unreachable server rather than of a bad query
+const UNAVAILABLE_RESPONSES: [&str; 7] = [
+    "Service Unavailable",
+    "Bad Gateway",
+    "Gateway Timeout",
+    "Too Many Requests",
+    "TOO_MANY_SIMULTANEOUS_QUERIES",
+    "TIMEOUT_EXCEEDED",
+    "NETWORK_ERROR",
+];
+
+impl Error {
+    // Only an unreachable or overloaded backend is worth asking again
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::StorageUnavailable { .. })
+    }
+
+    // Stable label for metrics and logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::StorageUnavailable { .. } => "storage_unavailable",
+            Error::SchemaMismatch { .. } => "schema_mismatch",
+            Error::QueryFailed { .. } => "query_failed",
+            Error::ValidationFailed(_) => "validation_failed",
+            Error::ConfigurationMissing { .. } => "configuration_missing",
+            Error::InvalidConfiguration(_) => "invalid_configuration",
+            Error::Io { .. } => "io",
+        }
+    }
+
+    pub fn io(context: impl Into<String>, source: std::io::Error) -> Self {
+        Error::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn from_clickhouse(context: &str, error: ClickhouseError) -> Self {
+        let context = context.to_string();
+
+        match &error {
+            ClickhouseError::Network(_) | ClickhouseError::TimedOut => Error::StorageUnavailable {
+                context,
+                source: Box::new(error),
+            },
+            ClickhouseError::BadResponse(response)
+                if UNAVAILABLE_RESPONSES.iter().any(|r| response.contains(r)) =>
+            {
+                Error::StorageUnavailable {
+                    context,
+                    source: Box::new(error),
+                }
+            }
+            ClickhouseError::BadResponse(response)
+                if SCHEMA_EXCEPTIONS.iter().any(|e| response.contains(e)) =>
+            {
+                Error::SchemaMismatch {
+                    context,
+                    source: Box::new(error),
+                }
+            }
+            ClickhouseError::NotEnoughData
+            | ClickhouseError::InvalidTagEncoding(_)
+            | ClickhouseError::InvalidUtf8Encoding(_)
+            | ClickhouseError::SequenceMustHaveLength
+            | ClickhouseError::DeserializeAnyNotSupported => Error::SchemaMismatch {
+                context,
+                source: Box::new(error),
+            },
+            _ => Error::QueryFailed {
+                context,
+                source: Box::new(error),
+            },
+        }
+    }
+}
 
 pub trait StorageResult<T> {
-    fn with_context(self, context: &str) -> anyhow::Result<T>;
+    fn with_context(self, context: &str) -> Result<T>;
+}
+
+impl<T> StorageResult<T> for std::result::Result<T, ClickhouseError> {
+    fn with_context(self, context: &str) -> Result<T> {
+        self.map_err(|e| Error::from_clickhouse(context, e))
+    }
 }
 
-impl<T> StorageResult<T> for Result<T, Error> {
-    fn with_context(self, context: &str) -> anyhow::Result<T> {
-        self.map_err(|e| anyhow!("{}: {}", context, e))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_clickhouse_errors() {
+        let network = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+
+        assert!(matches!(
+            Error::from_clickhouse("ctx", ClickhouseError::Network(Box::new(network))),
+            Error::StorageUnavailable { .. }
+        ));
+        assert!(matches!(
+            Error::from_clickhouse(
+                "ctx",
+                ClickhouseError::BadResponse("Service Unavailable".to_string())
+            ),
+            Error::StorageUnavailable { .. }
+        ));
+        assert!(matches!(
+            Error::from_clickhouse(
+                "ctx",
+                ClickhouseError::BadResponse(
+                    "Code: 60. DB::Exception: Table default.transfers does not exist. (UNKNOWN_TABLE)"
+                        .to_string()
+                )
+            ),
+            Error::SchemaMismatch { .. }
+        ));
+        assert!(matches!(
+            Error::from_clickhouse("ctx", ClickhouseError::NotEnoughData),
+            Error::SchemaMismatch { .. }
+        ));
+        assert!(matches!(
+            Error::from_clickhouse(
+                "ctx",
+                ClickhouseError::BadResponse("Code: 62. DB::Exception: Syntax error".to_string())
+            ),
+            Error::QueryFailed { .. }
+        ));
+    }
+
+    #[test]
+    fn keeps_the_source_chain() {
+        let error = Error::from_clickhouse("Could not fetch transfers", ClickhouseError::TimedOut);
+
+        assert_eq!(
+            error.to_string(),
+            "Could not fetch transfers: storage is unavailable"
+        );
+        assert_eq!(
+            std::error::Error::source(&error).map(|s| s.to_string()),
+            Some("timeout expired".to_string())
+        );
     }
 }
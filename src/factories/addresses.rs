@@ -0,0 +1,305 @@
+use std::collections::HashSet;
+
+use crate::errors::{Error, Result};
+use rand::{
+    distributions::{Alphanumeric, WeightedIndex},
+    prelude::Distribution,
+    Rng,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AddressPoolConfig {
+    pub size: usize,
+    // Activity of the k-th most active address is proportional to 1 / k^zipf_exponent:
+    // 0.0 is uniform, ~1.0 resembles real token activity with a few whales and a long tail
+    pub zipf_exponent: f64,
+}
+
+impl Default for AddressPoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 1_000,
+            zipf_exponent: 1.1,
+        }
+    }
+}
+
+pub enum AddressPicker {
+    // Every pick is a brand new address
+    Fresh,
+    Pool {
+        addresses: Vec<String>,
+        activity: WeightedIndex<f64>,
+        // Running totals of the activity weights, for receivers drawn without the sender
+        cumulative: Vec<f64>,
+    },
+}
+
+impl AddressPicker {
+    pub fn new(config: Option<&AddressPoolConfig>, rng: &mut impl Rng) -> Result<Self> {
+        let Some(config) = config else {
+            return Ok(AddressPicker::Fresh);
+        };
+
+        // Senders and receivers always differ, which takes two addresses
+        if config.size < 2 {
+            return Err(Error::InvalidConfiguration(
+                "Address pool must contain at least 2 addresses".to_string(),
+            ));
+        }
+
+        let mut unique = HashSet::with_capacity(config.size);
+        let mut addresses = Vec::with_capacity(config.size);
+        while addresses.len() < config.size {
+            let address = rand_address(rng);
+            if unique.insert(address.clone()) {
+                addresses.push(address);
+            }
+        }
+
+        let weights: Vec<f64> = (1..=config.size)
+            .map(|rank| 1.0 / (rank as f64).powf(config.zipf_exponent))
+            .collect();
+        let activity = WeightedIndex::new(&weights).map_err(|e| {
+            Error::InvalidConfiguration(format!("Invalid address activity distribution: {}", e))
+        })?;
+        let cumulative = weights
+            .iter()
+            .scan(0.0, |total, weight| {
+                *total += weight;
+                Some(*total)
+            })
+            .collect();
+
+        Ok(AddressPicker::Pool {
+            addresses,
+            activity,
+            cumulative,
+        })
+    }
+
+    pub fn pick(&self, rng: &mut impl Rng) -> String {
+        match self {
+            AddressPicker::Fresh => rand_address(rng),
+            AddressPicker::Pool {
+                addresses,
+                activity,
+                ..
+            } => addresses[activity.sample(rng)].clone(),
+        }
+    }
+
+    // Sender and receiver always differ
+    pub fn pick_pair(&self, rng: &mut impl Rng) -> (String, String) {
+        match self {
+            AddressPicker::Fresh => (rand_address(rng), rand_address(rng)),
+            AddressPicker::Pool {
+                addresses,
+                activity,
+                cumulative,
+            } => {
+                let from = activity.sample(rng);
+                let to = pick_other_than(cumulative, from, rng);
+
+                (addresses[from].clone(), addresses[to].clone())
+            }
+        }
+    }
+}
+
+// Draws from the activity distribution renormalised without `excluded` in a single binary
+// search: the draw skips over the excluded weight instead of landing on it
+fn pick_other_than(cumulative: &[f64], excluded: usize, rng: &mut impl Rng) -> usize {
+    let before = if excluded == 0 {
+        0.0
+    } else {
+        cumulative[excluded - 1]
+    };
+    let total = cumulative[cumulative.len() - 1];
+    let others = total - (cumulative[excluded] - before);
+
+    if !others.is_normal() {
+        // The other weights vanish next to the excluded one, treat them as equally active
+        let index = rng.gen_range(0..cumulative.len() - 1);
+        return if index >= excluded { index + 1 } else { index };
+    }
+
+    let mut target = rng.gen_range(0.0..others);
+    if target >= before {
+        target += cumulative[excluded] - before;
+    }
+
+    let index = cumulative.partition_point(|&total| total <= target);
+    if index < cumulative.len() && index != excluded {
+        return index;
+    }
+
+    // Rounding landed on the excluded weight or past the last one, take a neighbour
+    let last = cumulative.len() - 1;
+    if index == excluded && excluded < last {
+        excluded + 1
+    } else if excluded == last {
+        last - 1
+    } else {
+        last
+    }
+}
+
+pub fn rand_address(rng: &mut impl Rng) -> String {
+    let suffix: String = rng
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(char::from)
+        .collect();
+    format!("0x{}", suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn pool_limits_the_number_of_distinct_addresses() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(1);
+        let picker = AddressPicker::new(
+            Some(&AddressPoolConfig {
+                size: 5,
+                zipf_exponent: 0.0,
+            }),
+            &mut rng,
+        )?;
+
+        let picked: HashSet<String> = (0..1_000).map(|_| picker.pick(&mut rng)).collect();
+
+        assert_eq!(picked.len(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn power_law_activity_favours_top_ranked_addresses() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(2);
+        let picker = AddressPicker::new(
+            Some(&AddressPoolConfig {
+                size: 100,
+                zipf_exponent: 1.2,
+            }),
+            &mut rng,
+        )?;
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for _ in 0..10_000 {
+            *counts.entry(picker.pick(&mut rng)).or_default() += 1;
+        }
+
+        let AddressPicker::Pool { addresses, .. } = &picker else {
+            panic!("Expected a pooled picker");
+        };
+        let whale = counts.get(&addresses[0]).copied().unwrap_or(0);
+        let tail = counts.get(&addresses[99]).copied().unwrap_or(0);
+
+        assert!(whale > 20 * tail.max(1), "whale: {}, tail: {}", whale, tail);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pairs_never_transfer_to_self_in_a_pool() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(3);
+        let picker = AddressPicker::new(
+            Some(&AddressPoolConfig {
+                size: 2,
+                zipf_exponent: 2.0,
+            }),
+            &mut rng,
+        )?;
+
+        assert!((0..1_000)
+            .map(|_| picker.pick_pair(&mut rng))
+            .all(|(from, to)| from != to));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pairs_terminate_when_the_tail_is_almost_never_picked() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(5);
+        let picker = AddressPicker::new(
+            Some(&AddressPoolConfig {
+                size: 2,
+                zipf_exponent: 50.0,
+            }),
+            &mut rng,
+        )?;
+
+        let AddressPicker::Pool { addresses, .. } = &picker else {
+            panic!("Expected a pooled picker");
+        };
+
+        for _ in 0..1_000 {
+            let (from, to) = picker.pick_pair(&mut rng);
+            assert_eq!(from, addresses[0]);
+            assert_eq!(to, addresses[1]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn receivers_follow_activity_without_the_sender() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(6);
+        let picker = AddressPicker::new(
+            Some(&AddressPoolConfig {
+                size: 3,
+                zipf_exponent: 1.0,
+            }),
+            &mut rng,
+        )?;
+        let AddressPicker::Pool { addresses, .. } = &picker else {
+            panic!("Expected a pooled picker");
+        };
+
+        // Weights 1, 1/2 and 1/3: without the top address the others go 3:2
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for (from, to) in (0..30_000).map(|_| picker.pick_pair(&mut rng)) {
+            if from == addresses[0] {
+                *counts.entry(to).or_default() += 1;
+            }
+        }
+
+        let second = counts.get(&addresses[1]).copied().unwrap_or(0) as f64;
+        let third = counts.get(&addresses[2]).copied().unwrap_or(0) as f64;
+        assert!(
+            (second / third - 1.5).abs() < 0.1,
+            "{} vs {}",
+            second,
+            third
+        );
+        assert!(!counts.contains_key(&addresses[0]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn pools_smaller_than_a_pair_are_rejected() {
+        let mut rng = StdRng::seed_from_u64(4);
+
+        for size in [0, 1] {
+            let picker = AddressPicker::new(
+                Some(&AddressPoolConfig {
+                    size,
+                    ..Default::default()
+                }),
+                &mut rng,
+            );
+
+            assert!(picker.is_err());
+        }
+    }
+}
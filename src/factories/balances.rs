@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use crate::errors::{Error, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::addresses::{rand_address, AddressPicker};
+
+pub const MINT_ADDRESS: &str = "0x0000000000";
+
+// Draws from the activity distribution before falling back to any holder / a fresh address
+const PICK_ATTEMPTS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BalanceConfig {
+    // Issuer of the airdrops, the only address allowed to go negative
+    pub mint_address: String,
+    pub airdrop_recipients: usize,
+    pub airdrop_amount: f64,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            mint_address: MINT_ADDRESS.to_string(),
+            airdrop_recipients: 10,
+            airdrop_amount: 10_000.0,
+        }
+    }
+}
+
+// Simulated balances of every address that received tokens so far
+pub struct BalanceBook {
+    config: BalanceConfig,
+    balances: HashMap<String, f64>,
+    holders: Vec<String>,
+    positions: HashMap<String, usize>,
+    airdropped: usize,
+}
+
+impl BalanceBook {
+    pub fn new(config: &BalanceConfig) -> Result<Self> {
+        if config.airdrop_recipients == 0 || config.airdrop_amount <= 0.0 {
+            return Err(Error::InvalidConfiguration(
+                "Balance respecting generation needs a positive airdrop to at least one address"
+                    .to_string(),
+            ));
+        }
+
+        Ok(BalanceBook {
+            config: config.clone(),
+            balances: HashMap::new(),
+            holders: vec![],
+            positions: HashMap::new(),
+            airdropped: 0,
+        })
+    }
+
+    pub fn balance_of(&self, address: &str) -> f64 {
+        self.balances.get(address).copied().unwrap_or(0.0)
+    }
+
+    // Airdrops come first, afterwards only funded addresses send and never more than they hold
+    pub fn next_transfer(
+        &mut self,
+        addresses: &AddressPicker,
+        min_amount: f64,
+        max_amount: f64,
+        rng: &mut impl Rng,
+    ) -> (String, String, f64) {
+        if self.airdropped < self.config.airdrop_recipients {
+            self.airdropped += 1;
+
+            let to = self.pick_other_than(&self.config.mint_address, addresses, rng);
+            let amount = self.config.airdrop_amount;
+            self.credit(&to, amount);
+
+            return (self.config.mint_address.clone(), to, amount);
+        }
+
+        let (from, balance) = match self.pick_sender(addresses, rng) {
+            Some(from) => {
+                let balance = self.balance_of(&from);
+                (from, balance)
+            }
+            // Nobody holds anything, the mint issues more like it does for airdrops
+            None => (self.config.mint_address.clone(), f64::INFINITY),
+        };
+        let to = self.pick_other_than(&from, addresses, rng);
+
+        let amount = if balance <= min_amount {
+            balance
+        } else {
+            rng.gen_range(min_amount..=max_amount.clamp(min_amount, balance))
+        };
+
+        self.debit(&from, amount);
+        self.credit(&to, amount);
+
+        (from, to, amount)
+    }
+
+    fn pick_sender(&self, addresses: &AddressPicker, rng: &mut impl Rng) -> Option<String> {
+        if self.holders.is_empty() {
+            return None;
+        }
+
+        for _ in 0..PICK_ATTEMPTS {
+            let candidate = addresses.pick(rng);
+            if self.positions.contains_key(&candidate) {
+                return Some(candidate);
+            }
+        }
+
+        Some(self.holders[rng.gen_range(0..self.holders.len())].clone())
+    }
+
+    fn pick_other_than(
+        &self,
+        address: &str,
+        addresses: &AddressPicker,
+        rng: &mut impl Rng,
+    ) -> String {
+        for _ in 0..PICK_ATTEMPTS {
+            let candidate = addresses.pick(rng);
+            if candidate != address {
+                return candidate;
+            }
+        }
+
+        rand_address(rng)
+    }
+
+    fn credit(&mut self, address: &str, amount: f64) {
+        let balance = self.balances.entry(address.to_string()).or_default();
+        *balance += amount;
+
+        if *balance > 0.0 && !self.positions.contains_key(address) {
+            self.positions
+                .insert(address.to_string(), self.holders.len());
+            self.holders.push(address.to_string());
+        }
+    }
+
+    fn debit(&mut self, address: &str, amount: f64) {
+        let balance = self.balances.entry(address.to_string()).or_default();
+        *balance -= amount;
+
+        if *balance <= 0.0 {
+            if let Some(position) = self.positions.remove(address) {
+                self.holders.swap_remove(position);
+                if let Some(moved) = self.holders.get(position) {
+                    self.positions.insert(moved.clone(), position);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::factories::addresses::AddressPoolConfig;
+
+    #[test]
+    fn airdrops_before_anything_else() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(1);
+        let config = BalanceConfig {
+            airdrop_recipients: 2,
+            airdrop_amount: 50.0,
+            ..Default::default()
+        };
+        let mut book = BalanceBook::new(&config)?;
+
+        let (from, to, amount) = book.next_transfer(&AddressPicker::Fresh, 1.0, 10.0, &mut rng);
+
+        assert_eq!(from, MINT_ADDRESS);
+        assert_eq!(amount, 50.0);
+        assert_eq!(book.balance_of(&to), 50.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn senders_never_overspend() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(2);
+        let addresses = AddressPicker::new(
+            Some(&AddressPoolConfig {
+                size: 10,
+                zipf_exponent: 1.0,
+            }),
+            &mut rng,
+        )?;
+        let config = BalanceConfig {
+            airdrop_recipients: 3,
+            airdrop_amount: 100.0,
+            ..Default::default()
+        };
+        let mut book = BalanceBook::new(&config)?;
+
+        for _ in 0..3 {
+            book.next_transfer(&addresses, 1.0, 500.0, &mut rng);
+        }
+
+        for _ in 0..1_000 {
+            let (from, to, amount) = book.next_transfer(&addresses, 1.0, 500.0, &mut rng);
+
+            assert_ne!(from, to);
+            assert!(amount > 0.0);
+            assert!(book.balance_of(&from) >= 0.0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn mints_when_nobody_holds_anything() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(3);
+        let config = BalanceConfig {
+            airdrop_recipients: 1,
+            airdrop_amount: 50.0,
+            ..Default::default()
+        };
+        let mut book = BalanceBook::new(&config)?;
+
+        let (_, holder, _) = book.next_transfer(&AddressPicker::Fresh, 1.0, 10.0, &mut rng);
+        book.debit(&holder, 50.0);
+
+        let (from, to, amount) = book.next_transfer(&AddressPicker::Fresh, 1.0, 10.0, &mut rng);
+
+        assert_eq!(from, MINT_ADDRESS);
+        assert!((1.0..=10.0).contains(&amount));
+        assert_eq!(book.balance_of(&to), amount);
+
+        Ok(())
+    }
+
+    #[test]
+    fn requires_an_initial_supply() {
+        let config = BalanceConfig {
+            airdrop_recipients: 0,
+            ..Default::default()
+        };
+
+        assert!(BalanceBook::new(&config).is_err());
+    }
+}
@@ -1,7 +1,23 @@
-use anyhow::Result;
-use clickhouse::Client;
+use clap::ValueEnum;
+use clickhouse::{Client, Compression};
+use serde::{Deserialize, Serialize};
 
-use crate::{repositories::clickhouse::ClickhouseStorage, utils::env::env_get};
+use crate::{
+    errors::Result,
+    repositories::{
+        clickhouse::ClickhouseStorage,
+        layers::{DynStorage, StorageLayers},
+    },
+    utils::env::env_get,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClickhouseCompression {
+    None,
+    #[default]
+    Lz4,
+}
 
 #[derive(Debug)]
 pub struct ClickhouseClientConfig {
@@ -9,6 +25,9 @@ pub struct ClickhouseClientConfig {
     pub user: String,
     pub password: String,
     pub database: String,
+    // Server side `max_execution_time`, unlimited when unset
+    pub query_timeout_secs: Option<u64>,
+    pub compression: ClickhouseCompression,
 }
 
 impl ClickhouseClientConfig {
@@ -18,6 +37,8 @@ impl ClickhouseClientConfig {
             user: env_get("CLICKHOUSE_USER")?,
             password: env_get("CLICKHOUSE_PASSWORD")?,
             database: env_get("CLICKHOUSE_DB")?,
+            query_timeout_secs: None,
+            compression: ClickhouseCompression::default(),
         })
     }
 }
@@ -25,17 +46,41 @@ impl ClickhouseClientConfig {
 pub struct ClickhouseFactory;
 
 impl ClickhouseFactory {
-    pub async fn storage(config: ClickhouseClientConfig) -> Result<ClickhouseStorage> {
-        let client = Client::default()
+    // Connects without touching the schema
+    pub fn connect(config: &ClickhouseClientConfig) -> ClickhouseStorage {
+        let compression = match config.compression {
+            ClickhouseCompression::None => Compression::None,
+            ClickhouseCompression::Lz4 => Compression::Lz4,
+        };
+
+        let mut client = Client::default()
             .with_url(&config.host)
             .with_user(&config.user)
             .with_password(&config.password)
-            .with_database(&config.database);
+            .with_database(&config.database)
+            .with_compression(compression);
+
+        if let Some(timeout) = config.query_timeout_secs {
+            client = client.with_option("max_execution_time", timeout.to_string());
+        }
 
-        let storage = ClickhouseStorage::new(client);
+        ClickhouseStorage::new(client)
+    }
+
+    // Connects to freshly reset tables
+    pub async fn storage(config: ClickhouseClientConfig) -> Result<ClickhouseStorage> {
+        let storage = Self::connect(&config);
 
-        storage.ensure_schema().await?;
+        storage.reset().await?;
 
         Ok(storage)
     }
+
+    // The backend wrapped in whichever decorators `layers` enables
+    pub async fn layered(
+        config: ClickhouseClientConfig,
+        layers: StorageLayers,
+    ) -> Result<DynStorage> {
+        Ok(layers.apply(Self::storage(config).await?))
+    }
 }
@@ -1,23 +1,53 @@
+use std::sync::Arc;
+
+use crate::{
+    sources::GeneratorSource,
+    utils::time::{Now, SystemNow},
+};
+
 use super::generator::{DefaultTransferGenerator, TransferGenConfig, TransferGenerator};
 
 pub fn generator() -> GeneratorBuilder {
     GeneratorBuilder {
         config: TransferGenConfig::default(),
+        clock: Arc::new(SystemNow),
     }
 }
 
 pub struct GeneratorBuilder {
     config: TransferGenConfig,
+    clock: Arc<dyn Now>,
 }
 
 impl GeneratorBuilder {
     pub fn with_config(self, config: TransferGenConfig) -> Self {
-        Self { config }
+        Self { config, ..self }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn with_clock(self, clock: impl Now + 'static) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            ..self
+        }
     }
 
     pub fn build(self) -> Box<dyn TransferGenerator> {
         Box::new(DefaultTransferGenerator {
             config: self.config,
+            clock: self.clock,
+        })
+    }
+
+    // Unpaced, for reading as many transfers as needed right away
+    pub fn source(self) -> GeneratorSource {
+        GeneratorSource::new(DefaultTransferGenerator {
+            config: self.config,
+            clock: self.clock,
         })
     }
 }
@@ -1,7 +1,13 @@
-use anyhow::Result;
-use rand::{distributions::Alphanumeric, Rng};
+use std::sync::Arc;
 
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::addresses::{AddressPicker, AddressPoolConfig};
+use super::balances::{BalanceBook, BalanceConfig};
+use super::prices::{PriceModel, PricePath};
 use crate::{
+    errors::Result,
     models::transfer::Transfer,
     utils::time::{Now, SystemNow},
 };
@@ -10,13 +16,24 @@ pub trait TransferGenerator {
     fn generate(&self, count: usize) -> Result<Vec<Transfer>>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct TransferGenConfig {
     pub min_amount: f64,
     pub max_amount: f64,
     pub min_price: f64,
     pub max_price: f64,
     pub max_age_secs: u64,
+    // Same seed and clock always produce the same transfers, `None` seeds from OS entropy
+    pub seed: Option<u64>,
+    // `None` creates a fresh address for every sender and receiver
+    pub address_pool: Option<AddressPoolConfig>,
+    // Path models emit transfers in chronological order so consecutive prices are coherent
+    pub price_model: PriceModel,
+    // Only emit transfers senders can afford, starting from airdrops. Implies chronological order
+    pub balances: Option<BalanceConfig>,
+    // Average simulated transfers per second when streaming
+    pub stream_rate: f64,
 }
 
 impl Default for TransferGenConfig {
@@ -27,54 +44,277 @@ impl Default for TransferGenConfig {
             min_price: 0.1,
             max_price: 2.0,
             max_age_secs: 86_400 * 30,
+            seed: None,
+            address_pool: None,
+            price_model: PriceModel::Uniform,
+            balances: None,
+            stream_rate: 10.0,
         }
     }
 }
 
 pub struct DefaultTransferGenerator {
     pub config: TransferGenConfig,
+    pub clock: Arc<dyn Now>,
 }
 
 impl Default for DefaultTransferGenerator {
     fn default() -> Self {
         Self {
             config: TransferGenConfig::default(),
+            clock: Arc::new(SystemNow),
+        }
+    }
+}
+
+impl TransferGenConfig {
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+}
+
+// Per-run state shared by batch and streamed generation
+pub struct GenerationState {
+    rng: StdRng,
+    addresses: AddressPicker,
+    prices: PricePath,
+    book: Option<BalanceBook>,
+    min_amount: f64,
+    max_amount: f64,
+}
+
+impl GenerationState {
+    pub fn new(config: &TransferGenConfig) -> Result<Self> {
+        let mut rng = config.rng();
+        let addresses = AddressPicker::new(config.address_pool.as_ref(), &mut rng)?;
+        let prices = PricePath::new(&config.price_model, config.min_price, config.max_price)?;
+        let book = config.balances.as_ref().map(BalanceBook::new).transpose()?;
+
+        Ok(GenerationState {
+            rng,
+            addresses,
+            prices,
+            book,
+            min_amount: config.min_amount,
+            max_amount: config.max_amount,
+        })
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    // Price paths and simulated balances are only coherent when asked in chronological order
+    pub fn is_chronological(&self) -> bool {
+        self.prices.is_time_ordered() || self.book.is_some()
+    }
+
+    pub fn transfer_at(&mut self, ts: u64) -> Transfer {
+        let (from, to, amount) = self.parties();
+        let usd_price = self.prices.price_at(ts, &mut self.rng);
+
+        Transfer {
+            ts,
+            from,
+            to,
+            amount,
+            usd_price,
+        }
+    }
+
+    // Draws the timestamp last, in the order seeded output has always been produced with.
+    // Only for independent prices, which don't depend on the timestamp
+    fn unordered_transfer(&mut self, now: u64, max_age_secs: u64) -> Transfer {
+        let (from, to, amount) = self.parties();
+        let usd_price = self.prices.price_at(now, &mut self.rng);
+        let ts = now.saturating_sub(self.rng.gen_range(0..=max_age_secs));
+
+        Transfer {
+            ts,
+            from,
+            to,
+            amount,
+            usd_price,
+        }
+    }
+
+    fn parties(&mut self) -> (String, String, f64) {
+        let rng = &mut self.rng;
+
+        match self.book.as_mut() {
+            Some(book) => {
+                book.next_transfer(&self.addresses, self.min_amount, self.max_amount, rng)
+            }
+            None => {
+                let (from, to) = self.addresses.pick_pair(rng);
+                let amount = rng.gen_range(self.min_amount..=self.max_amount);
+                (from, to, amount)
+            }
         }
     }
 }
 
 impl TransferGenerator for DefaultTransferGenerator {
-    fn generate(&self, count: usize) -> anyhow::Result<Vec<Transfer>> {
-        let mut rng = rand::thread_rng();
-        let now = SystemNow::now_unix()?;
-
-        let ts = (0..count)
-            .map(|_| {
-                let from = rand_address(&mut rng);
-                let to = rand_address(&mut rng);
-                let amount = rng.gen_range(self.config.min_amount..=self.config.max_amount);
-                let usd_price = rng.gen_range(self.config.min_price..=self.config.max_price);
-                let ts = now - rng.gen_range(0..=self.config.max_age_secs);
-
-                Transfer {
-                    ts,
-                    from,
-                    to,
-                    amount,
-                    usd_price,
-                }
-            })
+    fn generate(&self, count: usize) -> Result<Vec<Transfer>> {
+        let mut state = GenerationState::new(&self.config)?;
+        let now = self.clock.now_unix()?;
+
+        if !state.is_chronological() {
+            let max_age_secs = self.config.max_age_secs;
+            return Ok((0..count)
+                .map(|_| state.unordered_transfer(now, max_age_secs))
+                .collect());
+        }
+
+        let mut timestamps: Vec<u64> = (0..count)
+            // Clocks earlier than the maximum age pile the oldest transfers up at zero
+            .map(|_| now.saturating_sub(state.rng().gen_range(0..=self.config.max_age_secs)))
+            .collect();
+        timestamps.sort_unstable();
+
+        let ts = timestamps
+            .into_iter()
+            .map(|ts| state.transfer_at(ts))
             .collect();
 
         Ok(ts)
     }
 }
 
-fn rand_address(rng: &mut impl Rng) -> String {
-    let suffix: String = rng
-        .sample_iter(&Alphanumeric)
-        .take(10)
-        .map(char::from)
-        .collect();
-    format!("0x{}", suffix)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::factories::balances::MINT_ADDRESS;
+    use crate::services::integrity::IntegrityChecker;
+    use crate::utils::time::FixedClock;
+
+    fn seeded(seed: u64) -> DefaultTransferGenerator {
+        DefaultTransferGenerator {
+            config: TransferGenConfig {
+                seed: Some(seed),
+                ..Default::default()
+            },
+            clock: Arc::new(FixedClock(1_700_000_000)),
+        }
+    }
+
+    #[test]
+    fn same_seed_produces_identical_transfers() -> Result<()> {
+        let first = seeded(42).generate(100)?;
+        let second = seeded(42).generate(100)?;
+
+        assert_eq!(first, second);
+
+        Ok(())
+    }
+
+    #[test]
+    fn clocks_earlier_than_the_maximum_age_stop_at_zero() -> Result<()> {
+        let random_walk = PriceModel::GeometricBrownian {
+            initial: 1.0,
+            drift: 0.0,
+            volatility: 0.5,
+        };
+
+        for price_model in [PriceModel::Uniform, random_walk] {
+            let generator = DefaultTransferGenerator {
+                config: TransferGenConfig {
+                    seed: Some(1),
+                    price_model,
+                    ..Default::default()
+                },
+                clock: Arc::new(FixedClock(60)),
+            };
+
+            let transfers = generator.generate(100)?;
+
+            assert!(transfers.iter().all(|t| t.ts <= 60));
+            assert!(transfers.iter().any(|t| t.ts == 0));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn different_seeds_produce_different_transfers() -> Result<()> {
+        assert_ne!(seeded(1).generate(10)?, seeded(2).generate(10)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn pooled_addresses_are_reused_across_transfers() -> Result<()> {
+        let mut generator = seeded(11);
+        generator.config.address_pool = Some(AddressPoolConfig {
+            size: 20,
+            zipf_exponent: 1.0,
+        });
+
+        let transfers = generator.generate(500)?;
+
+        let distinct: std::collections::HashSet<&str> = transfers
+            .iter()
+            .flat_map(|t| [t.from.as_str(), t.to.as_str()])
+            .collect();
+
+        assert!(distinct.len() <= 20);
+        assert!(transfers.iter().all(|t| t.from != t.to));
+
+        Ok(())
+    }
+
+    #[test]
+    fn price_path_models_emit_chronological_transfers() -> Result<()> {
+        let mut generator = seeded(5);
+        generator.config.price_model = PriceModel::GeometricBrownian {
+            initial: 1.0,
+            drift: 0.0,
+            volatility: 0.5,
+        };
+
+        let transfers = generator.generate(200)?;
+
+        assert!(transfers.windows(2).all(|w| w[0].ts <= w[1].ts));
+        assert_eq!(transfers[0].usd_price, 1.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn balance_respecting_mode_passes_integrity_checks() -> Result<()> {
+        let mut generator = seeded(9);
+        generator.config.address_pool = Some(AddressPoolConfig {
+            size: 50,
+            zipf_exponent: 1.1,
+        });
+        generator.config.balances = Some(BalanceConfig::default());
+
+        let transfers = generator.generate(2_000)?;
+
+        let report = IntegrityChecker::new()
+            .with_issuers([MINT_ADDRESS])
+            .check(&transfers);
+
+        assert!(report.is_clean(), "{}", report);
+        assert_eq!(transfers[0].from, MINT_ADDRESS);
+
+        Ok(())
+    }
+
+    #[test]
+    fn timestamps_are_relative_to_the_injected_clock() -> Result<()> {
+        let generator = seeded(7);
+
+        let transfers = generator.generate(1_000)?;
+
+        let oldest = 1_700_000_000 - generator.config.max_age_secs;
+        assert!(transfers
+            .iter()
+            .all(|t| (oldest..=1_700_000_000).contains(&t.ts)));
+
+        Ok(())
+    }
 }
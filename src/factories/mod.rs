@@ -1,3 +1,8 @@
+pub mod addresses;
+pub mod balances;
 pub mod clickhouse;
 pub mod defaults;
 pub mod generator;
+pub mod prices;
+pub mod scenarios;
+pub mod stream;
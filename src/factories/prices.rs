@@ -0,0 +1,244 @@
+use crate::errors::{Error, Result};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 86_400.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricePoint {
+    pub ts: u64,
+    pub usd_price: f64,
+}
+
+// Drift, volatility and reversion speed are annualized
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceModel {
+    // Independent draw between `min_price` and `max_price` for every transfer
+    #[default]
+    Uniform,
+    GeometricBrownian {
+        initial: f64,
+        drift: f64,
+        volatility: f64,
+    },
+    // Ornstein-Uhlenbeck process on the log price, pulled towards `mean`
+    MeanReverting {
+        initial: f64,
+        mean: f64,
+        reversion_speed: f64,
+        volatility: f64,
+    },
+    // Step function over a recorded series: the last point at or before the transfer wins,
+    // transfers before the first point get the first point's price
+    Replay(Vec<PricePoint>),
+}
+
+pub struct PricePath {
+    model: PriceModel,
+    min_price: f64,
+    max_price: f64,
+    current: f64,
+    last_ts: Option<u64>,
+}
+
+impl PricePath {
+    pub fn new(model: &PriceModel, min_price: f64, max_price: f64) -> Result<Self> {
+        let model = match model {
+            PriceModel::Replay(series) if series.is_empty() => {
+                return Err(Error::InvalidConfiguration(
+                    "Replayed price series must not be empty".to_string(),
+                ))
+            }
+            PriceModel::Replay(series) => {
+                let mut series = series.clone();
+                series.sort_by_key(|p| p.ts);
+                PriceModel::Replay(series)
+            }
+            PriceModel::GeometricBrownian { initial, .. }
+            | PriceModel::MeanReverting { initial, .. }
+                if *initial <= 0.0 =>
+            {
+                return Err(Error::InvalidConfiguration(
+                    "Initial price must be positive".to_string(),
+                ))
+            }
+            PriceModel::MeanReverting { mean, .. } if *mean <= 0.0 => {
+                return Err(Error::InvalidConfiguration(
+                    "Mean price must be positive".to_string(),
+                ))
+            }
+            model => model.clone(),
+        };
+
+        let current = match &model {
+            PriceModel::GeometricBrownian { initial, .. }
+            | PriceModel::MeanReverting { initial, .. } => *initial,
+            _ => 0.0,
+        };
+
+        Ok(PricePath {
+            model,
+            min_price,
+            max_price,
+            current,
+            last_ts: None,
+        })
+    }
+
+    // Path models only make sense when asked for prices in chronological order
+    pub fn is_time_ordered(&self) -> bool {
+        self.model != PriceModel::Uniform
+    }
+
+    pub fn price_at(&mut self, ts: u64, rng: &mut impl Rng) -> f64 {
+        let dt = self
+            .last_ts
+            .map(|last_ts| ts.saturating_sub(last_ts) as f64 / SECONDS_PER_YEAR)
+            .unwrap_or(0.0);
+        self.last_ts = Some(ts);
+
+        match &self.model {
+            PriceModel::Uniform => rng.gen_range(self.min_price..=self.max_price),
+            PriceModel::GeometricBrownian {
+                drift, volatility, ..
+            } => {
+                if dt > 0.0 {
+                    let z: f64 = rng.sample(StandardNormal);
+                    self.current *= ((drift - volatility * volatility / 2.0) * dt
+                        + volatility * dt.sqrt() * z)
+                        .exp();
+                }
+                self.current
+            }
+            PriceModel::MeanReverting {
+                mean,
+                reversion_speed,
+                volatility,
+                ..
+            } => {
+                if dt > 0.0 {
+                    let z: f64 = rng.sample(StandardNormal);
+                    let log_price = self.current.ln();
+                    let log_price = log_price
+                        + reversion_speed * (mean.ln() - log_price) * dt
+                        + volatility * dt.sqrt() * z;
+                    self.current = log_price.exp();
+                }
+                self.current
+            }
+            PriceModel::Replay(series) => {
+                let idx = series.partition_point(|p| p.ts <= ts);
+                series[idx.saturating_sub(1)].usd_price
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn uniform_prices_stay_within_bounds() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut path = PricePath::new(&PriceModel::Uniform, 0.5, 1.5)?;
+
+        assert!(!path.is_time_ordered());
+        assert!((0..1_000)
+            .map(|ts| path.price_at(ts, &mut rng))
+            .all(|p| (0.5..=1.5).contains(&p)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn brownian_path_starts_at_initial_price_and_stays_positive() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(2);
+        let model = PriceModel::GeometricBrownian {
+            initial: 2.0,
+            drift: 0.1,
+            volatility: 0.8,
+        };
+        let mut path = PricePath::new(&model, 0.0, 0.0)?;
+
+        assert_eq!(path.price_at(1_000, &mut rng), 2.0);
+        assert_eq!(
+            path.price_at(1_000, &mut rng),
+            2.0,
+            "No time passed, no move"
+        );
+
+        let prices: Vec<f64> = (1..=1_000)
+            .map(|i| path.price_at(1_000 + i * 3_600, &mut rng))
+            .collect();
+
+        assert!(prices.iter().all(|&p| p > 0.0));
+        assert!(prices.windows(2).any(|w| w[0] != w[1]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn mean_reverting_path_is_pulled_towards_mean() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(3);
+        let model = PriceModel::MeanReverting {
+            initial: 10.0,
+            mean: 1.0,
+            reversion_speed: 50.0,
+            volatility: 0.1,
+        };
+        let mut path = PricePath::new(&model, 0.0, 0.0)?;
+
+        path.price_at(0, &mut rng);
+        let later = (1..=365)
+            .map(|day| path.price_at(day * 86_400, &mut rng))
+            .last()
+            .unwrap_or_default();
+
+        assert!((later - 1.0).abs() < 0.5, "price after a year: {}", later);
+
+        Ok(())
+    }
+
+    #[test]
+    fn replays_recorded_series() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(4);
+        let model = PriceModel::Replay(vec![
+            PricePoint {
+                ts: 200,
+                usd_price: 2.0,
+            },
+            PricePoint {
+                ts: 100,
+                usd_price: 1.0,
+            },
+        ]);
+        let mut path = PricePath::new(&model, 0.0, 0.0)?;
+
+        assert_eq!(path.price_at(50, &mut rng), 1.0);
+        assert_eq!(path.price_at(150, &mut rng), 1.0);
+        assert_eq!(path.price_at(200, &mut rng), 2.0);
+        assert_eq!(path.price_at(10_000, &mut rng), 2.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_models() {
+        assert!(PricePath::new(&PriceModel::Replay(vec![]), 0.0, 0.0).is_err());
+        assert!(PricePath::new(
+            &PriceModel::GeometricBrownian {
+                initial: 0.0,
+                drift: 0.0,
+                volatility: 0.1,
+            },
+            0.0,
+            0.0
+        )
+        .is_err());
+    }
+}
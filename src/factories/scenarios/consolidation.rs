@@ -0,0 +1,107 @@
+use rand::Rng;
+
+use super::{Scenario, ScenarioSettings};
+use crate::errors::Result;
+use crate::factories::{addresses::rand_address, generator::TransferGenerator};
+use crate::models::transfer::Transfer;
+
+// Exchange deposit addresses feeding a hot wallet that periodically sweeps into cold storage
+pub struct HotWalletConsolidation {
+    pub settings: ScenarioSettings,
+    pub deposits_per_sweep: usize,
+    pub min_deposit: f64,
+    pub max_deposit: f64,
+}
+
+impl Default for HotWalletConsolidation {
+    fn default() -> Self {
+        Self {
+            settings: ScenarioSettings::default(),
+            deposits_per_sweep: 10,
+            min_deposit: 10.0,
+            max_deposit: 1_000.0,
+        }
+    }
+}
+
+impl TransferGenerator for HotWalletConsolidation {
+    fn generate(&self, count: usize) -> Result<Vec<Transfer>> {
+        let mut rng = self.settings.rng();
+        let hot_wallet = rand_address(&mut rng);
+        let cold_wallet = rand_address(&mut rng);
+        let sweep_every = self.deposits_per_sweep.max(1) + 1;
+
+        let mut pending = 0.0;
+        let mut transfers = Vec::with_capacity(count);
+
+        for (i, ts) in self.settings.timeline(count)?.into_iter().enumerate() {
+            let (from, to, amount) = if (i + 1) % sweep_every == 0 {
+                (
+                    hot_wallet.clone(),
+                    cold_wallet.clone(),
+                    std::mem::take(&mut pending),
+                )
+            } else {
+                let amount = rng.gen_range(self.min_deposit..=self.max_deposit);
+                pending += amount;
+                (rand_address(&mut rng), hot_wallet.clone(), amount)
+            };
+
+            transfers.push(Transfer {
+                ts,
+                from,
+                to,
+                amount,
+                usd_price: self.settings.usd_price,
+            });
+        }
+
+        Ok(transfers)
+    }
+}
+
+impl Scenario for HotWalletConsolidation {
+    fn name(&self) -> &'static str {
+        "hot_wallet_consolidation"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::integrity::IntegrityChecker;
+
+    #[test]
+    fn sweeps_exactly_what_was_deposited() -> Result<()> {
+        let scenario = HotWalletConsolidation {
+            settings: ScenarioSettings {
+                seed: Some(1),
+                ..Default::default()
+            },
+            deposits_per_sweep: 4,
+            ..Default::default()
+        };
+
+        let transfers = scenario.generate(25)?;
+        let hot_wallet = &transfers[0].to;
+        let sweeps: Vec<&Transfer> = transfers.iter().filter(|t| t.from == *hot_wallet).collect();
+
+        assert_eq!(sweeps.len(), 5);
+        assert!(sweeps.iter().all(|t| t.to == sweeps[0].to));
+
+        let deposited: f64 = transfers[..4].iter().map(|t| t.amount).sum();
+        assert_eq!(sweeps[0].amount, deposited);
+
+        let report = IntegrityChecker::new()
+            .with_issuers(
+                transfers
+                    .iter()
+                    .filter(|t| t.to == *hot_wallet)
+                    .map(|t| t.from.clone()),
+            )
+            .check(&transfers);
+        assert_eq!(report.negative_balances(), 0, "Hot wallet never overspends");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use rand::Rng;
+
+use super::{Scenario, ScenarioSettings};
+use crate::errors::Result;
+use crate::factories::{addresses::rand_address, generator::TransferGenerator};
+use crate::models::transfer::Transfer;
+
+// One attacker sprays tiny amounts over many addresses to trace or phish them later
+pub struct DustAttack {
+    pub settings: ScenarioSettings,
+    pub dust_amount: f64,
+}
+
+impl Default for DustAttack {
+    fn default() -> Self {
+        Self {
+            settings: ScenarioSettings::default(),
+            dust_amount: 0.000_001,
+        }
+    }
+}
+
+impl TransferGenerator for DustAttack {
+    fn generate(&self, count: usize) -> Result<Vec<Transfer>> {
+        let mut rng = self.settings.rng();
+        let attacker = rand_address(&mut rng);
+
+        let transfers = self
+            .settings
+            .timeline(count)?
+            .into_iter()
+            .map(|ts| Transfer {
+                ts,
+                from: attacker.clone(),
+                to: rand_address(&mut rng),
+                amount: self.dust_amount * rng.gen_range(1.0..=2.0),
+                usd_price: self.settings.usd_price,
+            })
+            .collect();
+
+        Ok(transfers)
+    }
+}
+
+impl Scenario for DustAttack {
+    fn name(&self) -> &'static str {
+        "dust_attack"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn sprays_dust_over_distinct_victims() -> Result<()> {
+        let scenario = DustAttack {
+            settings: ScenarioSettings {
+                seed: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let transfers = scenario.generate(50)?;
+
+        let victims: HashSet<&str> = transfers.iter().map(|t| t.to.as_str()).collect();
+        assert_eq!(victims.len(), 50);
+        assert!(transfers.iter().all(|t| t.from == transfers[0].from));
+        assert!(transfers.iter().all(|t| t.amount <= 0.000_002));
+
+        Ok(())
+    }
+}
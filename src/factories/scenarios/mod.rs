@@ -0,0 +1,236 @@
+use std::sync::Arc;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::generator::TransferGenerator;
+use crate::{
+    errors::{Error, Result},
+    models::transfer::Transfer,
+    utils::time::{Now, SystemNow},
+};
+
+pub mod consolidation;
+pub mod dust;
+pub mod pump_and_dump;
+pub mod sybil;
+pub mod wash_trading;
+
+// Every transfer a scenario generates is part of the pattern it simulates
+pub trait Scenario: TransferGenerator {
+    fn name(&self) -> &'static str;
+}
+
+// `instance` tells apart several scenarios of the same kind mixed into one dataset
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScenarioLabel {
+    pub scenario: &'static str,
+    pub instance: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledTransfer {
+    pub transfer: Transfer,
+    // `None` for background traffic
+    pub label: Option<ScenarioLabel>,
+}
+
+#[derive(Clone)]
+pub struct ScenarioSettings {
+    pub seed: Option<u64>,
+    pub clock: Arc<dyn Now>,
+    // The scenario plays out over the window ending at the clock's now
+    pub window_secs: u64,
+    pub usd_price: f64,
+}
+
+impl Default for ScenarioSettings {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            clock: Arc::new(SystemNow),
+            window_secs: 86_400,
+            usd_price: 1.0,
+        }
+    }
+}
+
+impl ScenarioSettings {
+    pub fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        }
+    }
+
+    // Evenly spaced, non-decreasing timestamps covering the window
+    pub fn timeline(&self, count: usize) -> Result<Vec<u64>> {
+        let now = self.clock.now_unix()?;
+        let start = now.saturating_sub(self.window_secs);
+        let step = self.window_secs / count.max(1) as u64;
+
+        Ok((0..count as u64).map(|i| start + i * step).collect())
+    }
+}
+
+// Background traffic with scenarios mixed in, each contributing a fixed number of transfers
+pub struct ScenarioMix {
+    background: Box<dyn TransferGenerator>,
+    scenarios: Vec<(Box<dyn Scenario>, usize)>,
+}
+
+impl ScenarioMix {
+    pub fn new(background: Box<dyn TransferGenerator>) -> Self {
+        ScenarioMix {
+            background,
+            scenarios: vec![],
+        }
+    }
+
+    pub fn with_scenario(mut self, scenario: impl Scenario + 'static, count: usize) -> Self {
+        self.scenarios.push((Box::new(scenario), count));
+        self
+    }
+
+    // `count` is the total, background traffic fills whatever the scenarios leave.
+    // The result is chronological so it can be fed straight into stats and detectors
+    pub fn generate_labeled(&self, count: usize) -> Result<Vec<LabeledTransfer>> {
+        let scenario_count: usize = self.scenarios.iter().map(|(_, count)| count).sum();
+        if scenario_count > count {
+            return Err(Error::InvalidConfiguration(format!(
+                "Scenarios need {} transfers, more than the {} requested",
+                scenario_count, count
+            )));
+        }
+
+        let mut transfers: Vec<LabeledTransfer> = self
+            .background
+            .generate(count.saturating_sub(scenario_count))?
+            .into_iter()
+            .map(|transfer| LabeledTransfer {
+                transfer,
+                label: None,
+            })
+            .collect();
+
+        for (instance, (scenario, count)) in self.scenarios.iter().enumerate() {
+            let label = ScenarioLabel {
+                scenario: scenario.name(),
+                instance,
+            };
+
+            transfers.extend(scenario.generate(*count)?.into_iter().map(|transfer| {
+                LabeledTransfer {
+                    transfer,
+                    label: Some(label.clone()),
+                }
+            }));
+        }
+
+        transfers.sort_by_key(|t| t.transfer.ts);
+
+        Ok(transfers)
+    }
+}
+
+impl TransferGenerator for ScenarioMix {
+    fn generate(&self, count: usize) -> Result<Vec<Transfer>> {
+        Ok(self
+            .generate_labeled(count)?
+            .into_iter()
+            .map(|t| t.transfer)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::factories::defaults::generator;
+    use crate::utils::time::FixedClock;
+
+    use super::{dust::DustAttack, wash_trading::WashTrading};
+
+    fn settings() -> ScenarioSettings {
+        ScenarioSettings {
+            seed: Some(1),
+            clock: Arc::new(FixedClock(1_700_000_000)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mixes_background_traffic_with_labeled_scenarios() -> Result<()> {
+        let background = generator()
+            .with_seed(1)
+            .with_clock(FixedClock(1_700_000_000))
+            .build();
+
+        let mix = ScenarioMix::new(background)
+            .with_scenario(
+                WashTrading {
+                    settings: settings(),
+                    ..Default::default()
+                },
+                12,
+            )
+            .with_scenario(
+                DustAttack {
+                    settings: settings(),
+                    ..Default::default()
+                },
+                30,
+            );
+
+        let transfers = mix.generate_labeled(100)?;
+
+        assert_eq!(transfers.len(), 100);
+        assert!(transfers
+            .windows(2)
+            .all(|w| w[0].transfer.ts <= w[1].transfer.ts));
+
+        let labeled = |scenario: &str| {
+            transfers
+                .iter()
+                .filter(|t| t.label.as_ref().is_some_and(|l| l.scenario == scenario))
+                .count()
+        };
+        assert_eq!(labeled("wash_trading"), 12);
+        assert_eq!(labeled("dust_attack"), 30);
+        assert_eq!(transfers.iter().filter(|t| t.label.is_none()).count(), 58);
+
+        Ok(())
+    }
+
+    #[test]
+    fn scenarios_must_fit_into_the_total() {
+        let mix = ScenarioMix::new(generator().build()).with_scenario(
+            WashTrading {
+                settings: settings(),
+                ..Default::default()
+            },
+            12,
+        );
+
+        assert!(matches!(
+            mix.generate_labeled(10),
+            Err(Error::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn timeline_spans_the_window() -> Result<()> {
+        let timeline = settings().timeline(4)?;
+
+        assert_eq!(
+            timeline,
+            vec![
+                1_700_000_000 - 86_400,
+                1_700_000_000 - 64_800,
+                1_700_000_000 - 43_200,
+                1_700_000_000 - 21_600
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use rand::Rng;
+
+use super::{Scenario, ScenarioSettings};
+use crate::errors::{Error, Result};
+use crate::factories::{addresses::rand_address, generator::TransferGenerator};
+use crate::models::transfer::Transfer;
+
+// An organizer quietly accumulates from many sellers, pumps the price and dumps on many buyers
+pub struct PumpAndDump {
+    pub settings: ScenarioSettings,
+    // Fraction of the transfers spent accumulating, the rest is the dump. Above zero, there is
+    // nothing to dump otherwise
+    pub accumulation_share: f64,
+    // Peak price relative to `settings.usd_price`
+    pub pump_multiplier: f64,
+    pub amount: f64,
+}
+
+impl Default for PumpAndDump {
+    fn default() -> Self {
+        Self {
+            settings: ScenarioSettings::default(),
+            accumulation_share: 0.5,
+            pump_multiplier: 5.0,
+            amount: 500.0,
+        }
+    }
+}
+
+impl TransferGenerator for PumpAndDump {
+    fn generate(&self, count: usize) -> Result<Vec<Transfer>> {
+        if !(self.accumulation_share > 0.0 && self.accumulation_share <= 1.0) {
+            return Err(Error::InvalidConfiguration(format!(
+                "Pump and dump accumulation_share must be in (0, 1], got {}",
+                self.accumulation_share
+            )));
+        }
+
+        let mut rng = self.settings.rng();
+        let organizer = rand_address(&mut rng);
+        let base = self.settings.usd_price;
+        let peak = base * self.pump_multiplier;
+
+        // At least one purchase, however few transfers the share rounds to
+        let accumulating = ((count as f64 * self.accumulation_share).round() as usize)
+            .max(1)
+            .min(count);
+        let dumping = count - accumulating;
+
+        let mut accumulated = 0.0;
+        let mut transfers = Vec::with_capacity(count);
+
+        for (i, ts) in self.settings.timeline(count)?.into_iter().enumerate() {
+            if i < accumulating {
+                let amount = self.amount * rng.gen_range(0.5..=1.5);
+                accumulated += amount;
+
+                // Price creeps up while the organizer is buying
+                let progress = i as f64 / accumulating as f64;
+                transfers.push(Transfer {
+                    ts,
+                    from: rand_address(&mut rng),
+                    to: organizer.clone(),
+                    amount,
+                    usd_price: base * (1.0 + 0.1 * progress),
+                });
+            } else {
+                // The whole position is sold off while the price decays from the peak
+                let progress = (i - accumulating) as f64 / dumping as f64;
+                transfers.push(Transfer {
+                    ts,
+                    from: organizer.clone(),
+                    to: rand_address(&mut rng),
+                    amount: accumulated / dumping as f64,
+                    usd_price: peak - (peak - base) * progress,
+                });
+            }
+        }
+
+        Ok(transfers)
+    }
+}
+
+impl Scenario for PumpAndDump {
+    fn name(&self) -> &'static str {
+        "pump_and_dump"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::services::stats::calculator::{CalculatesStats, StatsCalculator};
+
+    #[test]
+    fn organizer_buys_low_and_sells_everything_high() -> anyhow::Result<()> {
+        let scenario = PumpAndDump {
+            settings: ScenarioSettings {
+                seed: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let transfers = scenario.generate(100)?;
+        let organizer = &transfers[0].to;
+
+        let stats = StatsCalculator::new().calculate_user_stats(&transfers);
+        let organizer_stats = stats
+            .iter()
+            .find(|s| s.address == *organizer)
+            .ok_or_else(|| anyhow!("Organizer is not found in stats"))?;
+
+        assert!(organizer_stats.avg_sell_price > 2.0 * organizer_stats.avg_buy_price);
+        assert_eq!(
+            transfers.iter().filter(|t| t.from == *organizer).count(),
+            50
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn always_has_something_to_dump() -> anyhow::Result<()> {
+        let scenario = PumpAndDump {
+            accumulation_share: 0.1,
+            ..Default::default()
+        };
+
+        let transfers = scenario.generate(3)?;
+
+        assert!(transfers.iter().all(|t| t.amount > 0.0));
+
+        for accumulation_share in [0.0, -0.5, 1.5, f64::NAN] {
+            let scenario = PumpAndDump {
+                accumulation_share,
+                ..Default::default()
+            };
+            assert!(matches!(
+                scenario.generate(10),
+                Err(Error::InvalidConfiguration(_))
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use super::{Scenario, ScenarioSettings};
+use crate::errors::Result;
+use crate::factories::{addresses::rand_address, generator::TransferGenerator};
+use crate::models::transfer::Transfer;
+
+// Airdrop farming: a funder fans out to many sybil wallets which later funnel into one collector
+pub struct SybilFanOut {
+    pub settings: ScenarioSettings,
+    pub amount: f64,
+}
+
+impl Default for SybilFanOut {
+    fn default() -> Self {
+        Self {
+            settings: ScenarioSettings::default(),
+            amount: 10.0,
+        }
+    }
+}
+
+impl TransferGenerator for SybilFanOut {
+    fn generate(&self, count: usize) -> Result<Vec<Transfer>> {
+        let mut rng = self.settings.rng();
+        let funder = rand_address(&mut rng);
+        let collector = rand_address(&mut rng);
+
+        let fan_out = count.div_ceil(2);
+        let sybils: Vec<String> = (0..fan_out).map(|_| rand_address(&mut rng)).collect();
+
+        let transfers = self
+            .settings
+            .timeline(count)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, ts)| {
+                let (from, to) = if i < fan_out {
+                    (funder.clone(), sybils[i].clone())
+                } else {
+                    (sybils[i - fan_out].clone(), collector.clone())
+                };
+
+                Transfer {
+                    ts,
+                    from,
+                    to,
+                    amount: self.amount,
+                    usd_price: self.settings.usd_price,
+                }
+            })
+            .collect();
+
+        Ok(transfers)
+    }
+}
+
+impl Scenario for SybilFanOut {
+    fn name(&self) -> &'static str {
+        "sybil_fan_out"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn funds_sybils_and_collects_from_them() -> Result<()> {
+        let scenario = SybilFanOut {
+            settings: ScenarioSettings {
+                seed: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let transfers = scenario.generate(20)?;
+        let (fan_out, fan_in) = transfers.split_at(10);
+
+        let funded: HashSet<&str> = fan_out.iter().map(|t| t.to.as_str()).collect();
+        let collected: HashSet<&str> = fan_in.iter().map(|t| t.from.as_str()).collect();
+
+        assert_eq!(funded.len(), 10);
+        assert_eq!(funded, collected);
+        assert!(fan_out.iter().all(|t| t.from == fan_out[0].from));
+        assert!(fan_in.iter().all(|t| t.to == fan_in[0].to));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use rand::Rng;
+
+use super::{Scenario, ScenarioSettings};
+use crate::errors::{Error, Result};
+use crate::factories::{addresses::rand_address, generator::TransferGenerator};
+use crate::models::transfer::Transfer;
+
+// A small ring of colluding addresses passing roughly the same amount around to fake volume
+pub struct WashTrading {
+    pub settings: ScenarioSettings,
+    pub ring_size: usize,
+    pub amount: f64,
+    // Relative noise added to the amount so the loop is not trivially identical, in [0, 1)
+    // so that amounts stay positive
+    pub amount_jitter: f64,
+}
+
+impl Default for WashTrading {
+    fn default() -> Self {
+        Self {
+            settings: ScenarioSettings::default(),
+            ring_size: 3,
+            amount: 1_000.0,
+            amount_jitter: 0.01,
+        }
+    }
+}
+
+impl TransferGenerator for WashTrading {
+    fn generate(&self, count: usize) -> Result<Vec<Transfer>> {
+        if !(0.0..1.0).contains(&self.amount_jitter) {
+            return Err(Error::InvalidConfiguration(format!(
+                "Wash trading amount_jitter must be in [0, 1), got {}",
+                self.amount_jitter
+            )));
+        }
+
+        let mut rng = self.settings.rng();
+        let ring: Vec<String> = (0..self.ring_size.max(2))
+            .map(|_| rand_address(&mut rng))
+            .collect();
+
+        let transfers = self
+            .settings
+            .timeline(count)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, ts)| {
+                let jitter = rng.gen_range(-self.amount_jitter..=self.amount_jitter);
+
+                Transfer {
+                    ts,
+                    from: ring[i % ring.len()].clone(),
+                    to: ring[(i + 1) % ring.len()].clone(),
+                    amount: self.amount * (1.0 + jitter),
+                    usd_price: self.settings.usd_price,
+                }
+            })
+            .collect();
+
+        Ok(transfers)
+    }
+}
+
+impl Scenario for WashTrading {
+    fn name(&self) -> &'static str {
+        "wash_trading"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn volume_circulates_inside_the_ring() -> Result<()> {
+        let scenario = WashTrading {
+            settings: ScenarioSettings {
+                seed: Some(1),
+                ..Default::default()
+            },
+            ring_size: 4,
+            ..Default::default()
+        };
+
+        let transfers = scenario.generate(40)?;
+
+        let senders: HashSet<&str> = transfers.iter().map(|t| t.from.as_str()).collect();
+        let receivers: HashSet<&str> = transfers.iter().map(|t| t.to.as_str()).collect();
+
+        assert_eq!(senders.len(), 4);
+        assert_eq!(senders, receivers, "Nothing leaves the ring");
+        assert!(transfers
+            .iter()
+            .all(|t| (990.0..=1_010.0).contains(&t.amount)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_jitter_that_could_make_amounts_non_positive() {
+        for amount_jitter in [-0.1, 1.0, f64::NAN] {
+            let scenario = WashTrading {
+                amount_jitter,
+                ..Default::default()
+            };
+
+            assert!(matches!(
+                scenario.generate(10),
+                Err(Error::InvalidConfiguration(_))
+            ));
+        }
+    }
+}
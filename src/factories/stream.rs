@@ -0,0 +1,204 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::{stream, Stream};
+use rand::Rng;
+use rand_distr::Exp;
+use tokio::time::{self, MissedTickBehavior};
+
+use super::generator::{DefaultTransferGenerator, GenerationState};
+use crate::{
+    errors::{Error, Result},
+    models::transfer::Transfer,
+    utils::time::Now,
+};
+
+pub trait StreamsTransfers {
+    fn stream(&self) -> Result<TransferStream>;
+}
+
+impl StreamsTransfers for DefaultTransferGenerator {
+    fn stream(&self) -> Result<TransferStream> {
+        TransferStream::new(
+            GenerationState::new(&self.config)?,
+            self.clock.clone(),
+            self.config.stream_rate,
+        )
+    }
+}
+
+// Unbounded, lazily generated transfers with monotonically increasing timestamps starting at the
+// clock's now. Timestamps advance as if transfers arrived at `rate` per second on average
+pub struct TransferStream {
+    state: GenerationState,
+    clock: Arc<dyn Now>,
+    start: u64,
+    elapsed_secs: f64,
+    inter_arrival: Exp<f64>,
+}
+
+impl TransferStream {
+    pub fn new(state: GenerationState, clock: Arc<dyn Now>, rate: f64) -> Result<Self> {
+        if !(rate > 0.0 && rate.is_finite()) {
+            return Err(Error::InvalidConfiguration(format!(
+                "Stream rate must be positive, got {}",
+                rate
+            )));
+        }
+
+        let inter_arrival = Exp::new(rate).map_err(|e| {
+            Error::InvalidConfiguration(format!("Invalid stream rate {}: {}", rate, e))
+        })?;
+
+        Ok(TransferStream {
+            start: clock.now_unix()?,
+            state,
+            clock,
+            elapsed_secs: 0.0,
+            inter_arrival,
+        })
+    }
+
+    // Timestamps advance from `ts` instead of the clock's now, e.g. to fill a past window
+    pub fn starting_at(mut self, ts: u64) -> Self {
+        self.start = ts;
+        self
+    }
+
+    // Emits `rate` transfers per second of wall time, stamped with the clock's now
+    pub fn paced(self, rate: f64) -> Result<impl Stream<Item = Result<Transfer>> + Send> {
+        if !(rate > 0.0 && rate.is_finite()) {
+            return Err(Error::InvalidConfiguration(format!(
+                "Pacing rate must be positive, got {}",
+                rate
+            )));
+        }
+
+        // Rates above a billion per second would round the period to zero, which `interval` rejects
+        let period = Duration::from_secs_f64(1.0 / rate).max(Duration::from_nanos(1));
+        let mut interval = time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        Ok(stream::unfold(
+            (self, interval, 0u64),
+            |(mut transfers, mut interval, last_ts)| async move {
+                interval.tick().await;
+
+                let ts = match transfers.clock.now_unix() {
+                    Ok(now) => now.max(last_ts),
+                    Err(e) => return Some((Err(e), (transfers, interval, last_ts))),
+                };
+
+                let transfer = transfers.state.transfer_at(ts);
+
+                Some((Ok(transfer), (transfers, interval, ts)))
+            },
+        ))
+    }
+}
+
+impl Iterator for TransferStream {
+    type Item = Transfer;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let gap: f64 = self.state.rng().sample(self.inter_arrival);
+        self.elapsed_secs += gap;
+
+        let ts = self.start + self.elapsed_secs as u64;
+
+        Some(self.state.transfer_at(ts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::factories::generator::TransferGenConfig;
+    use crate::factories::prices::PriceModel;
+    use crate::utils::time::FixedClock;
+
+    fn generator(rate: f64) -> DefaultTransferGenerator {
+        DefaultTransferGenerator {
+            config: TransferGenConfig {
+                seed: Some(3),
+                stream_rate: rate,
+                price_model: PriceModel::GeometricBrownian {
+                    initial: 1.0,
+                    drift: 0.0,
+                    volatility: 0.5,
+                },
+                ..Default::default()
+            },
+            clock: Arc::new(FixedClock(1_700_000_000)),
+        }
+    }
+
+    #[test]
+    fn streams_lazily_with_monotonic_timestamps() -> Result<()> {
+        let transfers: Vec<Transfer> = generator(2.0).stream()?.take(10_000).collect();
+
+        assert_eq!(transfers.len(), 10_000);
+        assert_eq!(transfers[0].usd_price, 1.0);
+        assert!(transfers.windows(2).all(|w| w[0].ts <= w[1].ts));
+
+        let span = transfers[9_999].ts - 1_700_000_000;
+        assert!(
+            (4_500..=5_500).contains(&span),
+            "10k transfers at 2/s should span ~5000s, got {}",
+            span
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn seeded_streams_are_reproducible() -> Result<()> {
+        let first: Vec<Transfer> = generator(1.0).stream()?.take(100).collect();
+        let second: Vec<Transfer> = generator(1.0).stream()?.take(100).collect();
+
+        assert_eq!(first, second);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_non_positive_rates() {
+        assert!(generator(0.0).stream().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paced_stream_emits_at_target_rate() -> Result<()> {
+        let paced = generator(1.0).stream()?.paced(100.0)?;
+
+        let started = time::Instant::now();
+        let transfers: Vec<Transfer> = paced
+            .take(50)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+
+        assert_eq!(transfers.len(), 50);
+        assert!(transfers.iter().all(|t| t.ts == 1_700_000_000));
+        assert!(started.elapsed() >= Duration::from_millis(490));
+
+        Ok(())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn paces_rates_too_high_for_the_timer_at_its_resolution() -> Result<()> {
+        let paced = generator(1.0).stream()?.paced(1e12)?;
+
+        let transfers: Vec<Transfer> = paced
+            .take(10)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+
+        assert_eq!(transfers.len(), 10);
+
+        Ok(())
+    }
+}
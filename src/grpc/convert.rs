@@ -0,0 +1,99 @@
+use tonic::Status;
+use tracing::error;
+
+use super::proto;
+use crate::{
+    errors::Error,
+    models::{
+        transfer::{Transfer, TransferFilter},
+        user_stats::UserStats,
+    },
+    services::stats::ranking::RankBy,
+};
+
+impl From<proto::Transfer> for Transfer {
+    fn from(transfer: proto::Transfer) -> Self {
+        Transfer {
+            ts: transfer.ts,
+            from: transfer.from,
+            to: transfer.to,
+            amount: transfer.amount,
+            usd_price: transfer.usd_price,
+        }
+    }
+}
+
+impl From<Transfer> for proto::Transfer {
+    fn from(transfer: Transfer) -> Self {
+        proto::Transfer {
+            ts: transfer.ts,
+            from: transfer.from,
+            to: transfer.to,
+            amount: transfer.amount,
+            usd_price: transfer.usd_price,
+        }
+    }
+}
+
+impl From<UserStats> for proto::UserStats {
+    fn from(stats: UserStats) -> Self {
+        proto::UserStats {
+            address: stats.address,
+            total_volume: stats.total_volume,
+            avg_buy_price: stats.avg_buy_price,
+            avg_sell_price: stats.avg_sell_price,
+            max_balance: stats.max_balance,
+            pnl: stats.pnl,
+        }
+    }
+}
+
+impl From<proto::UserStats> for UserStats {
+    fn from(stats: proto::UserStats) -> Self {
+        UserStats {
+            address: stats.address,
+            total_volume: stats.total_volume,
+            avg_buy_price: stats.avg_buy_price,
+            avg_sell_price: stats.avg_sell_price,
+            max_balance: stats.max_balance,
+            pnl: stats.pnl,
+        }
+    }
+}
+
+impl From<proto::RankBy> for RankBy {
+    fn from(by: proto::RankBy) -> Self {
+        match by {
+            proto::RankBy::Volume => RankBy::Volume,
+            proto::RankBy::MaxBalance => RankBy::MaxBalance,
+            proto::RankBy::Pnl => RankBy::Pnl,
+            proto::RankBy::AvgBuyPrice => RankBy::AvgBuyPrice,
+            proto::RankBy::AvgSellPrice => RankBy::AvgSellPrice,
+        }
+    }
+}
+
+pub fn filter(address: Option<String>, range: Option<proto::TimeRange>) -> TransferFilter {
+    let range = range.unwrap_or_default();
+
+    TransferFilter {
+        address,
+        from_ts: range.from_ts,
+        to_ts: range.to_ts,
+    }
+}
+
+// Same split as the HTTP API: validation reaches the client verbatim, the rest only by kind
+pub fn status(error: Error) -> Status {
+    match &error {
+        Error::ValidationFailed(_) => Status::invalid_argument(error.to_string()),
+        Error::StorageUnavailable { .. } => {
+            error!(error = %error, kind = error.kind(), "rpc failed");
+            Status::unavailable(error.kind())
+        }
+        _ => {
+            error!(error = %error, kind = error.kind(), "rpc failed");
+            Status::internal(error.kind())
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use futures::{stream, Stream, StreamExt};
+use tonic::{transport::Server, Request, Response, Status, Streaming};
+use tracing::info;
+
+use crate::{
+    errors::{Error, Result},
+    metrics::metrics,
+    models::transfer::Transfer,
+    repositories::{shared::SharedStorage, storage::Storage},
+    services::{
+        analytics::Analytics,
+        ingest::validate_transfers,
+        live::LiveFeed,
+        stats::{
+            calculator::CalculatesStats,
+            ranking::{Direction, Ranking},
+        },
+    },
+    utils::shutdown::shutdown_signal,
+};
+
+pub mod convert;
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("token_analytics.v1");
+}
+
+use convert::{filter, status};
+use proto::analytics_server::{self, AnalyticsServer};
+
+const DEFAULT_BATCH_SIZE: usize = 10_000;
+
+pub struct GrpcService<S: Storage, C> {
+    storage: SharedStorage<S>,
+    calculator: Arc<C>,
+    feed: Arc<LiveFeed>,
+    batch_size: usize,
+}
+
+impl<S: Storage, C> GrpcService<S, C> {
+    pub fn new(storage: S, calculator: C) -> Self {
+        Self::shared(SharedStorage::new(storage), Arc::new(calculator))
+    }
+
+    // Serves storage and calculator that other servers use as well
+    pub fn shared(storage: SharedStorage<S>, calculator: Arc<C>) -> Self {
+        GrpcService {
+            storage,
+            calculator,
+            feed: Arc::default(),
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    // Live subscribers see transfers ingested through this feed, by any server sharing it
+    pub fn with_feed(mut self, feed: Arc<LiveFeed>) -> Self {
+        self.feed = feed;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+}
+
+impl<S, C> GrpcService<S, C>
+where
+    S: Storage + Send + Sync + 'static,
+    C: CalculatesStats + Send + Sync + 'static,
+{
+    pub fn into_server(self) -> AnalyticsServer<Self> {
+        AnalyticsServer::new(self)
+    }
+}
+
+pub async fn serve<S, C>(addr: SocketAddr, service: GrpcService<S, C>) -> Result<()>
+where
+    S: Storage + Send + Sync + 'static,
+    C: CalculatesStats + Send + Sync + 'static,
+{
+    info!(%addr, "serving the gRPC API");
+
+    Server::builder()
+        .add_service(service.into_server())
+        .serve_with_shutdown(addr, shutdown_signal())
+        .await
+        .map_err(|e| Error::io("gRPC server failed", std::io::Error::other(e)))
+}
+
+type StatsStream = Pin<Box<dyn Stream<Item = Result<proto::UserStats, Status>> + Send>>;
+
+// tonic dictates `Status` as the error type, however large
+#[allow(clippy::result_large_err)]
+#[tonic::async_trait]
+impl<S, C> analytics_server::Analytics for GrpcService<S, C>
+where
+    S: Storage + Send + Sync + 'static,
+    C: CalculatesStats + Send + Sync + 'static,
+{
+    async fn ingest_transfers(
+        &self,
+        request: Request<Streaming<proto::Transfer>>,
+    ) -> Result<Response<proto::IngestSummary>, Status> {
+        let mut batches = std::pin::pin!(request.into_inner().chunks(self.batch_size));
+        let mut ingested = 0;
+
+        // Earlier batches stay stored when a later one fails
+        while let Some(batch) = batches.next().await {
+            let batch = batch
+                .into_iter()
+                .map(|transfer| transfer.map(Transfer::from))
+                .collect::<Result<Vec<Transfer>, Status>>()?;
+
+            validate_transfers(&batch).map_err(status)?;
+
+            let mut storage = self.storage.write().await;
+            self.feed
+                .insert(&mut *storage, &batch)
+                .await
+                .map_err(status)?;
+            drop(storage);
+
+            // Best effort, failing now would have clients retry a stored batch
+            if let Ok(metrics) = metrics() {
+                metrics.transfers_ingested.inc_by(batch.len() as u64);
+            }
+            ingested += batch.len() as u64;
+        }
+
+        Ok(Response::new(proto::IngestSummary { ingested }))
+    }
+
+    async fn get_user_stats(
+        &self,
+        request: Request<proto::GetUserStatsRequest>,
+    ) -> Result<Response<proto::UserStats>, Status> {
+        let request = request.into_inner();
+        let filter = filter(Some(request.address.clone()), request.range);
+
+        Analytics::new(self.storage.clone(), self.calculator.clone())
+            .get_filtered_stats(&filter)
+            .await
+            .map_err(status)?
+            .into_iter()
+            .next()
+            .map(|stats| Response::new(stats.into()))
+            .ok_or_else(|| Status::not_found(format!("No transfers for {}", request.address)))
+    }
+
+    type StreamStatsStream = StatsStream;
+
+    async fn stream_stats(
+        &self,
+        request: Request<proto::StreamStatsRequest>,
+    ) -> Result<Response<Self::StreamStatsStream>, Status> {
+        let request = request.into_inner();
+        let direction = if request.ascending {
+            Direction::Ascending
+        } else {
+            Direction::Descending
+        };
+        let ranking = Ranking::new(request.sort().into(), direction);
+
+        let mut stats = Analytics::new(self.storage.clone(), self.calculator.clone())
+            .get_filtered_stats(&filter(None, request.range))
+            .await
+            .map_err(status)?;
+
+        match request.limit {
+            Some(limit) => stats = ranking.top_n(stats, limit as usize),
+            None => ranking.sort(&mut stats),
+        }
+
+        let stream = stream::iter(stats.into_iter().map(|stats| Ok(stats.into())));
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
@@ -1,7 +1,16 @@
+pub mod api;
 pub mod app;
+pub mod chain;
+pub mod cli;
+pub mod config;
+pub mod daemon;
 pub mod errors;
 pub mod factories;
+pub mod grpc;
+pub mod metrics;
 pub mod models;
+pub mod output;
 pub mod repositories;
 pub mod services;
+pub mod sources;
 pub mod utils;
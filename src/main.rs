@@ -1,33 +1,50 @@
+use std::process::ExitCode;
+
+use clap::Parser;
 use dotenv::dotenv;
-use rust_challenge::app::App;
-use rust_challenge::factories::clickhouse::{ClickhouseClientConfig, ClickhouseFactory};
-use rust_challenge::factories::defaults::generator;
-use rust_challenge::services::stats::calculator::StatsCalculator;
+use rust_challenge::cli::{self, commands, Cli};
+use rust_challenge::metrics::server;
+use rust_challenge::utils::telemetry::{self, TelemetryConfig};
+use tracing::error;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> ExitCode {
     dotenv().ok();
 
-    let generator = generator().build();
-
-    let config = ClickhouseClientConfig::from_env()?;
-    let storage = ClickhouseFactory::storage(config).await?;
-
-    // let mut storage = MockStorage::default();
-
-    let calculator = StatsCalculator::new();
-
-    let app = App {
-        storage,
-        calculator,
-        generator,
-    };
-
-    let stats = app.run(20).await?;
-
-    for stat in stats.iter().take(10) {
-        println!("{:?}", stat);
+    let cli = Cli::parse();
+
+    let res = async {
+        telemetry::init(&TelemetryConfig::from_env()?)?;
+
+        if let Ok(addr) = std::env::var("METRICS_ADDR") {
+            let addr = addr.parse().map_err(|e| {
+                rust_challenge::errors::Error::InvalidConfiguration(format!(
+                    "Invalid METRICS_ADDR `{}`: {}",
+                    addr, e
+                ))
+            })?;
+            tokio::spawn(async move {
+                if let Err(e) = server::serve(addr).await {
+                    error!(error = %e, "metrics endpoint stopped");
+                }
+            });
+        }
+
+        commands::run(cli).await
+    }
+    .await;
+
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            let mut source = std::error::Error::source(&e);
+            while let Some(cause) = source {
+                eprintln!("  caused by: {}", cause);
+                source = cause.source();
+            }
+
+            cli::exit_code(&e)
+        }
     }
-
-    Ok(())
 }
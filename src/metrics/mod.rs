@@ -0,0 +1,150 @@
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+
+use crate::errors::{Error, Result};
+
+pub mod server;
+
+const NAMESPACE: &str = "token_analytics";
+
+pub struct Metrics {
+    registry: Registry,
+    pub transfers_ingested: IntCounter,
+    pub insert_duration: HistogramVec,
+    pub query_duration: HistogramVec,
+    pub addresses_tracked: IntGauge,
+    pub calculation_duration: Histogram,
+    pub errors: IntCounterVec,
+}
+
+// Process wide registry, created on first use
+pub fn metrics() -> Result<&'static Metrics> {
+    static METRICS: OnceLock<prometheus::Result<Metrics>> = OnceLock::new();
+
+    METRICS
+        .get_or_init(Metrics::new)
+        .as_ref()
+        .map_err(|e| Error::InvalidConfiguration(format!("Invalid metric definitions: {}", e)))
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None)?;
+
+        let transfers_ingested = IntCounter::new(
+            "transfers_ingested_total",
+            "Transfers written to storage by the app",
+        )?;
+        let insert_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "storage_insert_duration_seconds",
+                "Latency of storage inserts",
+            ),
+            &["table"],
+        )?;
+        let query_duration = HistogramVec::new(
+            HistogramOpts::new("storage_query_duration_seconds", "Latency of storage reads"),
+            &["operation"],
+        )?;
+        let addresses_tracked = IntGauge::new(
+            "addresses_tracked",
+            "Addresses in the most recent stats calculation",
+        )?;
+        let calculation_duration = Histogram::with_opts(HistogramOpts::new(
+            "stats_calculation_duration_seconds",
+            "Time spent calculating user stats",
+        ))?;
+        let errors = IntCounterVec::new(Opts::new("errors_total", "Errors by kind"), &["kind"])?;
+
+        registry.register(Box::new(transfers_ingested.clone()))?;
+        registry.register(Box::new(insert_duration.clone()))?;
+        registry.register(Box::new(query_duration.clone()))?;
+        registry.register(Box::new(addresses_tracked.clone()))?;
+        registry.register(Box::new(calculation_duration.clone()))?;
+        registry.register(Box::new(errors.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            transfers_ingested,
+            insert_duration,
+            query_duration,
+            addresses_tracked,
+            calculation_duration,
+            errors,
+        })
+    }
+
+    pub fn record_error(&self, error: &Error) {
+        self.errors.with_label_values(&[error.kind()]).inc();
+    }
+
+    // Times `call` into the histogram under `label` and counts its failure, if any
+    pub async fn time<T>(
+        &self,
+        histogram: &HistogramVec,
+        label: &str,
+        call: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let started = Instant::now();
+        let res = call.await;
+
+        histogram
+            .with_label_values(&[label])
+            .observe(started.elapsed().as_secs_f64());
+        if let Err(e) = &res {
+            self.record_error(e);
+        }
+
+        res
+    }
+
+    // Prometheus text exposition format
+    pub fn render(&self) -> Result<String> {
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| Error::InvalidConfiguration(format!("Could not encode metrics: {}", e)))?;
+
+        String::from_utf8(buffer)
+            .map_err(|e| Error::InvalidConfiguration(format!("Could not encode metrics: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn times_calls_and_counts_failures() -> Result<()> {
+        let metrics = metrics()?;
+        let histogram = metrics.query_duration.with_label_values(&["metrics_test"]);
+        let errors = metrics.errors.with_label_values(&["validation_failed"]);
+        let errors_before = errors.get();
+
+        metrics
+            .time(&metrics.query_duration, "metrics_test", async { Ok(()) })
+            .await?;
+        let failed = metrics
+            .time(&metrics.query_duration, "metrics_test", async {
+                Err::<(), _>(Error::ValidationFailed("boom".to_string()))
+            })
+            .await;
+
+        assert!(failed.is_err());
+        assert_eq!(histogram.get_sample_count(), 2);
+        assert!(errors.get() > errors_before);
+
+        let rendered = metrics.render()?;
+        assert!(rendered.contains(
+            "token_analytics_storage_query_duration_seconds_count{operation=\"metrics_test\"} 2"
+        ));
+
+        Ok(())
+    }
+}
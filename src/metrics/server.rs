@@ -0,0 +1,61 @@
+use std::net::SocketAddr;
+
+use axum::{http::header, http::StatusCode, response::IntoResponse, routing::get, Router};
+use tracing::info;
+
+use super::{metrics, Metrics};
+use crate::errors::{Error, Result};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+pub fn router() -> Router {
+    Router::new().route("/metrics", get(scrape))
+}
+
+async fn scrape() -> impl IntoResponse {
+    match metrics().and_then(Metrics::render) {
+        Ok(body) => (StatusCode::OK, [(header::CONTENT_TYPE, CONTENT_TYPE)], body).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+// Serves `/metrics` until the process exits
+pub async fn serve(addr: SocketAddr) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| Error::io(format!("Could not bind {}", addr), e))?;
+
+    info!(%addr, "serving metrics");
+
+    axum::serve(listener, router())
+        .await
+        .map_err(|e| Error::io("Metrics server failed", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn exposes_metrics_in_text_format() -> anyhow::Result<()> {
+        metrics()?.transfers_ingested.inc();
+
+        let response = router()
+            .oneshot(Request::get("/metrics").body(Body::empty())?)
+            .await?;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], CONTENT_TYPE);
+
+        let body = response.into_body().collect().await?.to_bytes();
+        let body = String::from_utf8(body.to_vec())?;
+        assert!(body.contains("# TYPE token_analytics_transfers_ingested_total counter"));
+
+        Ok(())
+    }
+}
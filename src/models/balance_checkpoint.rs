@@ -0,0 +1,58 @@
+use std::collections::BTreeSet;
+
+use clickhouse::Row;
+use serde::{Deserialize, Serialize};
+
+use super::transfer::Transfer;
+
+// Balance of `address` after applying every transfer with `ts` up to and including the checkpoint's `ts`
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Row)]
+pub struct BalanceCheckpoint {
+    pub ts: u64,
+    pub address: String,
+    pub balance: f64,
+}
+
+// `transfers` must only contain transfers that happened after the checkpoint
+pub fn balance_since_checkpoint(
+    checkpoint: Option<&BalanceCheckpoint>,
+    address: &str,
+    transfers: &[Transfer],
+) -> f64 {
+    let start = checkpoint.map(|c| c.balance).unwrap_or(0.0);
+
+    transfers.iter().fold(start, |balance, t| {
+        let mut balance = balance;
+        if t.to == address {
+            balance += t.amount;
+        }
+        if t.from == address {
+            balance -= t.amount;
+        }
+        balance
+    })
+}
+
+// Checkpoints a batch of newly stored transfers makes wrong: those of every party to the batch
+// at or after its earliest transfer, which backfilled or late transfers land before
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleCheckpoints {
+    pub from_ts: u64,
+    pub addresses: BTreeSet<String>,
+}
+
+impl StaleCheckpoints {
+    pub fn of(transfers: &[Transfer]) -> Option<Self> {
+        let from_ts = transfers.iter().map(|t| t.ts).min()?;
+        let addresses = transfers
+            .iter()
+            .flat_map(|t| [t.from.clone(), t.to.clone()])
+            .collect();
+
+        Some(StaleCheckpoints { from_ts, addresses })
+    }
+
+    pub fn contains(&self, checkpoint: &BalanceCheckpoint) -> bool {
+        checkpoint.ts >= self.from_ts && self.addresses.contains(&checkpoint.address)
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+// `index` is the position of the offending transfer in the checked sequence
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum IntegrityIssue {
+    NegativeBalance {
+        index: usize,
+        ts: u64,
+        address: String,
+        balance: f64,
+    },
+    NonPositiveAmount {
+        index: usize,
+        amount: f64,
+    },
+    NonPositivePrice {
+        index: usize,
+        usd_price: f64,
+    },
+    SelfTransfer {
+        index: usize,
+        address: String,
+    },
+    DuplicateRow {
+        index: usize,
+        duplicate_of: usize,
+    },
+    TimestampRegression {
+        index: usize,
+        ts: u64,
+        previous_ts: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct IntegrityReport {
+    pub checked: usize,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn negative_balances(&self) -> usize {
+        self.count(|i| matches!(i, IntegrityIssue::NegativeBalance { .. }))
+    }
+
+    pub fn non_positive_amounts(&self) -> usize {
+        self.count(|i| matches!(i, IntegrityIssue::NonPositiveAmount { .. }))
+    }
+
+    pub fn non_positive_prices(&self) -> usize {
+        self.count(|i| matches!(i, IntegrityIssue::NonPositivePrice { .. }))
+    }
+
+    pub fn self_transfers(&self) -> usize {
+        self.count(|i| matches!(i, IntegrityIssue::SelfTransfer { .. }))
+    }
+
+    pub fn duplicate_rows(&self) -> usize {
+        self.count(|i| matches!(i, IntegrityIssue::DuplicateRow { .. }))
+    }
+
+    pub fn timestamp_regressions(&self) -> usize {
+        self.count(|i| matches!(i, IntegrityIssue::TimestampRegression { .. }))
+    }
+
+    fn count(&self, predicate: impl Fn(&IntegrityIssue) -> bool) -> usize {
+        self.issues.iter().filter(|i| predicate(i)).count()
+    }
+}
+
+impl std::fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} issues in {} transfers: {} negative balances, {} non-positive amounts, \
+             {} non-positive prices, {} self-transfers, {} duplicate rows, {} timestamp regressions",
+            self.issues.len(),
+            self.checked,
+            self.negative_balances(),
+            self.non_positive_amounts(),
+            self.non_positive_prices(),
+            self.self_transfers(),
+            self.duplicate_rows(),
+            self.timestamp_regressions(),
+        )
+    }
+}
@@ -1,2 +1,5 @@
+pub mod balance_checkpoint;
+pub mod integrity_report;
+pub mod supply_report;
 pub mod transfer;
 pub mod user_stats;
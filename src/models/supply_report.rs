@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct HolderBalance {
+    pub address: String,
+    pub balance: f64,
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SupplyReport {
+    pub as_of: u64,
+    pub total_supply: f64,
+    pub holders: usize,
+    pub top_holders: Vec<HolderBalance>,
+    pub top_holders_share: f64,
+    pub gini: f64,
+    pub nakamoto_coefficient: usize,
+}
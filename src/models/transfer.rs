@@ -10,9 +10,30 @@ pub struct Transfer {
     pub usd_price: f64,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TransferOrdering {
     Raw,
     Chronological,
     ByVolume,
 }
+
+// Bounds are inclusive, `address` matches either side of a transfer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransferFilter {
+    pub address: Option<String>,
+    pub from_ts: Option<u64>,
+    pub to_ts: Option<u64>,
+}
+
+impl TransferFilter {
+    pub fn matches(&self, transfer: &Transfer) -> bool {
+        let address_matches = self
+            .address
+            .as_ref()
+            .is_none_or(|address| transfer.from == *address || transfer.to == *address);
+
+        address_matches
+            && self.from_ts.is_none_or(|from_ts| transfer.ts >= from_ts)
+            && self.to_ts.is_none_or(|to_ts| transfer.ts <= to_ts)
+    }
+}
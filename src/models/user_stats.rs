@@ -1,10 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct UserStats {
     pub address: String,
     pub total_volume: f64,
     pub avg_buy_price: f64,
     pub avg_sell_price: f64,
     pub max_balance: f64,
+    // Realized against the average buy price
+    pub pnl: f64,
 }
@@ -0,0 +1,33 @@
+use std::io::Write;
+
+use super::{format_float, metrics, write_error, StatsWriter, COLUMNS};
+use crate::{errors::Result, models::user_stats::UserStats};
+
+pub struct CsvWriter<W: Write> {
+    inner: ::csv::Writer<W>,
+    precision: Option<usize>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W, precision: Option<usize>) -> Result<Self> {
+        let mut inner = ::csv::Writer::from_writer(out);
+        inner.write_record(COLUMNS).map_err(write_error)?;
+
+        Ok(CsvWriter { inner, precision })
+    }
+}
+
+impl<W: Write> StatsWriter for CsvWriter<W> {
+    fn write(&mut self, stats: &UserStats) -> Result<()> {
+        let metrics = metrics(stats).map(|value| format_float(value, self.precision));
+
+        self.inner
+            .write_field(&stats.address)
+            .and_then(|_| self.inner.write_record(&metrics))
+            .map_err(write_error)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.inner.flush().map_err(write_error)
+    }
+}
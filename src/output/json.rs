@@ -0,0 +1,119 @@
+use std::io::Write;
+
+use serde_json::{json, Map, Number, Value};
+
+use super::{metrics, round, write_error, StatsWriter, COLUMNS};
+use crate::{errors::Result, models::user_stats::UserStats};
+
+// Either a single JSON array, opened on the first row, or one object per line
+pub struct JsonWriter<W: Write> {
+    out: W,
+    precision: Option<usize>,
+    array: bool,
+    rows: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn array(out: W, precision: Option<usize>) -> Self {
+        JsonWriter {
+            out,
+            precision,
+            array: true,
+            rows: 0,
+        }
+    }
+
+    pub fn lines(out: W, precision: Option<usize>) -> Self {
+        JsonWriter {
+            out,
+            precision,
+            array: false,
+            rows: 0,
+        }
+    }
+
+    fn object(&self, stats: &UserStats) -> Value {
+        let mut object = Map::new();
+        object.insert(COLUMNS[0].to_string(), json!(stats.address));
+
+        for (column, value) in COLUMNS[1..].iter().zip(metrics(stats)) {
+            object.insert(column.to_string(), number(value, self.precision));
+        }
+
+        Value::Object(object)
+    }
+}
+
+// Non-finite values have no JSON representation and become null
+fn number(value: f64, precision: Option<usize>) -> Value {
+    let value = precision.map_or(value, |precision| round(value, precision));
+
+    Number::from_f64(value).map_or(Value::Null, Value::Number)
+}
+
+impl<W: Write> StatsWriter for JsonWriter<W> {
+    fn write(&mut self, stats: &UserStats) -> Result<()> {
+        let separator: &[u8] = match (self.array, self.rows) {
+            (true, 0) => b"[\n  ",
+            (true, _) => b",\n  ",
+            (false, _) => b"",
+        };
+        self.out.write_all(separator).map_err(write_error)?;
+
+        let object = self.object(stats);
+        serde_json::to_writer(&mut self.out, &object).map_err(write_error)?;
+
+        if !self.array {
+            self.out.write_all(b"\n").map_err(write_error)?;
+        }
+        self.rows += 1;
+
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if self.array {
+            let trailer: &[u8] = if self.rows == 0 { b"[]\n" } else { b"\n]\n" };
+            self.out.write_all(trailer).map_err(write_error)?;
+        }
+
+        self.out.flush().map_err(write_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{tests::render, OutputFormat, OutputOptions};
+    use super::*;
+
+    #[test]
+    fn writes_a_valid_array_with_stable_keys() -> Result<()> {
+        let json = render(OutputOptions {
+            format: OutputFormat::Json,
+            precision: Some(3),
+        })?;
+
+        let parsed: Vec<Map<String, Value>> = serde_json::from_str(&json).map_err(write_error)?;
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1]["total_volume"], json!(0.667));
+
+        let first_line = json.lines().nth(1).unwrap_or_default();
+        assert!(first_line.starts_with("  {\"address\":\"0xAlice\",\"total_volume\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn writes_one_object_per_line() -> Result<()> {
+        let jsonl = render(OutputOptions {
+            format: OutputFormat::Jsonl,
+            precision: None,
+        })?;
+
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"address\":\"0xAlice\""));
+
+        Ok(())
+    }
+}
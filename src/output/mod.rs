@@ -0,0 +1,221 @@
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{Error, Result},
+    models::user_stats::UserStats,
+};
+
+mod csv;
+mod json;
+mod parquet;
+mod table;
+
+// Column order shared by every format, changing it breaks downstream consumers
+pub const COLUMNS: [&str; 6] = [
+    "address",
+    "total_volume",
+    "avg_buy_price",
+    "avg_sell_price",
+    "max_balance",
+    "pnl",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Csv,
+    Json,
+    Jsonl,
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    // Digits after the decimal point, full precision when unset. Parquet always keeps full precision
+    pub precision: Option<usize>,
+}
+
+// Rows are written as they come, `finish` writes any trailer and flushes
+pub trait StatsWriter {
+    fn write(&mut self, stats: &UserStats) -> Result<()>;
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+pub fn writer<W: Write + Send + 'static>(
+    out: W,
+    options: OutputOptions,
+) -> Result<Box<dyn StatsWriter>> {
+    let precision = options.precision;
+
+    Ok(match options.format {
+        OutputFormat::Table => Box::new(table::TableWriter::new(out, precision)),
+        OutputFormat::Csv => Box::new(csv::CsvWriter::new(out, precision)?),
+        OutputFormat::Json => Box::new(json::JsonWriter::array(out, precision)),
+        OutputFormat::Jsonl => Box::new(json::JsonWriter::lines(out, precision)),
+        OutputFormat::Parquet => Box::new(parquet::ParquetWriter::new(out)?),
+    })
+}
+
+// Rows go out one at a time without rendering the whole output in memory. The stats themselves
+// are already computed and sorted, which needs every address at once
+pub fn write_stats<'a, W: Write + Send + 'static>(
+    out: W,
+    options: OutputOptions,
+    stats: impl IntoIterator<Item = &'a UserStats>,
+) -> Result<()> {
+    let mut writer = writer(out, options)?;
+
+    for stat in stats {
+        writer.write(stat)?;
+    }
+
+    writer.finish()
+}
+
+fn metrics(stats: &UserStats) -> [f64; 5] {
+    [
+        stats.total_volume,
+        stats.avg_buy_price,
+        stats.avg_sell_price,
+        stats.max_balance,
+        stats.pnl,
+    ]
+}
+
+// The one rounding rule of every format: the exact binary value, ties to even, as `format!` does
+fn round(value: f64, precision: usize) -> f64 {
+    format!("{:.*}", precision, value).parse().unwrap_or(value)
+}
+
+fn format_float(value: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => format!("{:.*}", precision, round(value, precision)),
+        None => value.to_string(),
+    }
+}
+
+fn write_error(e: impl Into<std::io::Error>) -> Error {
+    Error::io("Could not write stats", e.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    // `writer` takes ownership, tests read back through a shared buffer
+    #[derive(Clone, Default)]
+    pub(super) struct SharedBuffer(pub Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        pub(super) fn bytes(&self) -> Vec<u8> {
+            self.0.lock().unwrap().clone()
+        }
+
+        pub(super) fn text(&self) -> String {
+            String::from_utf8(self.bytes()).unwrap()
+        }
+    }
+
+    pub(super) fn sample() -> Vec<UserStats> {
+        vec![
+            UserStats {
+                address: "0xAlice".to_string(),
+                total_volume: 1234.5678,
+                avg_buy_price: 1.0,
+                avg_sell_price: 0.0,
+                max_balance: 100.0,
+                pnl: 0.0,
+            },
+            UserStats {
+                address: "0xBob".to_string(),
+                total_volume: 2.0 / 3.0,
+                avg_buy_price: 0.5,
+                avg_sell_price: 2.25,
+                max_balance: 0.0,
+                pnl: -1.5,
+            },
+        ]
+    }
+
+    pub(super) fn render(options: OutputOptions) -> Result<String> {
+        let buffer = SharedBuffer::default();
+        write_stats(buffer.clone(), options, &sample())?;
+
+        Ok(buffer.text())
+    }
+
+    #[test]
+    fn applies_precision_to_text_formats() -> Result<()> {
+        let csv = render(OutputOptions {
+            format: OutputFormat::Csv,
+            precision: Some(2),
+        })?;
+
+        assert_eq!(
+            csv,
+            "address,total_volume,avg_buy_price,avg_sell_price,max_balance,pnl\n\
+             0xAlice,1234.57,1.00,0.00,100.00,0.00\n\
+             0xBob,0.67,0.50,2.25,0.00,-1.50\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn every_format_rounds_the_same_way() -> Result<()> {
+        let stats = [UserStats {
+            address: "0xTie".to_string(),
+            total_volume: 2.5,
+            avg_buy_price: 0.125,
+            avg_sell_price: 1.005,
+            max_balance: -0.5,
+            pnl: 3.5,
+        }];
+        let written = |format: OutputFormat, precision: usize| -> Result<String> {
+            let buffer = SharedBuffer::default();
+            let options = OutputOptions {
+                format,
+                precision: Some(precision),
+            };
+            write_stats(buffer.clone(), options, &stats)?;
+            Ok(buffer.text())
+        };
+
+        assert_eq!(
+            written(OutputFormat::Csv, 0)?.lines().nth(1),
+            Some("0xTie,2,0,1,-0,4")
+        );
+        assert_eq!(
+            written(OutputFormat::Jsonl, 0)?,
+            "{\"address\":\"0xTie\",\"total_volume\":2.0,\"avg_buy_price\":0.0,\
+             \"avg_sell_price\":1.0,\"max_balance\":-0.0,\"pnl\":4.0}\n"
+        );
+
+        assert_eq!(
+            written(OutputFormat::Csv, 2)?.lines().nth(1),
+            Some("0xTie,2.50,0.12,1.00,-0.50,3.50")
+        );
+        assert!(written(OutputFormat::Jsonl, 2)?
+            .contains("\"avg_buy_price\":0.12,\"avg_sell_price\":1.0,"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use parquet::{
+    data_type::{ByteArray, ByteArrayType, DoubleType},
+    errors::ParquetError,
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+
+use super::{metrics, StatsWriter};
+use crate::{
+    errors::{Error, Result},
+    models::user_stats::UserStats,
+};
+
+// Field order follows `COLUMNS`
+const SCHEMA: &str = "
+    message user_stats {
+        REQUIRED BYTE_ARRAY address (UTF8);
+        REQUIRED DOUBLE total_volume;
+        REQUIRED DOUBLE avg_buy_price;
+        REQUIRED DOUBLE avg_sell_price;
+        REQUIRED DOUBLE max_balance;
+        REQUIRED DOUBLE pnl;
+    }
+";
+
+// Rows are buffered into row groups of this size, bounding memory regardless of the export size
+const ROW_GROUP_SIZE: usize = 64 * 1_024;
+
+pub struct ParquetWriter<W: Write + Send> {
+    inner: SerializedFileWriter<W>,
+    addresses: Vec<ByteArray>,
+    metrics: [Vec<f64>; 5],
+}
+
+fn parquet_error(e: ParquetError) -> Error {
+    Error::io(
+        "Could not write stats",
+        std::io::Error::other(e.to_string()),
+    )
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    pub fn new(out: W) -> Result<Self> {
+        let schema = Arc::new(parse_message_type(SCHEMA).map_err(parquet_error)?);
+        let properties = Arc::new(WriterProperties::builder().build());
+
+        Ok(ParquetWriter {
+            inner: SerializedFileWriter::new(out, schema, properties).map_err(parquet_error)?,
+            addresses: Vec::with_capacity(ROW_GROUP_SIZE),
+            metrics: Default::default(),
+        })
+    }
+
+    fn write_row_group(&mut self) -> Result<()> {
+        if self.addresses.is_empty() {
+            return Ok(());
+        }
+
+        let mut row_group = self.inner.next_row_group().map_err(parquet_error)?;
+
+        if let Some(mut column) = row_group.next_column().map_err(parquet_error)? {
+            column
+                .typed::<ByteArrayType>()
+                .write_batch(&self.addresses, None, None)
+                .map_err(parquet_error)?;
+            column.close().map_err(parquet_error)?;
+        }
+
+        for values in &self.metrics {
+            if let Some(mut column) = row_group.next_column().map_err(parquet_error)? {
+                column
+                    .typed::<DoubleType>()
+                    .write_batch(values, None, None)
+                    .map_err(parquet_error)?;
+                column.close().map_err(parquet_error)?;
+            }
+        }
+
+        row_group.close().map_err(parquet_error)?;
+
+        self.addresses.clear();
+        self.metrics.iter_mut().for_each(Vec::clear);
+
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> StatsWriter for ParquetWriter<W> {
+    fn write(&mut self, stats: &UserStats) -> Result<()> {
+        self.addresses.push(ByteArray::from(stats.address.as_str()));
+        for (column, value) in self.metrics.iter_mut().zip(metrics(stats)) {
+            column.push(value);
+        }
+
+        if self.addresses.len() >= ROW_GROUP_SIZE {
+            self.write_row_group()?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.write_row_group()?;
+        self.inner.close().map_err(parquet_error)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+
+    use super::super::tests::sample;
+    use super::super::{write_stats, OutputFormat, OutputOptions, COLUMNS};
+
+    #[test]
+    fn writes_readable_parquet() -> anyhow::Result<()> {
+        let file = tempfile::tempfile()?;
+        write_stats(
+            file.try_clone()?,
+            OutputOptions {
+                format: OutputFormat::Parquet,
+                precision: Some(1),
+            },
+            &sample(),
+        )?;
+
+        let reader = SerializedFileReader::new(file)?;
+
+        let fields: Vec<String> = reader
+            .metadata()
+            .file_metadata()
+            .schema()
+            .get_fields()
+            .iter()
+            .map(|f| f.name().to_string())
+            .collect();
+        assert_eq!(fields, COLUMNS);
+
+        let rows = reader
+            .get_row_iter(None)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].get_string(0)?, "0xBob");
+        assert_eq!(rows[1].get_double(1)?, 2.0 / 3.0);
+
+        Ok(())
+    }
+}
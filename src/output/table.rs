@@ -0,0 +1,115 @@
+use std::io::Write;
+
+use super::{format_float, metrics, write_error, StatsWriter, COLUMNS};
+use crate::{errors::Result, models::user_stats::UserStats};
+
+// Column widths are fitted to the first rows, later wider values just push their row out
+const SIZING_ROWS: usize = 1_024;
+
+pub struct TableWriter<W: Write> {
+    out: W,
+    precision: Option<usize>,
+    pending: Vec<[String; 6]>,
+    widths: Option<[usize; 6]>,
+}
+
+impl<W: Write> TableWriter<W> {
+    pub fn new(out: W, precision: Option<usize>) -> Self {
+        TableWriter {
+            out,
+            precision,
+            pending: vec![],
+            widths: None,
+        }
+    }
+
+    fn cells(&self, stats: &UserStats) -> [String; 6] {
+        let [volume, buy, sell, balance, pnl] =
+            metrics(stats).map(|value| format_float(value, self.precision));
+
+        [stats.address.clone(), volume, buy, sell, balance, pnl]
+    }
+
+    // Sizes the columns to what was buffered so far and prints the header and buffered rows
+    fn flush_pending(&mut self) -> Result<[usize; 6]> {
+        let mut widths = COLUMNS.map(str::len);
+        for row in &self.pending {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let header = COLUMNS.map(str::to_string);
+        write_row(&mut self.out, &header, &widths)?;
+
+        let separator = widths.map(|width| "-".repeat(width));
+        write_row(&mut self.out, &separator, &widths)?;
+
+        for row in std::mem::take(&mut self.pending) {
+            write_row(&mut self.out, &row, &widths)?;
+        }
+
+        self.widths = Some(widths);
+
+        Ok(widths)
+    }
+}
+
+// Address left aligned, numbers right aligned
+fn write_row(out: &mut impl Write, row: &[String; 6], widths: &[usize; 6]) -> Result<()> {
+    let mut line = format!("{:<width$}", row[0], width = widths[0]);
+    for (cell, width) in row[1..].iter().zip(&widths[1..]) {
+        line.push_str(&format!("  {:>width$}", cell, width = width));
+    }
+
+    writeln!(out, "{}", line.trim_end()).map_err(write_error)
+}
+
+impl<W: Write> StatsWriter for TableWriter<W> {
+    fn write(&mut self, stats: &UserStats) -> Result<()> {
+        let row = self.cells(stats);
+
+        match self.widths {
+            Some(widths) => write_row(&mut self.out, &row, &widths),
+            None => {
+                self.pending.push(row);
+                if self.pending.len() >= SIZING_ROWS {
+                    self.flush_pending()?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        if self.widths.is_none() {
+            self.flush_pending()?;
+        }
+
+        self.out.flush().map_err(write_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{tests::render, OutputFormat, OutputOptions};
+    use super::*;
+
+    #[test]
+    fn aligns_columns() -> Result<()> {
+        let table = render(OutputOptions {
+            format: OutputFormat::Table,
+            precision: Some(1),
+        })?;
+
+        assert_eq!(
+            table,
+            "address  total_volume  avg_buy_price  avg_sell_price  max_balance   pnl\n\
+             -------  ------------  -------------  --------------  -----------  ----\n\
+             0xAlice        1234.6            1.0             0.0        100.0   0.0\n\
+             0xBob             0.7            0.5             2.2          0.0  -1.5\n"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::storage::Storage;
+use crate::errors::Result;
+use crate::models::{
+    balance_checkpoint::BalanceCheckpoint,
+    transfer::{Transfer, TransferFilter, TransferOrdering},
+};
+
+// Read-through cache for `get_sorted`, dropped as a whole on every `insert_all` through it.
+// Writes that bypass this wrapper are not seen until the next insert
+pub struct CachingStorage<S: Storage> {
+    inner: S,
+    sorted: Mutex<HashMap<TransferOrdering, Vec<Transfer>>>,
+}
+
+impl<S: Storage> CachingStorage<S> {
+    pub fn new(inner: S) -> Self {
+        CachingStorage {
+            inner,
+            sorted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, HashMap<TransferOrdering, Vec<Transfer>>> {
+        // Entries are inserted whole, a poisoned lock can't expose a partial one
+        self.sorted
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl<S: Storage + Send + Sync> Storage for CachingStorage<S> {
+    async fn get_sorted(&self, transfer_ordering: TransferOrdering) -> Result<Vec<Transfer>> {
+        if let Some(cached) = self.cache().get(&transfer_ordering) {
+            return Ok(cached.clone());
+        }
+
+        let transfers = self.inner.get_sorted(transfer_ordering).await?;
+        self.cache().insert(transfer_ordering, transfers.clone());
+
+        Ok(transfers)
+    }
+
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+        self.inner.get_filtered(filter).await
+    }
+
+    async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
+        // Invalidate even if the insert fails, part of it may have landed
+        self.cache().clear();
+        self.inner.insert_all(transfers).await
+    }
+
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>> {
+        self.inner.get_checkpoint(address, ts).await
+    }
+
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+        self.inner.insert_checkpoints(checkpoints).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clickhouse::{
+        test::{handlers, Mock},
+        Client,
+    };
+
+    use super::*;
+    use crate::repositories::clickhouse::ClickhouseStorage;
+
+    fn transfer(ts: u64) -> Transfer {
+        Transfer {
+            ts,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_repeated_queries_from_cache() -> anyhow::Result<()> {
+        let mock = Mock::new();
+        let client = Client::default().with_url(mock.url());
+        let storage = CachingStorage::new(ClickhouseStorage::new(client));
+
+        // A single response: a second round trip would find no handler and fail
+        mock.add(handlers::provide(vec![transfer(1)]));
+
+        let first = storage.get_sorted(TransferOrdering::Chronological).await?;
+        let second = storage.get_sorted(TransferOrdering::Chronological).await?;
+
+        assert_eq!(first, vec![transfer(1)]);
+        assert_eq!(first, second);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn inserts_invalidate_the_cache() -> anyhow::Result<()> {
+        let mock = Mock::new();
+        let client = Client::default().with_url(mock.url());
+        let mut storage = CachingStorage::new(ClickhouseStorage::new(client));
+
+        mock.add(handlers::provide(vec![transfer(1)]));
+        storage.get_sorted(TransferOrdering::Chronological).await?;
+
+        mock.add(handlers::provide(vec![0u64]));
+        mock.add(handlers::record::<Transfer>());
+        storage.insert_all(&[transfer(2)]).await?;
+
+        mock.add(handlers::provide(vec![transfer(1), transfer(2)]));
+        let after_insert = storage.get_sorted(TransferOrdering::Chronological).await?;
+
+        assert_eq!(after_insert, vec![transfer(1), transfer(2)]);
+
+        Ok(())
+    }
+}
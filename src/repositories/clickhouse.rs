@@ -1,12 +1,15 @@
 use super::storage::Storage;
-use crate::errors::StorageResult;
-use crate::models::transfer::{Transfer, TransferOrdering};
-use anyhow::Result;
+use crate::errors::{Result, StorageResult};
+use crate::metrics::metrics;
+use crate::models::balance_checkpoint::{BalanceCheckpoint, StaleCheckpoints};
+use crate::models::transfer::{Transfer, TransferFilter, TransferOrdering};
 use async_trait::async_trait;
 use clickhouse::sql::Identifier;
 use clickhouse::Client;
+use tracing::{debug, instrument};
 
 pub const TABLE: &str = "transfers";
+pub const CHECKPOINTS_TABLE: &str = "balance_checkpoints";
 
 pub struct ClickhouseStorage {
     client: Client,
@@ -17,15 +20,9 @@ impl ClickhouseStorage {
         ClickhouseStorage { client }
     }
 
-    pub async fn ensure_schema(&self) -> Result<()> {
-        self.client
-            .query("DROP TABLE IF EXISTS ?")
-            .bind(Identifier(TABLE))
-            .with_option("wait_end_of_query", "1")
-            .execute()
-            .await
-            .with_context(&format!("Could not drop the table {}", TABLE))?;
-
+    // Creates missing tables, leaving existing data alone
+    #[instrument(skip(self), err)]
+    pub async fn migrate(&self) -> Result<()> {
         let query = r"
             CREATE TABLE IF NOT EXISTS ? (
                 ts UInt64,
@@ -44,51 +41,230 @@ impl ClickhouseStorage {
             .await
             .with_context(&format!("Could not create table {}", TABLE))?;
 
+        let query = r"
+            CREATE TABLE IF NOT EXISTS ? (
+                ts UInt64,
+                address String,
+                balance Float64
+            ) ENGINE = ReplacingMergeTree()
+            ORDER BY (address, ts)
+        ";
+
+        self.client
+            .query(query)
+            .bind(Identifier(CHECKPOINTS_TABLE))
+            .execute()
+            .await
+            .with_context(&format!("Could not create table {}", CHECKPOINTS_TABLE))?;
+
         Ok(())
     }
+
+    // Drops every table and recreates them empty
+    #[instrument(skip(self), err)]
+    pub async fn reset(&self) -> Result<()> {
+        for table in [TABLE, CHECKPOINTS_TABLE] {
+            self.client
+                .query("DROP TABLE IF EXISTS ?")
+                .bind(Identifier(table))
+                .with_option("wait_end_of_query", "1")
+                .execute()
+                .await
+                .with_context(&format!("Could not drop the table {}", table))?;
+        }
+
+        self.migrate().await
+    }
+
+    // Counts first so that the usual append past every checkpoint doesn't schedule a mutation
+    async fn drop_stale_checkpoints(&self, stale: &StaleCheckpoints) -> Result<()> {
+        let addresses: Vec<&str> = stale.addresses.iter().map(String::as_str).collect();
+
+        let count = self
+            .client
+            .query("SELECT count() FROM ? WHERE ts >= ? AND has(?, address)")
+            .bind(Identifier(CHECKPOINTS_TABLE))
+            .bind(stale.from_ts)
+            .bind(&addresses)
+            .fetch_one::<u64>()
+            .await
+            .with_context("Could not look up stale balance checkpoints")?;
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        debug!(count, from_ts = stale.from_ts, "dropping stale checkpoints");
+
+        self.client
+            .query("DELETE FROM ? WHERE ts >= ? AND has(?, address)")
+            .bind(Identifier(CHECKPOINTS_TABLE))
+            .bind(stale.from_ts)
+            .bind(&addresses)
+            .execute()
+            .await
+            .with_context("Could not drop stale balance checkpoints")
+    }
 }
 
 #[async_trait]
 impl Storage for ClickhouseStorage {
+    #[instrument(skip(self), err)]
     async fn get_sorted(&self, transfer_ordering: TransferOrdering) -> Result<Vec<Transfer>> {
-        let order_by_clause = match transfer_ordering {
-            TransferOrdering::Raw => "",
-            TransferOrdering::Chronological => " ORDER BY ts ASC",
-            TransferOrdering::ByVolume => " ORDER BY amount DESC",
-        };
+        let metrics = metrics()?;
+        metrics
+            .time(&metrics.query_duration, "get_sorted", async {
+                let order_by_clause = match transfer_ordering {
+                    TransferOrdering::Raw => "",
+                    TransferOrdering::Chronological => " ORDER BY ts ASC",
+                    TransferOrdering::ByVolume => " ORDER BY amount DESC",
+                };
 
-        let query = format!("SELECT * from ? {}", order_by_clause);
+                let query = format!("SELECT * from ? {}", order_by_clause);
 
-        let res = self
-            .client
-            .query(&query)
-            .bind(Identifier(TABLE))
-            .fetch_all::<Transfer>()
+                let res = self
+                    .client
+                    .query(&query)
+                    .bind(Identifier(TABLE))
+                    .fetch_all::<Transfer>()
+                    .await
+                    .with_context("Could not fetch transfers")?;
+
+                debug!(rows = res.len(), "fetched transfers");
+
+                Ok(res)
+            })
             .await
-            .with_context("Could not fetch transfers")?;
+    }
+
+    #[instrument(skip(self), err)]
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+        let metrics = metrics()?;
+        metrics
+            .time(&metrics.query_duration, "get_filtered", async {
+                let mut conditions = vec![];
+                if filter.address.is_some() {
+                    conditions.push("(`from` = ? OR `to` = ?)");
+                }
+                if filter.from_ts.is_some() {
+                    conditions.push("ts >= ?");
+                }
+                if filter.to_ts.is_some() {
+                    conditions.push("ts <= ?");
+                }
+
+                let where_clause = if conditions.is_empty() {
+                    String::new()
+                } else {
+                    format!(" WHERE {}", conditions.join(" AND "))
+                };
+
+                let query = format!("SELECT * from ?{} ORDER BY ts ASC", where_clause);
+
+                let mut query = self.client.query(&query).bind(Identifier(TABLE));
+                if let Some(address) = &filter.address {
+                    query = query.bind(address).bind(address);
+                }
+                if let Some(from_ts) = filter.from_ts {
+                    query = query.bind(from_ts);
+                }
+                if let Some(to_ts) = filter.to_ts {
+                    query = query.bind(to_ts);
+                }
 
-        Ok(res)
+                let res = query
+                    .fetch_all::<Transfer>()
+                    .await
+                    .with_context("Could not fetch transfers")?;
+
+                debug!(rows = res.len(), "fetched filtered transfers");
+
+                Ok(res)
+            })
+            .await
     }
 
+    #[instrument(skip_all, fields(batch_size = transfers.len()), err)]
     async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
-        let mut insert = self
-            .client
-            .insert("transfers")
-            .with_context("Could not insert transfers")?;
+        let metrics = metrics()?;
+        metrics
+            .time(&metrics.insert_duration, TABLE, async {
+                // Done first, failing after the insert would report stored transfers as failed.
+                // Checkpoints dropped for transfers that then don't land only cost a longer replay
+                if let Some(stale) = StaleCheckpoints::of(transfers) {
+                    self.drop_stale_checkpoints(&stale).await?;
+                }
 
-        for row in transfers {
-            insert
-                .write(row)
-                .await
-                .with_context("Could not insert transfers")?;
-        }
+                let mut insert = self
+                    .client
+                    .insert("transfers")
+                    .with_context("Could not insert transfers")?;
+
+                for row in transfers {
+                    insert
+                        .write(row)
+                        .await
+                        .with_context("Could not insert transfers")?;
+                }
 
-        insert
-            .end()
+                insert
+                    .end()
+                    .await
+                    .with_context("Could not insert transfers")?;
+
+                Ok(())
+            })
             .await
-            .with_context("Could not insert transfers")?;
+    }
 
-        Ok(())
+    #[instrument(skip(self), err)]
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>> {
+        let metrics = metrics()?;
+        metrics
+            .time(&metrics.query_duration, "get_checkpoint", async {
+                let res = self
+                    .client
+                    .query(
+                        "SELECT ?fields FROM ? FINAL WHERE address = ? AND ts <= ? \
+                         ORDER BY ts DESC LIMIT 1",
+                    )
+                    .bind(Identifier(CHECKPOINTS_TABLE))
+                    .bind(address)
+                    .bind(ts)
+                    .fetch_optional::<BalanceCheckpoint>()
+                    .await
+                    .with_context("Could not fetch balance checkpoint")?;
+
+                Ok(res)
+            })
+            .await
+    }
+
+    #[instrument(skip_all, fields(batch_size = checkpoints.len()), err)]
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+        let metrics = metrics()?;
+        metrics
+            .time(&metrics.insert_duration, CHECKPOINTS_TABLE, async {
+                let mut insert = self
+                    .client
+                    .insert(CHECKPOINTS_TABLE)
+                    .with_context("Could not insert balance checkpoints")?;
+
+                for row in checkpoints {
+                    insert
+                        .write(row)
+                        .await
+                        .with_context("Could not insert balance checkpoints")?;
+                }
+
+                insert
+                    .end()
+                    .await
+                    .with_context("Could not insert balance checkpoints")?;
+
+                Ok(())
+            })
+            .await
     }
 }
 
@@ -103,7 +279,7 @@ mod tests {
     use clickhouse::{
         test::{
             handlers::{self, RecordControl},
-            Mock,
+            status, Mock,
         },
         Client,
     };
@@ -115,6 +291,7 @@ mod tests {
         let mock = Mock::new();
         let client = Client::default().with_url(mock.url());
         let mut storage = ClickhouseStorage::new(client);
+        mock.add(handlers::provide(vec![0u64]));
         let recording: RecordControl<Transfer> = mock.add(handlers::record());
 
         let transfers = generator().build().generate(20)?;
@@ -128,6 +305,101 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn inserting_drops_stale_checkpoints() -> Result<()> {
+        let mock = Mock::new();
+        let client = Client::default().with_url(mock.url());
+        let mut storage = ClickhouseStorage::new(client);
+        mock.add(handlers::provide(vec![2u64]));
+        let delete = mock.add(handlers::record_ddl());
+        let _: RecordControl<Transfer> = mock.add(handlers::record());
+
+        storage
+            .insert_all(&[Transfer {
+                ts: 500,
+                from: "0xAlice".to_string(),
+                to: "0xBob".to_string(),
+                amount: 1.0,
+                usd_price: 1.0,
+            }])
+            .await?;
+
+        let query = delete.query().await;
+        assert!(
+            query.starts_with("DELETE FROM `balance_checkpoints`"),
+            "{}",
+            query
+        );
+        assert!(query.contains("ts >= 500"), "{}", query);
+        assert!(query.contains("['0xAlice','0xBob']"), "{}", query);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn inserts_nothing_when_stale_checkpoints_cannot_be_dropped() -> Result<()> {
+        let mock = Mock::new();
+        let client = Client::default().with_url(mock.url());
+        let mut storage = ClickhouseStorage::new(client);
+        mock.add(handlers::failure(status::SERVICE_UNAVAILABLE));
+
+        let res = storage.insert_all(&generator().build().generate(5)?).await;
+        assert!(res.is_err());
+
+        // The insert never started, so the next one is the first to reach the recording
+        let transfers = generator().build().generate(3)?;
+        mock.add(handlers::provide(vec![0u64]));
+        let recording: RecordControl<Transfer> = mock.add(handlers::record());
+        storage.insert_all(&transfers).await?;
+
+        let rows: Vec<Transfer> = recording.collect().await;
+        assert_eq!(rows, transfers);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn inserting_checkpoints() -> Result<()> {
+        let mock = Mock::new();
+        let client = Client::default().with_url(mock.url());
+        let mut storage = ClickhouseStorage::new(client);
+        let recording: RecordControl<BalanceCheckpoint> = mock.add(handlers::record());
+
+        let checkpoints = vec![BalanceCheckpoint {
+            ts: 86_399,
+            address: "0xBob".to_string(),
+            balance: 42.0,
+        }];
+
+        storage.insert_checkpoints(&checkpoints).await?;
+
+        let rows: Vec<BalanceCheckpoint> = recording.collect().await;
+
+        assert_eq!(rows, checkpoints);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fetches_nearest_checkpoint() -> Result<()> {
+        let mock = Mock::new();
+        let client = Client::default().with_url(mock.url());
+        let storage = ClickhouseStorage::new(client);
+
+        let checkpoint = BalanceCheckpoint {
+            ts: 86_399,
+            address: "0xBob".to_string(),
+            balance: 42.0,
+        };
+        mock.add(handlers::provide(vec![checkpoint.clone()]));
+
+        let res = storage.get_checkpoint("0xBob", 100_000).await?;
+
+        assert_eq!(res, Some(checkpoint));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn it_gets_sorted_data() -> Result<()> {
         dotenv().ok();
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use super::{
+    caching::CachingStorage,
+    metered::{MeteredStorage, StorageMetrics},
+    retry::{CircuitBreakerConfig, RetryPolicy, RetryingStorage},
+    storage::Storage,
+    traced::TracedStorage,
+};
+
+pub type DynStorage = Box<dyn Storage + Send + Sync>;
+
+// Decorators to stack on a backend. From the backend outwards: retries, cache, metrics, tracing,
+// so cache hits skip retries while metrics and traces still see every call
+#[derive(Default)]
+pub struct StorageLayers {
+    pub retry: Option<(RetryPolicy, CircuitBreakerConfig)>,
+    pub cache: bool,
+    pub metrics: Option<Arc<StorageMetrics>>,
+    pub tracing: bool,
+}
+
+impl StorageLayers {
+    pub fn with_retry(mut self, policy: RetryPolicy, breaker: CircuitBreakerConfig) -> Self {
+        self.retry = Some((policy, breaker));
+        self
+    }
+
+    pub fn with_cache(mut self) -> Self {
+        self.cache = true;
+        self
+    }
+
+    pub fn with_metrics(mut self, metrics: Arc<StorageMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn with_tracing(mut self) -> Self {
+        self.tracing = true;
+        self
+    }
+
+    pub fn apply<S: Storage + Send + Sync + 'static>(self, storage: S) -> DynStorage {
+        let mut storage: DynStorage = Box::new(storage);
+
+        if let Some((policy, breaker)) = self.retry {
+            storage = Box::new(RetryingStorage::new(storage, policy, breaker));
+        }
+        if self.cache {
+            storage = Box::new(CachingStorage::new(storage));
+        }
+        if let Some(metrics) = self.metrics {
+            storage = Box::new(MeteredStorage::new(storage, metrics));
+        }
+        if self.tracing {
+            storage = Box::new(TracedStorage::new(storage));
+        }
+
+        storage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::transfer::{Transfer, TransferOrdering};
+    use crate::repositories::mock::MockStorage;
+
+    #[tokio::test]
+    async fn stacked_layers_pass_calls_through() -> anyhow::Result<()> {
+        let metrics = Arc::new(StorageMetrics::default());
+        let mut storage = StorageLayers::default()
+            .with_retry(RetryPolicy::default(), CircuitBreakerConfig::default())
+            .with_cache()
+            .with_metrics(metrics.clone())
+            .with_tracing()
+            .apply(MockStorage::default());
+
+        storage.insert_all(&[Transfer::default()]).await?;
+        let transfers = storage.get_sorted(TransferOrdering::Raw).await?;
+
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(metrics.snapshot()["get_sorted"].rows, 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use super::storage::Storage;
+use crate::errors::Result;
+use crate::models::{
+    balance_checkpoint::BalanceCheckpoint,
+    transfer::{Transfer, TransferFilter, TransferOrdering},
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperationStats {
+    pub calls: u64,
+    pub errors: u64,
+    pub rows: u64,
+    pub total_latency: Duration,
+    pub max_latency: Duration,
+}
+
+// Shared between the wrapper and whoever reports on it
+#[derive(Debug, Default)]
+pub struct StorageMetrics {
+    operations: Mutex<BTreeMap<&'static str, OperationStats>>,
+}
+
+impl StorageMetrics {
+    pub fn record(&self, operation: &'static str, latency: Duration, outcome: Result<u64, ()>) {
+        let mut operations = self
+            .operations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let stats = operations.entry(operation).or_default();
+
+        stats.calls += 1;
+        stats.total_latency += latency;
+        stats.max_latency = stats.max_latency.max(latency);
+        match outcome {
+            Ok(rows) => stats.rows += rows,
+            Err(()) => stats.errors += 1,
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<&'static str, OperationStats> {
+        self.operations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+pub struct MeteredStorage<S: Storage> {
+    inner: S,
+    metrics: Arc<StorageMetrics>,
+}
+
+impl<S: Storage> MeteredStorage<S> {
+    pub fn new(inner: S, metrics: Arc<StorageMetrics>) -> Self {
+        MeteredStorage { inner, metrics }
+    }
+}
+
+macro_rules! metered {
+    ($self:ident, $operation:literal, $call:expr, $rows:expr) => {{
+        let started = Instant::now();
+        let res = $call.await;
+        let rows: Result<u64, ()> = res.as_ref().map($rows).map_err(|_| ());
+
+        $self.metrics.record($operation, started.elapsed(), rows);
+
+        res
+    }};
+}
+
+#[async_trait]
+impl<S: Storage + Send + Sync> Storage for MeteredStorage<S> {
+    async fn get_sorted(&self, transfer_ordering: TransferOrdering) -> Result<Vec<Transfer>> {
+        metered!(
+            self,
+            "get_sorted",
+            self.inner.get_sorted(transfer_ordering),
+            |transfers| transfers.len() as u64
+        )
+    }
+
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+        metered!(
+            self,
+            "get_filtered",
+            self.inner.get_filtered(filter),
+            |transfers| transfers.len() as u64
+        )
+    }
+
+    async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
+        let rows = transfers.len() as u64;
+        metered!(self, "insert_all", self.inner.insert_all(transfers), |_| {
+            rows
+        })
+    }
+
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>> {
+        metered!(
+            self,
+            "get_checkpoint",
+            self.inner.get_checkpoint(address, ts),
+            |checkpoint| checkpoint.is_some() as u64
+        )
+    }
+
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+        let rows = checkpoints.len() as u64;
+        metered!(
+            self,
+            "insert_checkpoints",
+            self.inner.insert_checkpoints(checkpoints),
+            |_| rows
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::mock::MockStorage;
+
+    #[tokio::test]
+    async fn records_calls_and_rows_per_operation() -> anyhow::Result<()> {
+        let metrics = Arc::new(StorageMetrics::default());
+        let mut storage = MeteredStorage::new(MockStorage::default(), metrics.clone());
+
+        storage
+            .insert_all(&[Transfer::default(), Transfer::default()])
+            .await?;
+        storage.get_sorted(TransferOrdering::Raw).await?;
+        storage.get_sorted(TransferOrdering::Chronological).await?;
+
+        let snapshot = metrics.snapshot();
+
+        let inserts = &snapshot["insert_all"];
+        assert_eq!(inserts.calls, 1);
+        assert_eq!(inserts.rows, 2);
+        assert_eq!(inserts.errors, 0);
+
+        let reads = &snapshot["get_sorted"];
+        assert_eq!(reads.calls, 2);
+        assert_eq!(reads.rows, 4);
+        assert!(reads.max_latency <= reads.total_latency);
+
+        Ok(())
+    }
+}
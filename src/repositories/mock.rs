@@ -1,17 +1,28 @@
-use anyhow::Result;
 use async_trait::async_trait;
 
-use crate::models::transfer::{Transfer, TransferOrdering};
+use crate::errors::Result;
+use crate::models::{
+    balance_checkpoint::{BalanceCheckpoint, StaleCheckpoints},
+    transfer::{Transfer, TransferFilter, TransferOrdering},
+};
 
 use super::storage::Storage;
 
+#[derive(Default)]
 pub struct MockStorage {
     pub transfers: Vec<Transfer>,
+    pub checkpoints: Vec<BalanceCheckpoint>,
+    // Inserts replace the stored transfers unless set, then they are appended like a real backend
+    pub append: bool,
 }
 
-impl Default for MockStorage {
-    fn default() -> Self {
-        MockStorage { transfers: vec![] }
+impl MockStorage {
+    // Keeps every inserted batch, for use as an in-memory backend
+    pub fn appending() -> Self {
+        MockStorage {
+            append: true,
+            ..Default::default()
+        }
     }
 }
 
@@ -33,8 +44,52 @@ impl Storage for MockStorage {
         }
     }
 
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+        let mut transfers: Vec<Transfer> = self
+            .transfers
+            .iter()
+            .filter(|t| filter.matches(t))
+            .cloned()
+            .collect();
+
+        transfers.sort_by_key(|i| i.ts);
+
+        Ok(transfers)
+    }
+
     async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
-        self.transfers = transfers.to_vec();
+        if !self.append {
+            // A replaced history invalidates every checkpoint
+            self.transfers = transfers.to_vec();
+            self.checkpoints.clear();
+            return Ok(());
+        }
+
+        self.transfers.extend_from_slice(transfers);
+
+        if let Some(stale) = StaleCheckpoints::of(transfers) {
+            self.checkpoints.retain(|c| !stale.contains(c));
+        }
+
+        Ok(())
+    }
+
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>> {
+        Ok(self
+            .checkpoints
+            .iter()
+            .filter(|c| c.address == address && c.ts <= ts)
+            .max_by_key(|c| c.ts)
+            .cloned())
+    }
+
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+        self.checkpoints.retain(|c| {
+            !checkpoints
+                .iter()
+                .any(|n| n.address == c.address && n.ts == c.ts)
+        });
+        self.checkpoints.extend_from_slice(checkpoints);
         Ok(())
     }
 }
@@ -1,3 +1,9 @@
+pub mod caching;
 pub mod clickhouse;
+pub mod layers;
+pub mod metered;
 pub mod mock;
+pub mod retry;
+pub mod shared;
 pub mod storage;
+pub mod traced;
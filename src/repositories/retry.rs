@@ -0,0 +1,441 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rand::Rng;
+use tokio::time::{sleep, Instant};
+
+use super::storage::Storage;
+use crate::errors::{Error, Result};
+use crate::models::{
+    balance_checkpoint::BalanceCheckpoint,
+    transfer::{Transfer, TransferFilter, TransferOrdering},
+};
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    // Including the first call
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    // Fraction of the backoff randomly added or removed so that clients don't retry in lockstep
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    // `attempt` is the 1-based number of the attempt that just failed
+    pub fn backoff(&self, attempt: u32, rng: &mut impl Rng) -> Duration {
+        let exponential = self.initial_backoff.as_secs_f64()
+            * self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        let capped = exponential.min(self.max_backoff.as_secs_f64());
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 {
+            rng.gen_range(1.0 - jitter..=1.0 + jitter)
+        } else {
+            1.0
+        };
+
+        Duration::from_secs_f64(capped * factor)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    // Consecutive retryable failures that open the circuit
+    pub failure_threshold: u32,
+    // How long calls fail fast before a single trial call is let through. A trial that
+    // hasn't reported back within another cooldown is presumed lost and replaced
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("circuit breaker is open after {failures} consecutive failures")]
+pub struct CircuitOpen {
+    pub failures: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CircuitState {
+    Closed,
+    Open { until: Instant },
+    HalfOpen { trial_until: Instant },
+}
+
+#[derive(Debug)]
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: CircuitState,
+    failures: u32,
+}
+
+impl CircuitBreaker {
+    fn acquire(&mut self) -> Result<()> {
+        let now = Instant::now();
+        match self.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open { until } | CircuitState::HalfOpen { trial_until: until }
+                if now < until =>
+            {
+                Err(Error::StorageUnavailable {
+                    context: "Storage call rejected".to_string(),
+                    source: Box::new(CircuitOpen {
+                        failures: self.failures,
+                    }),
+                })
+            }
+            // Only the caller that flips the state gets through, everyone else is rejected
+            // above until the trial reports back
+            CircuitState::Open { .. } | CircuitState::HalfOpen { .. } => {
+                self.state = CircuitState::HalfOpen {
+                    trial_until: now + self.config.cooldown,
+                };
+                Ok(())
+            }
+        }
+    }
+
+    // Any answer from the backend, including a permanent error, proves it is reachable
+    fn record_success(&mut self) {
+        self.state = CircuitState::Closed;
+        self.failures = 0;
+    }
+
+    fn record_failure(&mut self) {
+        self.failures += 1;
+
+        let half_open = matches!(self.state, CircuitState::HalfOpen { .. });
+        if half_open || self.failures >= self.config.failure_threshold {
+            self.state = CircuitState::Open {
+                until: Instant::now() + self.config.cooldown,
+            };
+        }
+    }
+}
+
+// Retries transient failures of any backend with exponential backoff and stops hammering it
+// through a circuit breaker once it looks down
+pub struct RetryingStorage<S: Storage> {
+    inner: S,
+    policy: RetryPolicy,
+    breaker: Mutex<CircuitBreaker>,
+}
+
+impl<S: Storage> RetryingStorage<S> {
+    pub fn new(inner: S, policy: RetryPolicy, breaker: CircuitBreakerConfig) -> Self {
+        RetryingStorage {
+            inner,
+            policy,
+            breaker: Mutex::new(CircuitBreaker {
+                config: breaker,
+                state: CircuitState::Closed,
+                failures: 0,
+            }),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn breaker(&self) -> std::sync::MutexGuard<'_, CircuitBreaker> {
+        // The breaker holds plain counters, a panic mid-update can't leave it inconsistent
+        self.breaker
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Returns the delay before the next attempt, or `None` when the error should be surfaced
+    fn after_failure(&self, error: &Error, attempt: u32, max_attempts: u32) -> Option<Duration> {
+        let mut breaker = self.breaker();
+        if !error.is_retryable() {
+            breaker.record_success();
+            return None;
+        }
+
+        breaker.record_failure();
+
+        let circuit_open = matches!(breaker.state, CircuitState::Open { .. });
+        if circuit_open || attempt >= max_attempts {
+            return None;
+        }
+
+        Some(self.policy.backoff(attempt, &mut rand::thread_rng()))
+    }
+}
+
+macro_rules! with_retries {
+    ($self:ident, $call:expr) => {
+        with_retries!($self, $call, $self.policy.max_attempts)
+    };
+    ($self:ident, $call:expr, $max_attempts:expr) => {{
+        let mut attempt = 0;
+        loop {
+            $self.breaker().acquire()?;
+            attempt += 1;
+
+            match $call.await {
+                Ok(res) => {
+                    $self.breaker().record_success();
+                    break Ok(res);
+                }
+                Err(e) => match $self.after_failure(&e, attempt, $max_attempts) {
+                    Some(backoff) => sleep(backoff).await,
+                    None => break Err(e),
+                },
+            }
+        }
+    }};
+}
+
+// Inserts are not idempotent: a timeout after the server committed the rows would be retried
+// into duplicates, so writes go through the breaker exactly once and failures are surfaced
+macro_rules! without_retries {
+    ($self:ident, $call:expr) => {
+        with_retries!($self, $call, 1)
+    };
+}
+
+#[async_trait]
+impl<S: Storage + Send + Sync> Storage for RetryingStorage<S> {
+    async fn get_sorted(&self, transfer_ordering: TransferOrdering) -> Result<Vec<Transfer>> {
+        with_retries!(self, self.inner.get_sorted(transfer_ordering))
+    }
+
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+        with_retries!(self, self.inner.get_filtered(filter))
+    }
+
+    async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
+        without_retries!(self, self.inner.insert_all(transfers))
+    }
+
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>> {
+        with_retries!(self, self.inner.get_checkpoint(address, ts))
+    }
+
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+        without_retries!(self, self.inner.insert_checkpoints(checkpoints))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clickhouse::{
+        test::{handlers, status, Mock},
+        Client,
+    };
+
+    use super::*;
+    use crate::repositories::clickhouse::ClickhouseStorage;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            jitter: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn storage(mock: &Mock, breaker: CircuitBreakerConfig) -> RetryingStorage<ClickhouseStorage> {
+        let client = Client::default().with_url(mock.url());
+        RetryingStorage::new(ClickhouseStorage::new(client), policy(), breaker)
+    }
+
+    fn transfers() -> Vec<Transfer> {
+        vec![Transfer {
+            ts: 100,
+            from: "0xAlice".to_string(),
+            to: "0xBob".to_string(),
+            amount: 1.0,
+            usd_price: 1.0,
+        }]
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() -> anyhow::Result<()> {
+        let mock = Mock::new();
+        let storage = storage(&mock, CircuitBreakerConfig::default());
+
+        mock.add(handlers::failure(status::SERVICE_UNAVAILABLE));
+        mock.add(handlers::failure(status::SERVICE_UNAVAILABLE));
+        mock.add(handlers::provide(transfers()));
+
+        let res = storage.get_sorted(TransferOrdering::Chronological).await?;
+
+        assert_eq!(res, transfers());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let mock = Mock::new();
+        let storage = storage(&mock, CircuitBreakerConfig::default());
+
+        for _ in 0..3 {
+            mock.add(handlers::failure(status::SERVICE_UNAVAILABLE));
+        }
+
+        let res = storage.get_sorted(TransferOrdering::Chronological).await;
+
+        assert!(matches!(res, Err(Error::StorageUnavailable { .. })));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_permanent_failures() {
+        let mock = Mock::new();
+        let storage = storage(&mock, CircuitBreakerConfig::default());
+
+        mock.add(handlers::failure(status::BAD_REQUEST));
+
+        let res = storage.get_sorted(TransferOrdering::Chronological).await;
+
+        assert!(matches!(res, Err(Error::QueryFailed { .. })));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_inserts() -> anyhow::Result<()> {
+        let mock = Mock::new();
+        let mut storage = storage(&mock, CircuitBreakerConfig::default());
+
+        mock.add(handlers::failure(status::SERVICE_UNAVAILABLE));
+        let res = storage.insert_all(&transfers()).await;
+        assert!(matches!(res, Err(Error::StorageUnavailable { .. })));
+
+        // The failed insert wasn't replayed, so the next one reaches this handler
+        mock.add(handlers::provide(vec![0u64]));
+        let recording = mock.add(handlers::record());
+        storage.insert_all(&transfers()).await?;
+
+        let rows: Vec<Transfer> = recording.collect().await;
+        assert_eq!(rows, transfers());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn open_circuit_fails_fast_until_cooldown() -> anyhow::Result<()> {
+        let mock = Mock::new();
+        let storage = storage(
+            &mock,
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                cooldown: Duration::from_millis(200),
+            },
+        );
+
+        mock.add(handlers::failure(status::SERVICE_UNAVAILABLE));
+        mock.add(handlers::failure(status::SERVICE_UNAVAILABLE));
+
+        let tripped = storage.get_sorted(TransferOrdering::Chronological).await;
+        assert!(tripped.is_err());
+
+        // No handler is registered, reaching the server would fail the mock
+        let rejected = storage.get_sorted(TransferOrdering::Chronological).await;
+        let Err(Error::StorageUnavailable { source, .. }) = rejected else {
+            panic!("Expected the circuit to reject the call");
+        };
+        assert!(source.is::<CircuitOpen>());
+
+        sleep(Duration::from_millis(250)).await;
+        mock.add(handlers::provide(transfers()));
+
+        let recovered = storage.get_sorted(TransferOrdering::Chronological).await?;
+        assert_eq!(recovered, transfers());
+
+        Ok(())
+    }
+
+    fn breaker(state: CircuitState) -> CircuitBreaker {
+        CircuitBreaker {
+            config: CircuitBreakerConfig {
+                failure_threshold: 1,
+                cooldown: Duration::from_secs(60),
+            },
+            state,
+            failures: 1,
+        }
+    }
+
+    #[test]
+    fn half_open_circuit_lets_a_single_trial_through() {
+        let mut breaker = breaker(CircuitState::Open {
+            until: Instant::now(),
+        });
+
+        assert!(breaker.acquire().is_ok());
+        assert!(breaker.acquire().is_err());
+        assert!(breaker.acquire().is_err());
+
+        breaker.record_success();
+        assert!(breaker.acquire().is_ok());
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn permanent_failure_of_the_trial_closes_the_circuit() {
+        let mock = Mock::new();
+        let storage = storage(
+            &mock,
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                cooldown: Duration::from_millis(50),
+            },
+        );
+
+        mock.add(handlers::failure(status::SERVICE_UNAVAILABLE));
+        assert!(storage
+            .get_sorted(TransferOrdering::Chronological)
+            .await
+            .is_err());
+
+        sleep(Duration::from_millis(80)).await;
+        mock.add(handlers::failure(status::BAD_REQUEST));
+        let trial = storage.get_sorted(TransferOrdering::Chronological).await;
+        assert!(matches!(trial, Err(Error::QueryFailed { .. })));
+
+        mock.add(handlers::provide(transfers()));
+        let res = storage.get_sorted(TransferOrdering::Chronological).await;
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_the_cap() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            jitter: 0.0,
+            ..Default::default()
+        };
+        let mut rng = rand::thread_rng();
+
+        assert_eq!(policy.backoff(1, &mut rng), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2, &mut rng), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3, &mut rng), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4, &mut rng), Duration::from_millis(500));
+    }
+}
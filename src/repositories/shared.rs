@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::{RwLock, RwLockWriteGuard};
+
+use super::storage::Storage;
+use crate::errors::Result;
+use crate::models::{
+    balance_checkpoint::BalanceCheckpoint,
+    transfer::{Transfer, TransferFilter, TransferOrdering},
+};
+
+// Cheaply clonable handle for concurrent users such as request handlers. Reads run side by side,
+// inserts wait for them and take the storage exclusively
+pub struct SharedStorage<S: Storage> {
+    inner: Arc<RwLock<S>>,
+}
+
+impl<S: Storage> SharedStorage<S> {
+    pub fn new(inner: S) -> Self {
+        SharedStorage {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+}
+
+impl<S: Storage> SharedStorage<S> {
+    // Holds off every other reader and writer, for a write and whatever has to stay in the
+    // same order as the writes
+    pub async fn write(&self) -> RwLockWriteGuard<'_, S> {
+        self.inner.write().await
+    }
+}
+
+impl<S: Storage> Clone for SharedStorage<S> {
+    fn clone(&self) -> Self {
+        SharedStorage {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Storage + Send + Sync> Storage for SharedStorage<S> {
+    async fn get_sorted(&self, transfer_ordering: TransferOrdering) -> Result<Vec<Transfer>> {
+        self.inner.read().await.get_sorted(transfer_ordering).await
+    }
+
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+        self.inner.read().await.get_filtered(filter).await
+    }
+
+    async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
+        self.inner.write().await.insert_all(transfers).await
+    }
+
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>> {
+        self.inner.read().await.get_checkpoint(address, ts).await
+    }
+
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+        self.inner
+            .write()
+            .await
+            .insert_checkpoints(checkpoints)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::mock::MockStorage;
+
+    #[tokio::test]
+    async fn clones_see_each_others_writes() -> Result<()> {
+        let mut writer = SharedStorage::new(MockStorage::default());
+        let reader = writer.clone();
+
+        writer.insert_all(&[Transfer::default()]).await?;
+
+        assert_eq!(reader.get_sorted(TransferOrdering::Raw).await?.len(), 1);
+
+        Ok(())
+    }
+}
@@ -1,12 +1,19 @@
-use anyhow::Result;
 use async_trait::async_trait;
 
-use crate::models::transfer::{Transfer, TransferOrdering};
+use crate::errors::Result;
+use crate::models::{
+    balance_checkpoint::{balance_since_checkpoint, BalanceCheckpoint},
+    transfer::{Transfer, TransferFilter, TransferOrdering},
+};
 
 #[async_trait]
 pub trait Storage {
     async fn get_sorted(&self, transfer_ordering: TransferOrdering) -> Result<Vec<Transfer>>;
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>>;
+    // Also drops the checkpoints the new transfers make stale, see `StaleCheckpoints`
     async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()>;
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>>;
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()>;
 }
 
 #[async_trait]
@@ -20,3 +27,53 @@ impl<T: Storage + Send + Sync> RetrievesTransfersChronologically for T {
 pub trait RetrievesTransfersChronologically {
     async fn get_chronologically(&self) -> Result<Vec<Transfer>>;
 }
+
+#[async_trait]
+impl<T: Storage + Send + Sync> RetrievesBalancesAsOf for T {
+    async fn get_balance_at(&self, address: &str, ts: u64) -> Result<f64> {
+        let checkpoint = self.get_checkpoint(address, ts).await?;
+
+        let filter = TransferFilter {
+            address: Some(address.to_string()),
+            from_ts: checkpoint.as_ref().map(|c| c.ts + 1),
+            to_ts: Some(ts),
+        };
+
+        let transfers = self.get_filtered(&filter).await?;
+
+        Ok(balance_since_checkpoint(
+            checkpoint.as_ref(),
+            address,
+            &transfers,
+        ))
+    }
+}
+
+#[async_trait]
+pub trait RetrievesBalancesAsOf {
+    async fn get_balance_at(&self, address: &str, ts: u64) -> Result<f64>;
+}
+
+// Lets decorator stacks chosen at runtime be used wherever a concrete storage is expected
+#[async_trait]
+impl<T: Storage + Send + Sync + ?Sized> Storage for Box<T> {
+    async fn get_sorted(&self, transfer_ordering: TransferOrdering) -> Result<Vec<Transfer>> {
+        (**self).get_sorted(transfer_ordering).await
+    }
+
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+        (**self).get_filtered(filter).await
+    }
+
+    async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
+        (**self).insert_all(transfers).await
+    }
+
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>> {
+        (**self).get_checkpoint(address, ts).await
+    }
+
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+        (**self).insert_checkpoints(checkpoints).await
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Instant;
+
+use async_trait::async_trait;
+use tracing::{debug, warn, Instrument};
+
+use super::storage::Storage;
+use crate::errors::Result;
+use crate::models::{
+    balance_checkpoint::BalanceCheckpoint,
+    transfer::{Transfer, TransferFilter, TransferOrdering},
+};
+
+pub struct TracedStorage<S: Storage> {
+    inner: S,
+}
+
+impl<S: Storage> TracedStorage<S> {
+    pub fn new(inner: S) -> Self {
+        TracedStorage { inner }
+    }
+}
+
+macro_rules! traced {
+    ($operation:literal, $call:expr, $rows:expr) => {{
+        let span = tracing::info_span!("storage", operation = $operation);
+
+        async {
+            let started = Instant::now();
+            let res = $call.await;
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+
+            match &res {
+                Ok(value) => debug!(rows = $rows(value), elapsed_ms, "storage call succeeded"),
+                Err(error) => warn!(%error, elapsed_ms, "storage call failed"),
+            }
+
+            res
+        }
+        .instrument(span)
+        .await
+    }};
+}
+
+#[async_trait]
+impl<S: Storage + Send + Sync> Storage for TracedStorage<S> {
+    async fn get_sorted(&self, transfer_ordering: TransferOrdering) -> Result<Vec<Transfer>> {
+        traced!(
+            "get_sorted",
+            self.inner.get_sorted(transfer_ordering),
+            |transfers: &Vec<Transfer>| transfers.len()
+        )
+    }
+
+    async fn get_filtered(&self, filter: &TransferFilter) -> Result<Vec<Transfer>> {
+        traced!(
+            "get_filtered",
+            self.inner.get_filtered(filter),
+            |transfers: &Vec<Transfer>| transfers.len()
+        )
+    }
+
+    async fn insert_all(&mut self, transfers: &[Transfer]) -> Result<()> {
+        let rows = transfers.len();
+        traced!("insert_all", self.inner.insert_all(transfers), |_: &()| {
+            rows
+        })
+    }
+
+    async fn get_checkpoint(&self, address: &str, ts: u64) -> Result<Option<BalanceCheckpoint>> {
+        traced!(
+            "get_checkpoint",
+            self.inner.get_checkpoint(address, ts),
+            |checkpoint: &Option<BalanceCheckpoint>| checkpoint.is_some() as usize
+        )
+    }
+
+    async fn insert_checkpoints(&mut self, checkpoints: &[BalanceCheckpoint]) -> Result<()> {
+        let rows = checkpoints.len();
+        traced!(
+            "insert_checkpoints",
+            self.inner.insert_checkpoints(checkpoints),
+            |_: &()| rows
+        )
+    }
+}
@@ -1,9 +1,20 @@
+use std::time::Instant;
+
+use tracing::{info, instrument};
+
 use crate::{
-    models::user_stats::UserStats, repositories::storage::RetrievesTransfersChronologically,
+    errors::{Error, Result},
+    models::{
+        integrity_report::IntegrityReport, supply_report::SupplyReport, transfer::TransferFilter,
+        user_stats::UserStats,
+    },
+    repositories::storage::{RetrievesBalancesAsOf, RetrievesTransfersChronologically, Storage},
 };
-use anyhow::{anyhow, Result};
 
-use super::stats::calculator::CalculatesStats;
+use super::integrity::IntegrityChecker;
+use super::stats::{
+    calculator::CalculatesStats, distribution::supply_report, snapshots::checkpoints,
+};
 
 pub struct Analytics<C, S>
 where
@@ -26,15 +37,90 @@ where
         }
     }
 
+    #[instrument(skip(self), err)]
     pub async fn get_stats(&self) -> Result<Vec<UserStats>> {
-        let transfers = self
-            .storage
-            .get_chronologically()
-            .await
-            .map_err(|e| anyhow!("Could not calculate stats: {}", e))?;
+        let started = Instant::now();
+        let transfers = self.storage.get_chronologically().await?;
+        let fetched_ms = started.elapsed().as_millis() as u64;
+
+        let stats = self.calculator.calculate_user_stats(&transfers);
+
+        info!(
+            transfers = transfers.len(),
+            addresses = stats.len(),
+            fetched_ms,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "calculated stats"
+        );
+
+        Ok(stats)
+    }
+
+    // Stats over the transfers matching `filter`; balances start from zero at `from_ts`.
+    // With an address set only that address is reported
+    pub async fn get_filtered_stats(&self, filter: &TransferFilter) -> Result<Vec<UserStats>>
+    where
+        S: Storage + Sync,
+    {
+        let transfers = self.storage.get_filtered(filter).await?;
+
+        let mut stats = self.calculator.calculate_user_stats(&transfers);
+        if let Some(address) = &filter.address {
+            stats.retain(|s| s.address == *address);
+        }
+
+        Ok(stats)
+    }
+
+    // Storage keeps no ingestion order, ClickHouse reads parts in parallel, so the history is
+    // checked chronologically and shows no timestamp regressions. Those are for sequences with
+    // an order of their own
+    pub async fn check_integrity(&self, checker: &IntegrityChecker) -> Result<IntegrityReport> {
+        let transfers = self.storage.get_chronologically().await?;
+
+        Ok(checker.check(&transfers))
+    }
+
+    // Refuses to produce stats over a history that fails the integrity checks
+    pub async fn get_checked_stats(&self, checker: &IntegrityChecker) -> Result<Vec<UserStats>> {
+        let transfers = self.storage.get_chronologically().await?;
+
+        let report = checker.check(&transfers);
+        if !report.is_clean() {
+            return Err(Error::ValidationFailed(format!(
+                "Transfer history failed integrity checks: {}",
+                report
+            )));
+        }
 
         Ok(self.calculator.calculate_user_stats(&transfers))
     }
+
+    pub async fn get_supply_report(&self, as_of: u64, top_n: usize) -> Result<SupplyReport> {
+        let transfers = self.storage.get_chronologically().await?;
+
+        Ok(supply_report(&transfers, as_of, top_n))
+    }
+
+    pub async fn get_balance_at(&self, address: &str, ts: u64) -> Result<f64>
+    where
+        S: RetrievesBalancesAsOf + Sync,
+    {
+        self.storage.get_balance_at(address, ts).await
+    }
+
+    pub async fn materialize_checkpoints(&mut self, interval_secs: u64) -> Result<usize>
+    where
+        S: Storage + Send + Sync,
+    {
+        let transfers = self.storage.get_chronologically().await?;
+
+        let checkpoints = checkpoints(&transfers, interval_secs);
+
+        self.storage.insert_checkpoints(&checkpoints).await?;
+
+        Ok(checkpoints.len())
+    }
 }
 
 #[cfg(test)]
@@ -43,7 +129,12 @@ mod tests {
     use anyhow::anyhow;
     use anyhow::Result;
 
-    use crate::repositories::mock::MockStorage;
+    use crate::errors::Error;
+    use crate::models::transfer::{Transfer, TransferFilter};
+    use crate::repositories::{mock::MockStorage, storage::Storage};
+    use crate::services::integrity::IntegrityChecker;
+    use crate::services::stats::calculator::StatsCalculator;
+    use crate::services::stats::snapshots::checkpoints;
     use crate::{models::user_stats::UserStats, services::stats::calculator::MockCalculatesStats};
 
     use super::Analytics;
@@ -80,4 +171,202 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn reports_only_the_filtered_address() -> Result<()> {
+        let transfer = |ts: u64, to: &str| Transfer {
+            ts,
+            from: "0xMint".to_string(),
+            to: to.to_string(),
+            amount: 10.0,
+            usd_price: 1.0,
+        };
+        let storage = MockStorage {
+            transfers: vec![
+                transfer(10, "0xAlice"),
+                transfer(20, "0xBob"),
+                transfer(30, "0xAlice"),
+            ],
+            ..Default::default()
+        };
+
+        let analytics = Analytics::new(storage, StatsCalculator::new());
+
+        let stats = analytics
+            .get_filtered_stats(&TransferFilter {
+                address: Some("0xAlice".to_string()),
+                to_ts: Some(20),
+                ..Default::default()
+            })
+            .await?;
+
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].address, "0xAlice");
+        assert_eq!(stats[0].total_volume, 10.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn builds_supply_report_from_stored_transfers() -> Result<()> {
+        let storage = MockStorage {
+            transfers: vec![
+                Transfer {
+                    ts: 200,
+                    from: "0xAlice".to_string(),
+                    to: "0xBob".to_string(),
+                    amount: 40.0,
+                    usd_price: 1.0,
+                },
+                Transfer {
+                    ts: 100,
+                    from: "0xMint".to_string(),
+                    to: "0xAlice".to_string(),
+                    amount: 100.0,
+                    usd_price: 1.0,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let analytics = Analytics::new(storage, MockCalculatesStats::new());
+
+        let report = analytics.get_supply_report(150, 10).await?;
+
+        assert_eq!(report.total_supply, 100.0);
+        assert_eq!(report.holders, 1);
+        assert_eq!(report.top_holders[0].address, "0xAlice");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn answers_balance_queries_from_materialized_checkpoints() -> Result<()> {
+        let transfer = |ts: u64, from: &str, to: &str, amount: f64| Transfer {
+            ts,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            usd_price: 1.0,
+        };
+        let storage = MockStorage {
+            transfers: vec![
+                transfer(10, "0xMint", "0xAlice", 100.0),
+                transfer(150, "0xAlice", "0xBob", 30.0),
+                transfer(170, "0xBob", "0xAlice", 5.0),
+                transfer(320, "0xAlice", "0xBob", 25.0),
+            ],
+            ..Default::default()
+        };
+
+        let mut analytics = Analytics::new(storage, MockCalculatesStats::new());
+
+        let materialized = analytics.materialize_checkpoints(100).await?;
+
+        assert_eq!(materialized, 6);
+        assert_eq!(analytics.get_balance_at("0xAlice", 5).await?, 0.0);
+        assert_eq!(analytics.get_balance_at("0xAlice", 160).await?, 70.0);
+        assert_eq!(analytics.get_balance_at("0xAlice", 250).await?, 75.0);
+        assert_eq!(analytics.get_balance_at("0xAlice", 1_000).await?, 50.0);
+        assert_eq!(analytics.get_balance_at("0xBob", 1_000).await?, 50.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn backfilled_transfers_invalidate_later_checkpoints() -> Result<()> {
+        let transfer = |ts: u64, from: &str, to: &str, amount: f64| Transfer {
+            ts,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            usd_price: 1.0,
+        };
+        let mut storage = MockStorage {
+            transfers: vec![
+                transfer(10, "0xMint", "0xAlice", 100.0),
+                transfer(150, "0xAlice", "0xBob", 30.0),
+            ],
+            append: true,
+            ..Default::default()
+        };
+        storage.checkpoints = checkpoints(&storage.transfers, 100);
+
+        storage
+            .insert_all(&[transfer(120, "0xAlice", "0xCarol", 50.0)])
+            .await?;
+
+        let analytics = Analytics::new(storage, MockCalculatesStats::new());
+
+        assert_eq!(analytics.get_balance_at("0xAlice", 100).await?, 100.0);
+        assert_eq!(analytics.get_balance_at("0xAlice", 1_000).await?, 20.0);
+        assert_eq!(analytics.get_balance_at("0xBob", 1_000).await?, 30.0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refuses_stats_over_inconsistent_history() -> Result<()> {
+        let storage = MockStorage {
+            transfers: vec![Transfer {
+                ts: 100,
+                from: "0xAlice".to_string(),
+                to: "0xBob".to_string(),
+                amount: 10.0,
+                usd_price: 1.0,
+            }],
+            ..Default::default()
+        };
+        let mut calculator = MockCalculatesStats::new();
+        calculator.expect_calculate_user_stats().never();
+
+        let analytics = Analytics::new(storage, calculator);
+
+        let report = analytics.check_integrity(&IntegrityChecker::new()).await?;
+        assert_eq!(report.negative_balances(), 1);
+
+        let stats = analytics.get_checked_stats(&IntegrityChecker::new()).await;
+        assert!(
+            matches!(stats, Err(Error::ValidationFailed(_))),
+            "Alice spends tokens she never received"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn checks_history_stored_out_of_order_chronologically() -> Result<()> {
+        let transfer = |ts: u64, from: &str, to: &str| Transfer {
+            ts,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: 10.0,
+            usd_price: 1.0,
+        };
+        // Alice spends what she received, in storage order she would go negative first
+        let storage = MockStorage {
+            transfers: vec![
+                transfer(300, "0xMint", "0xBob"),
+                transfer(200, "0xAlice", "0xBob"),
+                transfer(100, "0xMint", "0xAlice"),
+            ],
+            ..Default::default()
+        };
+        let mut calculator = MockCalculatesStats::new();
+        calculator
+            .expect_calculate_user_stats()
+            .withf(|transfers| transfers.windows(2).all(|w| w[0].ts <= w[1].ts))
+            .times(1)
+            .returning(|_| vec![]);
+
+        let analytics = Analytics::new(storage, calculator);
+        let checker = IntegrityChecker::new().with_issuers(["0xMint"]);
+
+        let report = analytics.check_integrity(&checker).await?;
+        assert!(report.is_clean(), "{}", report);
+
+        analytics.get_checked_stats(&checker).await?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,109 @@
+use futures::{Stream, StreamExt};
+
+use crate::{
+    errors::{Error, Result},
+    models::transfer::Transfer,
+    repositories::storage::Storage,
+};
+
+// Drains the stream into storage in batches so that unbounded sources never sit in memory whole.
+// Batches are validated like the ones sent to the servers. Returns the number of ingested transfers
+pub async fn ingest_stream<S, T>(storage: &mut S, transfers: T, batch_size: usize) -> Result<usize>
+where
+    S: Storage + Send,
+    T: Stream<Item = Result<Transfer>>,
+{
+    let mut batches = std::pin::pin!(transfers.chunks(batch_size.max(1)));
+    let mut ingested = 0;
+
+    while let Some(batch) = batches.next().await {
+        let batch = batch.into_iter().collect::<Result<Vec<Transfer>>>()?;
+        validate_transfers(&batch)?;
+
+        storage.insert_all(&batch).await?;
+        ingested += batch.len();
+    }
+
+    Ok(ingested)
+}
+
+// Rejects the whole batch if any row could never be a real transfer
+pub fn validate_transfers(transfers: &[Transfer]) -> Result<()> {
+    for (index, transfer) in transfers.iter().enumerate() {
+        let problem = if !(transfer.amount.is_finite() && transfer.amount > 0.0) {
+            "amount must be positive"
+        } else if !(transfer.usd_price.is_finite() && transfer.usd_price > 0.0) {
+            "usd_price must be positive"
+        } else if transfer.from == transfer.to {
+            "from and to must differ"
+        } else {
+            continue;
+        };
+
+        return Err(Error::ValidationFailed(format!(
+            "Transfer {}: {}",
+            index, problem
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::stream;
+
+    use super::*;
+    use crate::factories::generator::DefaultTransferGenerator;
+    use crate::factories::{defaults::generator, stream::StreamsTransfers};
+    use crate::repositories::mock::MockStorage;
+
+    #[tokio::test]
+    async fn ingests_streamed_transfers_in_batches() -> Result<()> {
+        let mut storage = MockStorage::appending();
+        let transfers = DefaultTransferGenerator::default().stream()?.take(1_050);
+
+        let ingested = ingest_stream(&mut storage, stream::iter(transfers.map(Ok)), 100).await?;
+
+        assert_eq!(ingested, 1_050);
+        assert_eq!(storage.transfers.len(), 1_050);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_first_source_error() -> Result<()> {
+        let mut storage = MockStorage::appending();
+        let transfers = generator().build().generate(3)?;
+        let source = stream::iter(vec![
+            Ok(transfers[0].clone()),
+            Err(Error::ValidationFailed("source broke".to_string())),
+        ]);
+
+        let res = ingest_stream(&mut storage, source, 10).await;
+
+        assert!(res.is_err());
+        assert!(storage.transfers.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_batches_with_invalid_transfers() -> Result<()> {
+        let mut storage = MockStorage::appending();
+        let mut transfers = generator().build().generate(3)?;
+        transfers[2].amount = -1.0;
+
+        let res = ingest_stream(
+            &mut storage,
+            stream::iter(transfers.into_iter().map(Ok)),
+            10,
+        )
+        .await;
+
+        assert!(matches!(res, Err(Error::ValidationFailed(_))));
+        assert!(storage.transfers.is_empty());
+
+        Ok(())
+    }
+}
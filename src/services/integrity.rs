@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{
+    integrity_report::{IntegrityIssue, IntegrityReport},
+    transfer::Transfer,
+};
+
+// Absorbs float rounding so that spending the whole balance doesn't count as going negative
+const BALANCE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Default)]
+pub struct IntegrityChecker {
+    issuers: HashSet<String>,
+}
+
+impl IntegrityChecker {
+    pub fn new() -> Self {
+        IntegrityChecker::default()
+    }
+
+    // Issuers (mint / zero addresses) create supply, so their balance is expected to go negative
+    pub fn with_issuers(mut self, issuers: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.issuers.extend(issuers.into_iter().map(Into::into));
+        self
+    }
+
+    // Expects transfers in the order they are going to be accumulated, i.e. chronologically
+    pub fn check(&self, transfers: &[Transfer]) -> IntegrityReport {
+        let mut issues = vec![];
+        let mut balances: HashMap<&str, f64> = HashMap::new();
+        let mut seen: HashMap<(u64, &str, &str, u64, u64), usize> = HashMap::new();
+        let mut previous_ts: Option<u64> = None;
+
+        for (index, t) in transfers.iter().enumerate() {
+            if let Some(previous_ts) = previous_ts.filter(|&previous_ts| t.ts < previous_ts) {
+                issues.push(IntegrityIssue::TimestampRegression {
+                    index,
+                    ts: t.ts,
+                    previous_ts,
+                });
+            }
+            previous_ts = Some(previous_ts.map_or(t.ts, |previous_ts| previous_ts.max(t.ts)));
+
+            let key = (
+                t.ts,
+                t.from.as_str(),
+                t.to.as_str(),
+                t.amount.to_bits(),
+                t.usd_price.to_bits(),
+            );
+            if let Some(&duplicate_of) = seen.get(&key) {
+                issues.push(IntegrityIssue::DuplicateRow {
+                    index,
+                    duplicate_of,
+                });
+            } else {
+                seen.insert(key, index);
+            }
+
+            if t.amount.is_nan() || t.amount <= 0.0 {
+                issues.push(IntegrityIssue::NonPositiveAmount {
+                    index,
+                    amount: t.amount,
+                });
+            }
+
+            if t.usd_price.is_nan() || t.usd_price <= 0.0 {
+                issues.push(IntegrityIssue::NonPositivePrice {
+                    index,
+                    usd_price: t.usd_price,
+                });
+            }
+
+            if t.from == t.to {
+                issues.push(IntegrityIssue::SelfTransfer {
+                    index,
+                    address: t.from.clone(),
+                });
+            }
+
+            *balances.entry(&t.to).or_default() += t.amount;
+
+            let balance = balances.entry(&t.from).or_default();
+            let was_solvent = *balance >= -BALANCE_TOLERANCE;
+            *balance -= t.amount;
+
+            // Only the moment an address slips below zero is reported, not every transfer after it
+            if was_solvent && *balance < -BALANCE_TOLERANCE && !self.issuers.contains(&t.from) {
+                issues.push(IntegrityIssue::NegativeBalance {
+                    index,
+                    ts: t.ts,
+                    address: t.from.clone(),
+                    balance: *balance,
+                });
+            }
+        }
+
+        IntegrityReport {
+            checked: transfers.len(),
+            issues,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(ts: u64, from: &str, to: &str, amount: f64) -> Transfer {
+        Transfer {
+            ts,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            usd_price: 1.0,
+        }
+    }
+
+    #[test]
+    fn consistent_history_is_clean() {
+        let transfers = vec![
+            transfer(100, "0xMint", "0xAlice", 100.0),
+            transfer(200, "0xAlice", "0xBob", 60.0),
+            transfer(200, "0xAlice", "0xBob", 40.0),
+        ];
+
+        let report = IntegrityChecker::new()
+            .with_issuers(["0xMint"])
+            .check(&transfers);
+
+        assert!(report.is_clean(), "{}", report);
+        assert_eq!(report.checked, 3);
+    }
+
+    #[test]
+    fn flags_addresses_going_negative_once() {
+        let transfers = vec![
+            transfer(100, "0xAlice", "0xBob", 10.0),
+            transfer(200, "0xAlice", "0xBob", 10.0),
+            transfer(300, "0xBob", "0xAlice", 50.0),
+        ];
+
+        let report = IntegrityChecker::new().check(&transfers);
+
+        assert_eq!(
+            report.issues,
+            vec![
+                IntegrityIssue::NegativeBalance {
+                    index: 0,
+                    ts: 100,
+                    address: "0xAlice".to_string(),
+                    balance: -10.0,
+                },
+                IntegrityIssue::NegativeBalance {
+                    index: 2,
+                    ts: 300,
+                    address: "0xBob".to_string(),
+                    balance: -30.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_malformed_rows() {
+        let transfers = vec![
+            transfer(100, "0xMint", "0xAlice", 100.0),
+            Transfer {
+                usd_price: 0.0,
+                ..transfer(110, "0xAlice", "0xBob", 1.0)
+            },
+            transfer(120, "0xAlice", "0xBob", -5.0),
+            transfer(130, "0xAlice", "0xAlice", 1.0),
+            transfer(130, "0xAlice", "0xAlice", 1.0),
+            transfer(90, "0xAlice", "0xBob", 1.0),
+        ];
+
+        let report = IntegrityChecker::new()
+            .with_issuers(["0xMint"])
+            .check(&transfers);
+
+        assert_eq!(report.non_positive_prices(), 1);
+        assert_eq!(report.non_positive_amounts(), 1);
+        assert_eq!(report.self_transfers(), 2);
+        assert_eq!(report.duplicate_rows(), 1);
+        assert_eq!(report.timestamp_regressions(), 1);
+        assert_eq!(report.negative_balances(), 0);
+        assert!(report.issues.contains(&IntegrityIssue::DuplicateRow {
+            index: 4,
+            duplicate_of: 3,
+        }));
+        assert!(report
+            .issues
+            .contains(&IntegrityIssue::TimestampRegression {
+                index: 5,
+                ts: 90,
+                previous_ts: 130,
+            }));
+    }
+}
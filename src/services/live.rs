@@ -0,0 +1,176 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::sync::broadcast;
+
+use crate::{
+    errors::Result,
+    models::{
+        transfer::{Transfer, TransferOrdering},
+        user_stats::UserStats,
+    },
+    repositories::storage::Storage,
+};
+
+use super::stats::incremental::IncrementalStats;
+
+// Updates a subscriber may fall behind by before it starts missing them
+pub const DEFAULT_CAPACITY: usize = 1_024;
+
+// Current stats of the addresses one ingested batch changed
+pub type StatsUpdate = Arc<[UserStats]>;
+
+// Fans incremental stats out to live subscribers. Updates carry whole stats rather than
+// differences, so a subscriber that missed some catches up with any later update or a snapshot
+pub struct LiveFeed {
+    stats: Mutex<IncrementalStats>,
+    updates: broadcast::Sender<StatsUpdate>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        LiveFeed::new(IncrementalStats::default())
+    }
+}
+
+impl LiveFeed {
+    pub fn new(stats: IncrementalStats) -> Self {
+        Self::with_capacity(stats, DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(stats: IncrementalStats, capacity: usize) -> Self {
+        let (updates, _) = broadcast::channel(capacity.max(1));
+
+        LiveFeed {
+            stats: Mutex::new(stats),
+            updates,
+        }
+    }
+
+    // Stores a batch and publishes what it changed. Hold the storage write lock, live stats
+    // follow storage order. A batch older than the live stats has them rebuilt from the stored
+    // history, read before the insert so that a failed read leaves nothing stored
+    pub async fn insert<S>(&self, storage: &mut S, transfers: &[Transfer]) -> Result<usize>
+    where
+        S: Storage + Send + Sync,
+    {
+        if !self.lock().is_behind(transfers) {
+            storage.insert_all(transfers).await?;
+            return Ok(self.publish(transfers));
+        }
+
+        let stored = storage.get_sorted(TransferOrdering::Chronological).await?;
+        storage.insert_all(transfers).await?;
+
+        let mut stats = self.lock();
+        Ok(self.send(stats.rebuild(&stored, transfers)))
+    }
+
+    // Returns how many addresses changed. Never waits for subscribers, slow ones lag instead
+    pub fn publish(&self, transfers: &[Transfer]) -> usize {
+        // Sent under the lock so that subscribers see updates in the order they were applied
+        let mut stats = self.lock();
+        self.send(stats.apply(transfers))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StatsUpdate> {
+        self.updates.subscribe()
+    }
+
+    pub fn snapshot(&self, keep: impl Fn(&UserStats) -> bool) -> Vec<UserStats> {
+        let mut stats: Vec<UserStats> = self
+            .lock()
+            .snapshot()
+            .into_iter()
+            .filter(|stats| keep(stats))
+            .collect();
+        stats.sort_by(|a, b| a.address.cmp(&b.address));
+
+        stats
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IncrementalStats> {
+        self.stats
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn send(&self, changed: Vec<UserStats>) -> usize {
+        let count = changed.len();
+        if count > 0 {
+            // Having nobody listening is fine
+            self.updates.send(changed.into()).ok();
+        }
+
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    use super::*;
+    use crate::repositories::mock::MockStorage;
+
+    fn transfer(from: &str, to: &str) -> Transfer {
+        Transfer {
+            ts: 1,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: 1.0,
+            usd_price: 1.0,
+        }
+    }
+
+    #[test]
+    fn subscribers_receive_changed_stats() {
+        let feed = LiveFeed::default();
+        let mut updates = feed.subscribe();
+
+        assert_eq!(feed.publish(&[transfer("0xA", "0xB")]), 2);
+        assert_eq!(feed.publish(&[]), 0);
+
+        let update = updates.try_recv().ok();
+        let addresses: Option<Vec<&str>> = update
+            .as_ref()
+            .map(|u| u.iter().map(|s| s.address.as_str()).collect());
+        assert_eq!(addresses, Some(vec!["0xA", "0xB"]));
+        assert_eq!(updates.try_recv().err(), Some(TryRecvError::Empty));
+    }
+
+    #[tokio::test]
+    async fn rebuilds_for_batches_older_than_what_was_applied() -> Result<()> {
+        let feed = LiveFeed::default();
+        let mut storage = MockStorage::appending();
+        let at = |ts: u64, amount: f64| Transfer {
+            ts,
+            amount,
+            ..transfer("0xA", "0xB")
+        };
+
+        feed.insert(&mut storage, &[at(5, 1.0), at(9, 1.0)]).await?;
+        let mut updates = feed.subscribe();
+        feed.insert(&mut storage, &[at(1, 3.0)]).await?;
+
+        let update = updates.try_recv().ok();
+        let max_balances: Option<Vec<f64>> =
+            update.map(|u| u.iter().map(|s| s.max_balance).collect());
+        assert_eq!(max_balances, Some(vec![0.0, 5.0]));
+        assert_eq!(storage.transfers.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn slow_subscribers_lag_without_blocking_publishers() {
+        let feed = LiveFeed::with_capacity(IncrementalStats::new(), 2);
+        let mut updates = feed.subscribe();
+
+        for _ in 0..5 {
+            feed.publish(&[transfer("0xA", "0xB")]);
+        }
+
+        assert_eq!(updates.try_recv().err(), Some(TryRecvError::Lagged(3)));
+        assert_eq!(feed.snapshot(|s| s.address == "0xB")[0].total_volume, 5.0);
+    }
+}
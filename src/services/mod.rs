@@ -1,3 +1,6 @@
 pub mod analytics;
+pub mod ingest;
+pub mod integrity;
+pub mod live;
 pub mod pipeline_orig;
 pub mod stats;
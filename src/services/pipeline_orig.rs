@@ -64,6 +64,8 @@ pub fn calculate_user_stats(transfers: &[Transfer]) -> Vec<UserStats> {
                 avg_buy_price: avg(&buys),
                 avg_sell_price: avg(&sells),
                 max_balance: *max_balances.get(&addr).unwrap_or(&0.0),
+                pnl: sells.iter().map(|(px, amt)| px * amt).sum::<f64>()
+                    - avg(&buys) * sells.iter().map(|(_, amt)| amt).sum::<f64>(),
             }
         })
         .collect()
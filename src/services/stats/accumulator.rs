@@ -46,6 +46,15 @@ impl PriceAccumulator {
     pub fn max_balance(&self) -> f64 {
         self.max_balance
     }
+
+    // Sale proceeds minus what the sold amount cost at the average buy price
+    pub fn pnl(&self) -> f64 {
+        self.weight_sell_amount - self.sell_volume * self.avg_buy_price()
+    }
+
+    pub fn balance(&self) -> f64 {
+        self.balance
+    }
 }
 
 #[cfg(test)]
@@ -100,5 +109,6 @@ mod tests {
         assert_eq!(8.0, accumulator.avg_sell_price());
         assert_eq!(35.0, accumulator.total_volume());
         assert_eq!(33.0, accumulator.max_balance());
+        assert_eq!(-50.0, accumulator.pnl());
     }
 }
@@ -1,24 +1,47 @@
 use mockall::automock;
 
+use crate::metrics::metrics;
+
 use crate::models::{transfer::Transfer, user_stats::UserStats};
 use crate::services::stats::accumulator::PriceAccumulator;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{debug, instrument};
 
 #[automock]
 pub trait CalculatesStats {
     fn calculate_user_stats(&self, transfers: &[Transfer]) -> Vec<UserStats>;
 }
 
-pub struct StatsCalculator;
+// Lets one calculator be shared between concurrent requests
+impl<C: CalculatesStats + ?Sized> CalculatesStats for Arc<C> {
+    fn calculate_user_stats(&self, transfers: &[Transfer]) -> Vec<UserStats> {
+        (**self).calculate_user_stats(transfers)
+    }
+}
+
+#[derive(Default)]
+pub struct StatsCalculator {
+    // Still counted as counterparties, just left out of the results. Typically mints and exchanges
+    excluded: HashSet<String>,
+}
 
 impl StatsCalculator {
     pub fn new() -> Self {
-        StatsCalculator
+        StatsCalculator::default()
+    }
+
+    pub fn with_excluded(mut self, addresses: impl IntoIterator<Item = String>) -> Self {
+        self.excluded.extend(addresses);
+        self
     }
 }
 
 impl CalculatesStats for StatsCalculator {
+    #[instrument(skip_all, fields(transfers = transfers.len()))]
     fn calculate_user_stats(&self, transfers: &[Transfer]) -> Vec<UserStats> {
+        let started = Instant::now();
         let mut accumulators: HashMap<&str, PriceAccumulator> = HashMap::new();
         for t in transfers {
             accumulators
@@ -32,16 +55,34 @@ impl CalculatesStats for StatsCalculator {
                 .accumulate(-t.amount, t.usd_price);
         }
 
-        accumulators
+        let stats = accumulators
             .iter()
+            .filter(|(address, _)| !self.excluded.contains(**address))
             .map(|(&address, accumulator)| UserStats {
                 address: address.to_string(),
                 total_volume: accumulator.total_volume(),
                 avg_buy_price: accumulator.avg_buy_price(),
                 avg_sell_price: accumulator.avg_sell_price(),
                 max_balance: accumulator.max_balance(),
+                pnl: accumulator.pnl(),
             })
-            .collect::<Vec<UserStats>>()
+            .collect::<Vec<UserStats>>();
+
+        // Stats don't depend on metrics being available
+        if let Ok(metrics) = metrics() {
+            metrics.addresses_tracked.set(stats.len() as i64);
+            metrics
+                .calculation_duration
+                .observe(started.elapsed().as_secs_f64());
+        }
+
+        debug!(
+            addresses = stats.len(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "accumulated user stats"
+        );
+
+        stats
     }
 }
 
@@ -70,7 +111,7 @@ mod tests {
         let transfers = generator().with_config(config).build().generate(1)?;
 
         // Act: calculate user stats over the transfers
-        let stats = StatsCalculator.calculate_user_stats(&transfers);
+        let stats = StatsCalculator::new().calculate_user_stats(&transfers);
         assert_eq!(
             stats.len(),
             2,
@@ -115,14 +156,14 @@ mod tests {
 
         let transfers = vec![
             Transfer {
-                ts: SystemNow::now_unix()?,
+                ts: SystemNow.now_unix()?,
                 from: bob.clone(),
                 to: john.clone(),
                 amount: 10.0,
                 usd_price: 50.0,
             },
             Transfer {
-                ts: SystemNow::now_unix()?,
+                ts: SystemNow.now_unix()?,
                 from: john.clone(),
                 to: bob.clone(),
                 amount: 5.0,
@@ -131,7 +172,7 @@ mod tests {
         ];
 
         // Act:
-        let stats = StatsCalculator.calculate_user_stats(&transfers);
+        let stats = StatsCalculator::new().calculate_user_stats(&transfers);
         assert_eq!(stats.len(), 2, "Only 2 actors");
 
         let bob_stats = stats
@@ -195,7 +236,7 @@ mod tests {
         ];
 
         // Act:
-        let stats = StatsCalculator.calculate_user_stats(&transfers);
+        let stats = StatsCalculator::new().calculate_user_stats(&transfers);
 
         let bob_stats = stats
             .iter()
@@ -259,8 +300,10 @@ mod tests {
         ];
 
         // Act
-        let stats_correct_order = StatsCalculator.calculate_user_stats(&transfers_chronological);
-        let stats_wrong_order = StatsCalculator.calculate_user_stats(&transfers_random_order);
+        let stats_correct_order =
+            StatsCalculator::new().calculate_user_stats(&transfers_chronological);
+        let stats_wrong_order =
+            StatsCalculator::new().calculate_user_stats(&transfers_random_order);
 
         // Find Bob's stats in both results
         let bob_correct = stats_correct_order
@@ -313,8 +356,27 @@ mod tests {
 
     #[test]
     fn empty_transfers() {
-        let transfers = StatsCalculator.calculate_user_stats(&vec![]);
+        let transfers = StatsCalculator::new().calculate_user_stats(&[]);
 
         assert_eq!(0, transfers.len());
     }
+
+    #[test]
+    fn leaves_out_excluded_addresses() {
+        let transfers = vec![Transfer {
+            from: "0xMint".to_string(),
+            to: "0xAlice".to_string(),
+            amount: 10.0,
+            usd_price: 1.0,
+            ..Default::default()
+        }];
+
+        let stats = StatsCalculator::new()
+            .with_excluded(["0xMint".to_string()])
+            .calculate_user_stats(&transfers);
+
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].address, "0xAlice");
+        assert_eq!(stats[0].max_balance, 10.0);
+    }
 }
@@ -0,0 +1,239 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::models::supply_report::{HolderBalance, SupplyReport};
+use crate::models::transfer::Transfer;
+
+use super::accumulator::PriceAccumulator;
+
+#[derive(Default, Debug)]
+pub struct BalanceLedger<'a> {
+    accumulators: HashMap<&'a str, PriceAccumulator>,
+}
+
+impl<'a> BalanceLedger<'a> {
+    // Balances are plain sums, so unlike `max_balance` they don't depend on the replay order
+    pub fn as_of(transfers: &'a [Transfer], ts: u64) -> Self {
+        let mut ledger = BalanceLedger::default();
+
+        for t in transfers.iter().filter(|t| t.ts <= ts) {
+            ledger.apply(t);
+        }
+
+        ledger
+    }
+
+    pub fn apply(&mut self, t: &'a Transfer) {
+        self.accumulators
+            .entry(&t.to)
+            .or_default()
+            .accumulate(t.amount, t.usd_price);
+
+        self.accumulators
+            .entry(&t.from)
+            .or_default()
+            .accumulate(-t.amount, t.usd_price);
+    }
+
+    pub fn balance_of(&self, address: &str) -> f64 {
+        self.accumulators
+            .get(address)
+            .map(|accumulator| accumulator.balance())
+            .unwrap_or(0.0)
+    }
+
+    // Addresses with a positive balance, largest first, ties broken by address
+    pub fn holders(&self) -> Vec<(&'a str, f64)> {
+        let mut holders: Vec<(&'a str, f64)> = self
+            .accumulators
+            .iter()
+            .map(|(&address, accumulator)| (address, accumulator.balance()))
+            .filter(|(_, balance)| *balance > 0.0)
+            .collect();
+
+        holders.sort_unstable_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.0.cmp(b.0))
+        });
+
+        holders
+    }
+
+    pub fn report(&self, as_of: u64, top_n: usize) -> SupplyReport {
+        let holders = self.holders();
+        // Negative balances (issuers, missing history) are not part of the circulating supply
+        let total_supply: f64 = holders.iter().map(|(_, balance)| balance).sum();
+
+        let share = |balance: f64| {
+            if total_supply > 0.0 {
+                balance / total_supply
+            } else {
+                0.0
+            }
+        };
+
+        let top_holders: Vec<HolderBalance> = holders
+            .iter()
+            .take(top_n)
+            .map(|&(address, balance)| HolderBalance {
+                address: address.to_string(),
+                balance,
+                share: share(balance),
+            })
+            .collect();
+
+        let top_holders_share = top_holders.iter().map(|h| h.share).sum();
+        let balances: Vec<f64> = holders.iter().map(|(_, balance)| *balance).collect();
+
+        SupplyReport {
+            as_of,
+            total_supply,
+            holders: holders.len(),
+            top_holders,
+            top_holders_share,
+            gini: gini(&balances),
+            nakamoto_coefficient: nakamoto_coefficient(&balances, total_supply),
+        }
+    }
+}
+
+pub fn supply_report(transfers: &[Transfer], as_of: u64, top_n: usize) -> SupplyReport {
+    BalanceLedger::as_of(transfers, as_of).report(as_of, top_n)
+}
+
+// Expects balances sorted in descending order
+fn gini(balances: &[f64]) -> f64 {
+    let n = balances.len() as f64;
+    let total: f64 = balances.iter().sum();
+
+    if balances.is_empty() || total <= 0.0 {
+        return 0.0;
+    }
+
+    // Rank ascending: the smallest balance gets rank 1
+    let weighted: f64 = balances
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, balance)| (i as f64 + 1.0) * balance)
+        .sum();
+
+    (2.0 * weighted) / (n * total) - (n + 1.0) / n
+}
+
+// Smallest number of holders that together control more than half of the supply.
+// Expects balances sorted in descending order
+fn nakamoto_coefficient(balances: &[f64], total_supply: f64) -> usize {
+    let mut controlled = 0.0;
+
+    for (i, balance) in balances.iter().enumerate() {
+        controlled += balance;
+        if controlled > total_supply / 2.0 {
+            return i + 1;
+        }
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(ts: u64, from: &str, to: &str, amount: f64) -> Transfer {
+        Transfer {
+            ts,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            usd_price: 1.0,
+        }
+    }
+
+    fn transfers() -> Vec<Transfer> {
+        vec![
+            transfer(100, "0xMint", "0xAlice", 600.0),
+            transfer(100, "0xMint", "0xBob", 300.0),
+            transfer(200, "0xMint", "0xCarol", 100.0),
+            transfer(300, "0xAlice", "0xBob", 200.0),
+            transfer(400, "0xBob", "0xCarol", 500.0),
+        ]
+    }
+
+    #[test]
+    fn reconstructs_balances_at_given_timestamp() {
+        let transfers = transfers();
+
+        let ledger = BalanceLedger::as_of(&transfers, 300);
+
+        assert_eq!(ledger.balance_of("0xAlice"), 400.0);
+        assert_eq!(ledger.balance_of("0xBob"), 500.0);
+        assert_eq!(ledger.balance_of("0xCarol"), 100.0);
+        assert_eq!(ledger.balance_of("0xMint"), -1000.0);
+        assert_eq!(ledger.balance_of("0xNobody"), 0.0);
+    }
+
+    #[test]
+    fn reports_supply_and_holders() {
+        let transfers = transfers();
+
+        let report = supply_report(&transfers, 300, 2);
+
+        assert_eq!(report.as_of, 300);
+        assert_eq!(
+            report.total_supply, 1000.0,
+            "Minter's negative balance is not supply"
+        );
+        assert_eq!(report.holders, 3);
+        assert_eq!(
+            report.top_holders,
+            vec![
+                HolderBalance {
+                    address: "0xBob".to_string(),
+                    balance: 500.0,
+                    share: 0.5,
+                },
+                HolderBalance {
+                    address: "0xAlice".to_string(),
+                    balance: 400.0,
+                    share: 0.4,
+                },
+            ]
+        );
+        assert_eq!(report.top_holders_share, 0.9);
+        assert_eq!(
+            report.nakamoto_coefficient, 2,
+            "Bob alone holds exactly half, which is not a majority"
+        );
+    }
+
+    #[test]
+    fn holders_with_zero_balance_are_dropped() {
+        let transfers = transfers();
+
+        let report = supply_report(&transfers, 400, 10);
+
+        assert_eq!(report.holders, 2);
+        assert_eq!(report.top_holders[0].address, "0xCarol");
+        assert_eq!(report.top_holders[0].balance, 600.0);
+        assert_eq!(report.nakamoto_coefficient, 1);
+    }
+
+    #[test]
+    fn gini_of_equal_and_concentrated_distributions() {
+        assert_eq!(gini(&[10.0, 10.0, 10.0, 10.0]), 0.0);
+        assert_eq!(gini(&[100.0, 0.0, 0.0, 0.0]), 0.75);
+        assert_eq!(gini(&[]), 0.0);
+    }
+
+    #[test]
+    fn empty_history_produces_empty_report() {
+        let report = supply_report(&[], 1_000, 10);
+
+        assert_eq!(report.total_supply, 0.0);
+        assert_eq!(report.holders, 0);
+        assert!(report.top_holders.is_empty());
+        assert_eq!(report.nakamoto_coefficient, 0);
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::models::{transfer::Transfer, user_stats::UserStats};
+
+use super::accumulator::PriceAccumulator;
+
+// Keeps accumulators between batches so stats can follow a stream without recalculating
+// from scratch. Batches have to arrive in chronological order for max balances to be right,
+// an older one needs a `rebuild`
+#[derive(Debug, Default)]
+pub struct IncrementalStats {
+    accumulators: HashMap<String, PriceAccumulator>,
+    // Still counted as counterparties, just left out of the results
+    excluded: HashSet<String>,
+    latest_ts: Option<u64>,
+}
+
+impl IncrementalStats {
+    pub fn new() -> Self {
+        IncrementalStats::default()
+    }
+
+    pub fn with_excluded(mut self, addresses: impl IntoIterator<Item = String>) -> Self {
+        self.excluded.extend(addresses);
+        self
+    }
+
+    // Current stats of every address the batch touched, ordered by address
+    pub fn apply(&mut self, transfers: &[Transfer]) -> Vec<UserStats> {
+        self.accumulate(transfers.iter());
+        self.touched(transfers)
+    }
+
+    // Starts over from the stored history followed by a batch older than what was applied.
+    // Returns the same as `apply` would
+    pub fn rebuild(&mut self, stored: &[Transfer], transfers: &[Transfer]) -> Vec<UserStats> {
+        self.accumulators.clear();
+        self.latest_ts = None;
+
+        self.accumulate(stored.iter().chain(transfers));
+        self.touched(transfers)
+    }
+
+    // Whether the batch holds something older than what was applied, which `apply` can't take
+    pub fn is_behind(&self, transfers: &[Transfer]) -> bool {
+        match (transfers.iter().map(|t| t.ts).min(), self.latest_ts) {
+            (Some(ts), Some(latest_ts)) => ts < latest_ts,
+            _ => false,
+        }
+    }
+
+    pub fn get(&self, address: &str) -> Option<UserStats> {
+        if self.excluded.contains(address) {
+            return None;
+        }
+
+        self.accumulators
+            .get(address)
+            .map(|accumulator| user_stats(address, accumulator))
+    }
+
+    pub fn snapshot(&self) -> Vec<UserStats> {
+        self.accumulators
+            .iter()
+            .filter(|(address, _)| !self.excluded.contains(*address))
+            .map(|(address, accumulator)| user_stats(address, accumulator))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.accumulators.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accumulators.is_empty()
+    }
+
+    fn accumulate<'a>(&mut self, transfers: impl Iterator<Item = &'a Transfer>) {
+        let mut chronological: Vec<&Transfer> = transfers.collect();
+        chronological.sort_by_key(|t| t.ts);
+
+        if let Some(last) = chronological.last() {
+            self.latest_ts = self.latest_ts.max(Some(last.ts));
+        }
+
+        for t in chronological {
+            self.accumulator(&t.to).accumulate(t.amount, t.usd_price);
+            self.accumulator(&t.from).accumulate(-t.amount, t.usd_price);
+        }
+    }
+
+    fn touched(&self, transfers: &[Transfer]) -> Vec<UserStats> {
+        transfers
+            .iter()
+            .flat_map(|t| [t.to.as_str(), t.from.as_str()])
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|address| self.get(address))
+            .collect()
+    }
+
+    fn accumulator(&mut self, address: &str) -> &mut PriceAccumulator {
+        self.accumulators.entry(address.to_string()).or_default()
+    }
+}
+
+fn user_stats(address: &str, accumulator: &PriceAccumulator) -> UserStats {
+    UserStats {
+        address: address.to_string(),
+        total_volume: accumulator.total_volume(),
+        avg_buy_price: accumulator.avg_buy_price(),
+        avg_sell_price: accumulator.avg_sell_price(),
+        max_balance: accumulator.max_balance(),
+        pnl: accumulator.pnl(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Result;
+    use crate::factories::defaults::generator;
+    use crate::factories::{addresses::AddressPoolConfig, generator::TransferGenConfig};
+    use crate::services::stats::calculator::{CalculatesStats, StatsCalculator};
+
+    #[test]
+    fn batches_add_up_to_a_full_calculation() -> Result<()> {
+        let mut transfers = generator()
+            .with_config(TransferGenConfig {
+                seed: Some(3),
+                address_pool: Some(AddressPoolConfig {
+                    size: 20,
+                    zipf_exponent: 1.0,
+                }),
+                ..Default::default()
+            })
+            .build()
+            .generate(500)?;
+        transfers.sort_by_key(|t| t.ts);
+
+        let mut incremental = IncrementalStats::new();
+        for batch in transfers.chunks(37) {
+            incremental.apply(batch);
+        }
+
+        let mut expected = StatsCalculator::new().calculate_user_stats(&transfers);
+        let mut actual = incremental.snapshot();
+        expected.sort_by(|a, b| a.address.cmp(&b.address));
+        actual.sort_by(|a, b| a.address.cmp(&b.address));
+
+        assert_eq!(actual, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reports_only_touched_addresses() {
+        let transfer = |from: &str, to: &str| Transfer {
+            ts: 1,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: 2.0,
+            usd_price: 3.0,
+        };
+        let mut incremental = IncrementalStats::new().with_excluded(["0xMint".to_string()]);
+
+        incremental.apply(&[transfer("0xMint", "0xA"), transfer("0xMint", "0xB")]);
+        let changed = incremental.apply(&[transfer("0xB", "0xC")]);
+
+        let addresses: Vec<&str> = changed.iter().map(|s| s.address.as_str()).collect();
+        assert_eq!(addresses, ["0xB", "0xC"]);
+        assert_eq!(changed[0].total_volume, 4.0);
+        assert_eq!(incremental.len(), 4);
+        assert_eq!(incremental.get("0xMint"), None);
+    }
+
+    #[test]
+    fn rebuilds_for_older_batches() {
+        let transfer = |ts: u64, from: &str, to: &str| Transfer {
+            ts,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: 2.0,
+            usd_price: 1.0,
+        };
+        let stored = [transfer(1, "0xMint", "0xA"), transfer(3, "0xA", "0xB")];
+        let backfill = [transfer(2, "0xMint", "0xA")];
+        let mut incremental = IncrementalStats::new();
+
+        incremental.apply(&stored);
+        assert!(incremental.is_behind(&backfill));
+        assert!(!incremental.is_behind(&[transfer(3, "0xB", "0xA")]));
+
+        let changed = incremental.rebuild(&stored, &backfill);
+
+        let mut all = [stored.as_slice(), backfill.as_slice()].concat();
+        all.sort_by_key(|t| t.ts);
+        let mut expected = StatsCalculator::new().calculate_user_stats(&all);
+        expected.sort_by(|a, b| a.address.cmp(&b.address));
+        // 0xA, 0xB and 0xMint, the batch touched the first and the last
+        assert_eq!(changed, [expected[0].clone(), expected[2].clone()]);
+        assert_eq!(changed[0].max_balance, 4.0);
+
+        let mut actual = incremental.snapshot();
+        actual.sort_by(|a, b| a.address.cmp(&b.address));
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod accumulator;
 pub mod calculator;
+pub mod distribution;
+pub mod incremental;
 pub mod pipeline;
+pub mod ranking;
+pub mod snapshots;
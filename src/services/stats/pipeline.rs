@@ -25,6 +25,7 @@ pub fn calculate_user_stats(transfers: &[Transfer]) -> Vec<UserStats> {
             avg_buy_price: accumulator.avg_buy_price(),
             avg_sell_price: accumulator.avg_sell_price(),
             max_balance: accumulator.max_balance(),
+            pnl: accumulator.pnl(),
         })
         .collect::<Vec<UserStats>>()
 }
@@ -99,14 +100,14 @@ mod tests {
 
         let transfers = vec![
             Transfer {
-                ts: SystemNow::now_unix()?,
+                ts: SystemNow.now_unix()?,
                 from: bob.clone(),
                 to: john.clone(),
                 amount: 10.0,
                 usd_price: 50.0,
             },
             Transfer {
-                ts: SystemNow::now_unix()?,
+                ts: SystemNow.now_unix()?,
                 from: john.clone(),
                 to: bob.clone(),
                 amount: 5.0,
@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::models::user_stats::UserStats;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankBy {
+    #[default]
+    Volume,
+    MaxBalance,
+    Pnl,
+    AvgBuyPrice,
+    AvgSellPrice,
+}
+
+impl RankBy {
+    pub fn metric(&self, stats: &UserStats) -> f64 {
+        match self {
+            RankBy::Volume => stats.total_volume,
+            RankBy::MaxBalance => stats.max_balance,
+            RankBy::Pnl => stats.pnl,
+            RankBy::AvgBuyPrice => stats.avg_buy_price,
+            RankBy::AvgSellPrice => stats.avg_sell_price,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Ascending,
+    #[default]
+    Descending,
+}
+
+// A total order over stats: by metric in the given direction, then by address ascending,
+// so equal metrics come out the same way on every run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Ranking {
+    pub by: RankBy,
+    pub direction: Direction,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedStats {
+    // Competition ranking, equal metrics share a rank and the next rank is skipped
+    pub rank: usize,
+    pub stats: UserStats,
+}
+
+impl Ranking {
+    pub fn new(by: RankBy, direction: Direction) -> Self {
+        Ranking { by, direction }
+    }
+
+    pub fn compare(&self, a: &UserStats, b: &UserStats) -> Ordering {
+        let by_metric = self.by.metric(a).total_cmp(&self.by.metric(b));
+        let by_metric = match self.direction {
+            Direction::Ascending => by_metric,
+            Direction::Descending => by_metric.reverse(),
+        };
+
+        by_metric.then_with(|| a.address.cmp(&b.address))
+    }
+
+    pub fn sort(&self, stats: &mut [UserStats]) {
+        stats.sort_unstable_by(|a, b| self.compare(a, b));
+    }
+
+    // The first `n` in ranking order, in O(len * log n) and holding at most `n` stats at a time
+    pub fn top_n(&self, stats: impl IntoIterator<Item = UserStats>, n: usize) -> Vec<UserStats> {
+        if n == 0 {
+            return vec![];
+        }
+
+        // Max heap on ranking order, the root is the worst of the kept stats
+        let mut heap = BinaryHeap::with_capacity(n + 1);
+        for stats in stats {
+            let candidate = Ranked {
+                ranking: *self,
+                stats,
+            };
+
+            if heap.len() < n {
+                heap.push(candidate);
+            } else if heap.peek().is_some_and(|worst| candidate < *worst) {
+                heap.pop();
+                heap.push(candidate);
+            }
+        }
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|ranked| ranked.stats)
+            .collect()
+    }
+
+    pub fn leaderboard(
+        &self,
+        stats: impl IntoIterator<Item = UserStats>,
+        n: Option<usize>,
+    ) -> Leaderboard {
+        let ordered = match n {
+            Some(n) => self.top_n(stats, n),
+            None => {
+                let mut stats: Vec<UserStats> = stats.into_iter().collect();
+                self.sort(&mut stats);
+                stats
+            }
+        };
+
+        let mut entries: Vec<RankedStats> = Vec::with_capacity(ordered.len());
+        for (position, stats) in ordered.into_iter().enumerate() {
+            let rank = match entries.last() {
+                Some(previous)
+                    if self
+                        .by
+                        .metric(&previous.stats)
+                        .total_cmp(&self.by.metric(&stats))
+                        == Ordering::Equal =>
+                {
+                    previous.rank
+                }
+                _ => position + 1,
+            };
+
+            entries.push(RankedStats { rank, stats });
+        }
+
+        Leaderboard {
+            by: self.by,
+            entries,
+        }
+    }
+}
+
+struct Ranked {
+    ranking: Ranking,
+    stats: UserStats,
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ranking.compare(&self.stats, &other.stats)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leaderboard {
+    pub by: RankBy,
+    pub entries: Vec<RankedStats>,
+}
+
+impl fmt::Display for Leaderboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metric = self
+            .by
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        let address_width = self
+            .entries
+            .iter()
+            .map(|e| e.stats.address.len())
+            .max()
+            .unwrap_or(0)
+            .max("address".len());
+
+        writeln!(
+            f,
+            "{:>4}  {:<width$}  {:>16}",
+            "rank",
+            "address",
+            metric,
+            width = address_width
+        )?;
+
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{:>4}  {:<width$}  {:>16.4}",
+                entry.rank,
+                entry.stats.address,
+                self.by.metric(&entry.stats),
+                width = address_width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(address: &str, total_volume: f64, pnl: f64) -> UserStats {
+        UserStats {
+            address: address.to_string(),
+            total_volume,
+            pnl,
+            ..Default::default()
+        }
+    }
+
+    fn addresses(stats: &[UserStats]) -> Vec<&str> {
+        stats.iter().map(|s| s.address.as_str()).collect()
+    }
+
+    fn sample() -> Vec<UserStats> {
+        vec![
+            stats("0xD", 5.0, -1.0),
+            stats("0xB", 10.0, 3.0),
+            stats("0xA", 10.0, 0.5),
+            stats("0xC", 1.0, 7.0),
+        ]
+    }
+
+    #[test]
+    fn breaks_ties_by_address() {
+        let mut stats = sample();
+
+        Ranking::new(RankBy::Volume, Direction::Descending).sort(&mut stats);
+        assert_eq!(addresses(&stats), ["0xA", "0xB", "0xD", "0xC"]);
+
+        Ranking::new(RankBy::Volume, Direction::Ascending).sort(&mut stats);
+        assert_eq!(addresses(&stats), ["0xC", "0xD", "0xA", "0xB"]);
+    }
+
+    #[test]
+    fn top_n_matches_a_full_sort() {
+        let ranking = Ranking::new(RankBy::Pnl, Direction::Descending);
+        let mut sorted = sample();
+        ranking.sort(&mut sorted);
+
+        for n in 0..=5 {
+            let top = ranking.top_n(sample(), n);
+
+            assert_eq!(top, sorted[..n.min(sorted.len())]);
+        }
+    }
+
+    #[test]
+    fn equal_metrics_share_a_rank() {
+        let leaderboard = Ranking::default().leaderboard(sample(), Some(3));
+
+        let ranks: Vec<(usize, &str)> = leaderboard
+            .entries
+            .iter()
+            .map(|e| (e.rank, e.stats.address.as_str()))
+            .collect();
+        assert_eq!(ranks, [(1, "0xA"), (1, "0xB"), (3, "0xD")]);
+
+        let expected = [
+            "rank  address            volume",
+            "   1  0xA               10.0000",
+            "   1  0xB               10.0000",
+            "   3  0xD                5.0000",
+        ];
+        assert_eq!(leaderboard.to_string(), expected.join("\n") + "\n");
+    }
+}
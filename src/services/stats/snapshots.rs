@@ -0,0 +1,117 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::{balance_checkpoint::BalanceCheckpoint, transfer::Transfer};
+
+pub const DAILY: u64 = 86_400;
+
+// Expects chronologically sorted transfers. Emits one checkpoint per address at the end of every
+// interval in which its balance changed, so quiet addresses don't produce rows
+pub fn checkpoints(transfers: &[Transfer], interval_secs: u64) -> Vec<BalanceCheckpoint> {
+    let interval_secs = interval_secs.max(1);
+
+    let mut balances: HashMap<&str, f64> = HashMap::new();
+    let mut changed: BTreeSet<&str> = BTreeSet::new();
+    let mut current_period: Option<u64> = None;
+    let mut checkpoints = vec![];
+
+    let mut flush = |period: u64, changed: &mut BTreeSet<&str>, balances: &HashMap<&str, f64>| {
+        let ts = (period + 1).saturating_mul(interval_secs).saturating_sub(1);
+
+        for address in std::mem::take(changed) {
+            checkpoints.push(BalanceCheckpoint {
+                ts,
+                address: address.to_string(),
+                balance: balances.get(address).copied().unwrap_or(0.0),
+            });
+        }
+    };
+
+    for t in transfers {
+        let period = t.ts / interval_secs;
+
+        if let Some(current) = current_period.filter(|&current| current != period) {
+            flush(current, &mut changed, &balances);
+        }
+        current_period = Some(period);
+
+        *balances.entry(&t.from).or_default() -= t.amount;
+        *balances.entry(&t.to).or_default() += t.amount;
+        changed.insert(&t.from);
+        changed.insert(&t.to);
+    }
+
+    if let Some(current) = current_period {
+        flush(current, &mut changed, &balances);
+    }
+
+    checkpoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::balance_checkpoint::balance_since_checkpoint;
+
+    fn transfer(ts: u64, from: &str, to: &str, amount: f64) -> Transfer {
+        Transfer {
+            ts,
+            from: from.to_string(),
+            to: to.to_string(),
+            amount,
+            usd_price: 1.0,
+        }
+    }
+
+    #[test]
+    fn emits_checkpoints_at_the_end_of_each_active_interval() {
+        let transfers = vec![
+            transfer(10, "0xMint", "0xAlice", 100.0),
+            transfer(50, "0xAlice", "0xBob", 30.0),
+            transfer(250, "0xAlice", "0xBob", 20.0),
+        ];
+
+        let checkpoints = checkpoints(&transfers, 100);
+
+        let checkpoint = |ts: u64, address: &str, balance: f64| BalanceCheckpoint {
+            ts,
+            address: address.to_string(),
+            balance,
+        };
+
+        assert_eq!(
+            checkpoints,
+            vec![
+                checkpoint(99, "0xAlice", 70.0),
+                checkpoint(99, "0xBob", 30.0),
+                checkpoint(99, "0xMint", -100.0),
+                checkpoint(299, "0xAlice", 50.0),
+                checkpoint(299, "0xBob", 50.0),
+            ],
+            "Interval 100..199 had no activity and the minter did not move in the last one"
+        );
+    }
+
+    #[test]
+    fn replays_transfers_on_top_of_checkpoint() {
+        let checkpoint = BalanceCheckpoint {
+            ts: 99,
+            address: "0xAlice".to_string(),
+            balance: 70.0,
+        };
+        let transfers = vec![
+            transfer(120, "0xAlice", "0xBob", 20.0),
+            transfer(130, "0xCarol", "0xAlice", 5.0),
+        ];
+
+        assert_eq!(
+            balance_since_checkpoint(Some(&checkpoint), "0xAlice", &transfers),
+            55.0
+        );
+        assert_eq!(balance_since_checkpoint(None, "0xBob", &transfers), 20.0);
+    }
+
+    #[test]
+    fn no_transfers_no_checkpoints() {
+        assert!(checkpoints(&[], DAILY).is_empty());
+    }
+}
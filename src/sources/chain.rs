@@ -0,0 +1,284 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+use tracing::debug;
+
+use super::{Cursor, Positioned, SourceStream, TransferSource};
+use crate::{
+    chain::{
+        erc20::{Erc20Decoder, TransferLog, TRANSFER_TOPIC},
+        parse_quantity,
+        rpc::{LogFilter, Logs, RpcClient},
+        Log,
+    },
+    errors::{Error, Result},
+};
+
+// Block timestamp lookups in flight at once, for logs that don't carry one
+const TIMESTAMP_LOOKUPS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChainConfig {
+    // JSON-RPC endpoint over HTTP(S)
+    pub url: String,
+    // ERC-20 contract whose transfers are read
+    pub token: String,
+    pub decimals: u8,
+    // Logs carry no prices, every transfer gets this one
+    pub usd_price: f64,
+    pub from_block: u64,
+    // Inclusive, follows the chain head when unset
+    pub to_block: Option<u64>,
+    // Widest range asked for at once, narrowed while the node refuses it
+    pub max_block_range: u64,
+    // Blocks behind the head that are not read yet, so that reorgs rarely reach read logs
+    pub confirmations: u64,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            token: String::new(),
+            decimals: 18,
+            usd_price: 1.0,
+            from_block: 0,
+            to_block: None,
+            max_block_range: 2_000,
+            confirmations: 12,
+            poll_interval_ms: 12_000,
+        }
+    }
+}
+
+impl ChainConfig {
+    pub fn decoder(&self) -> Result<Erc20Decoder> {
+        Ok(Erc20Decoder::new(&self.token, self.decimals)?.with_usd_price(self.usd_price))
+    }
+}
+
+// ERC-20 transfers of one token from `eth_getLogs`, range by range. Cursors name the last
+// log read, resuming asks for its block again and skips logs up to it
+pub struct ChainSource {
+    client: Arc<RpcClient>,
+    decoder: Erc20Decoder,
+    config: ChainConfig,
+}
+
+impl ChainSource {
+    pub fn new(config: ChainConfig) -> Result<Self> {
+        Ok(ChainSource {
+            client: Arc::new(RpcClient::new(&config.url)?),
+            decoder: config.decoder()?,
+            config,
+        })
+    }
+
+    pub fn with_client(mut self, client: RpcClient) -> Self {
+        self.client = Arc::new(client);
+        self
+    }
+}
+
+struct Scan {
+    client: Arc<RpcClient>,
+    decoder: Erc20Decoder,
+    config: ChainConfig,
+    // First block not read yet
+    next: u64,
+    // Last block that may be read, as of the latest head lookup
+    safe: Option<u64>,
+    span: u64,
+    // Logs of the first block up to this index were read by a previous run
+    skip: Option<(u64, u64)>,
+    ready: VecDeque<Result<Positioned>>,
+    failed: bool,
+}
+
+#[async_trait]
+impl TransferSource for ChainSource {
+    async fn open(&self, from: Option<Cursor>) -> Result<SourceStream> {
+        let (next, skip) = match from {
+            None => (self.config.from_block, None),
+            Some(Cursor::Block { number, log_index }) => (number, Some((number, log_index))),
+            Some(cursor) => {
+                return Err(Error::InvalidConfiguration(format!(
+                    "Cursor {:?} does not belong to a chain",
+                    cursor
+                )))
+            }
+        };
+
+        let scan = Scan {
+            client: self.client.clone(),
+            decoder: self.decoder.clone(),
+            span: self.config.max_block_range.max(1),
+            config: self.config.clone(),
+            next,
+            safe: None,
+            skip,
+            ready: VecDeque::new(),
+            failed: false,
+        };
+
+        Ok(stream::unfold(scan, |mut scan| async move {
+            loop {
+                if let Some(transfer) = scan.ready.pop_front() {
+                    return Some((transfer, scan));
+                }
+                if scan.failed || scan.config.to_block.is_some_and(|to| scan.next > to) {
+                    return None;
+                }
+
+                // Nothing else can be read reliably once a range failed
+                if let Err(e) = scan.advance().await {
+                    scan.failed = true;
+                    return Some((Err(e), scan));
+                }
+            }
+        })
+        .boxed())
+    }
+}
+
+impl Scan {
+    // Reads the next range of blocks, or waits for the chain to grow past `next`
+    async fn advance(&mut self) -> Result<()> {
+        let safe = match self.safe {
+            Some(safe) if safe >= self.next => safe,
+            _ => {
+                let head = self.client.block_number().await?;
+                let safe = head.saturating_sub(self.config.confirmations);
+                self.safe = Some(safe);
+
+                if safe < self.next {
+                    debug!(next = self.next, safe, "waiting for new blocks");
+                    sleep(Duration::from_millis(self.config.poll_interval_ms)).await;
+                    return Ok(());
+                }
+                safe
+            }
+        };
+
+        let last = self.config.to_block.map_or(safe, |to| to.min(safe));
+        let to_block = last.min(self.next.saturating_add(self.span - 1));
+        let filter = LogFilter {
+            address: self.decoder.token().to_string(),
+            topic: TRANSFER_TOPIC.to_string(),
+            from_block: self.next,
+            to_block,
+        };
+
+        let logs = match self.client.logs(&filter).await? {
+            Logs::Found(logs) => logs,
+            Logs::TooMany if to_block == self.next => {
+                return Err(Error::io(
+                    format!("eth_getLogs failed for block {}", self.next),
+                    std::io::Error::other("node refuses to return the logs of a single block"),
+                ))
+            }
+            Logs::TooMany => {
+                self.span = (to_block - self.next).div_ceil(2);
+                debug!(
+                    from_block = self.next,
+                    to_block,
+                    span = self.span,
+                    "node limited the response, narrowing the range"
+                );
+                return Ok(());
+            }
+        };
+
+        debug!(
+            from_block = self.next,
+            to_block,
+            logs = logs.len(),
+            "read logs"
+        );
+        self.decode(logs).await?;
+
+        self.next = to_block + 1;
+        // Ranges may fit again once past a busy stretch
+        self.span = self
+            .span
+            .saturating_mul(2)
+            .min(self.config.max_block_range.max(1));
+
+        Ok(())
+    }
+
+    // Malformed logs are queued as errors to be skipped, failed timestamp lookups fail the range
+    async fn decode(&mut self, mut logs: Vec<Log>) -> Result<()> {
+        logs.retain(|log| !log.removed);
+        let timestamps = self.timestamps(&logs).await?;
+        let mut decoded = Vec::with_capacity(logs.len());
+
+        for log in &logs {
+            let timestamp =
+                missing_timestamp_block(log).and_then(|block| timestamps.get(&block).copied());
+
+            match self.decoder.decode(log, timestamp) {
+                Ok(Some(transfer)) => decoded.push(Ok(transfer)),
+                Ok(None) => {}
+                Err(e) => decoded.push(Err(e)),
+            }
+        }
+
+        // Nodes return logs in chain order, but cursors depend on it
+        decoded.sort_by_key(|transfer| {
+            transfer
+                .as_ref()
+                .map(|t| (t.block_number, t.log_index))
+                .unwrap_or_default()
+        });
+
+        let skip = self.skip.take();
+        self.ready
+            .extend(decoded.into_iter().filter_map(|transfer| match transfer {
+                Ok(TransferLog {
+                    block_number,
+                    log_index,
+                    ..
+                }) if skip.is_some_and(|skip| (block_number, log_index) <= skip) => None,
+                Ok(transfer) => Some(Ok(Positioned {
+                    cursor: Cursor::Block {
+                        number: transfer.block_number,
+                        log_index: transfer.log_index,
+                    },
+                    transfer: transfer.transfer,
+                })),
+                Err(e) => Some(Err(e)),
+            }));
+
+        Ok(())
+    }
+
+    // Of the blocks whose logs came without one, looked up concurrently
+    async fn timestamps(&self, logs: &[Log]) -> Result<HashMap<u64, u64>> {
+        let blocks: BTreeSet<u64> = logs.iter().filter_map(missing_timestamp_block).collect();
+
+        stream::iter(blocks)
+            .map(|block| {
+                let client = self.client.clone();
+                async move { Ok((block, client.block_timestamp(block).await?)) }
+            })
+            .buffer_unordered(TIMESTAMP_LOOKUPS)
+            .try_collect()
+            .await
+    }
+}
+
+// Logs with an unparsable block number are left for the decoder to reject
+fn missing_timestamp_block(log: &Log) -> Option<u64> {
+    match (&log.block_timestamp, &log.block_number) {
+        (None, Some(block)) => parse_quantity("blockNumber", block).ok(),
+        _ => None,
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{future, stream, StreamExt};
+use tokio::{io::BufReader, time};
+use tracing::debug;
+
+use super::{files::FileFormat, positioned, Cursor, SourceStream, TransferSource};
+use crate::errors::{Error, Result};
+
+// Watches a directory by polling it. Every file is read once per run, resuming skips the files
+// the cursor lists as done and carries on inside the cursor's one
+pub struct DirectorySource {
+    path: PathBuf,
+    poll_interval: Duration,
+}
+
+impl DirectorySource {
+    pub fn new(path: PathBuf, poll_interval: Duration) -> Self {
+        DirectorySource {
+            path,
+            poll_interval,
+        }
+    }
+}
+
+struct Spool {
+    dir: PathBuf,
+    poll_interval: Duration,
+    // Picked up whenever the cursor's file is found, late arrivals can sort before it
+    resume: Option<(String, u64)>,
+    done: Arc<BTreeSet<String>>,
+    // Complete once the next file is handed out, files are read one after the other
+    reading: Option<String>,
+    scanned: bool,
+    seen: HashSet<PathBuf>,
+    queue: VecDeque<(PathBuf, u64)>,
+}
+
+impl Spool {
+    fn next_file(&mut self) -> Option<(PathBuf, u64, Arc<BTreeSet<String>>)> {
+        let (path, start) = self.queue.pop_front()?;
+
+        if let Some(finished) = self.reading.replace(file_name(&path)) {
+            Arc::make_mut(&mut self.done).insert(finished);
+        }
+
+        Some((path, start, self.done.clone()))
+    }
+
+    fn enqueue(&mut self, found: Vec<PathBuf>) {
+        let names: Vec<String> = found.iter().map(|path| file_name(path)).collect();
+
+        // Files gone from the directory can't be read again, no need to remember them
+        if !self.scanned {
+            self.scanned = true;
+            let present: HashSet<&String> = names.iter().collect();
+            Arc::make_mut(&mut self.done).retain(|name| present.contains(name));
+        }
+
+        for (path, name) in found.into_iter().zip(names) {
+            if self.done.contains(&name) {
+                continue;
+            }
+
+            let start = match &self.resume {
+                Some((resume, offset)) if *resume == name => {
+                    let start = *offset;
+                    self.resume = None;
+                    start
+                }
+                _ => 0,
+            };
+            self.queue.push_back((path, start));
+        }
+    }
+}
+
+#[async_trait]
+impl TransferSource for DirectorySource {
+    async fn open(&self, from: Option<Cursor>) -> Result<SourceStream> {
+        let (resume, done) = match from {
+            None => (None, Arc::default()),
+            Some(Cursor::File { name, offset, done }) => (Some((name, offset)), done),
+            Some(cursor) => {
+                return Err(Error::InvalidConfiguration(format!(
+                    "Cursor {:?} does not belong to a directory",
+                    cursor
+                )))
+            }
+        };
+
+        let spool = Spool {
+            dir: self.path.clone(),
+            poll_interval: self.poll_interval,
+            resume,
+            done,
+            reading: None,
+            scanned: false,
+            seen: HashSet::new(),
+            queue: VecDeque::new(),
+        };
+
+        let files = stream::unfold(spool, |mut spool| async move {
+            loop {
+                if let Some(file) = spool.next_file() {
+                    return Some((Ok(file), spool));
+                }
+
+                match scan(&spool.dir, &mut spool.seen).await {
+                    Ok(found) if found.is_empty() && spool.scanned => {
+                        time::sleep(spool.poll_interval).await
+                    }
+                    Ok(found) => spool.enqueue(found),
+                    Err(e) => return Some((Err(e), spool)),
+                }
+            }
+        });
+
+        Ok(files
+            .then(|file| async move {
+                match file {
+                    Ok((path, start, done)) => read_file(path, start, done).await,
+                    Err(e) => stream::once(future::ready(Err(e))).boxed(),
+                }
+            })
+            .flatten()
+            .boxed())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// Unseen `*.jsonl` and `*.csv` files in name order, marked as seen
+async fn scan(dir: &Path, seen: &mut HashSet<PathBuf>) -> Result<Vec<PathBuf>> {
+    let read_error = |e| Error::io(format!("Could not read {}", dir.display()), e);
+
+    let mut entries = tokio::fs::read_dir(dir).await.map_err(read_error)?;
+    let mut found = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(read_error)? {
+        let path = entry.path();
+        let readable = path
+            .extension()
+            .is_some_and(|ext| ext == "jsonl" || ext == "csv");
+        if readable && !seen.contains(&path) {
+            found.push(path);
+        }
+    }
+
+    found.sort();
+    seen.extend(found.iter().cloned());
+
+    Ok(found)
+}
+
+async fn read_file(path: PathBuf, start: u64, done: Arc<BTreeSet<String>>) -> SourceStream {
+    debug!(path = %path.display(), start, "reading transfers file");
+
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(e) => {
+            let error = Error::io(format!("Could not open {}", path.display()), e);
+            return stream::once(future::ready(Err(error))).boxed();
+        }
+    };
+
+    let name = file_name(&path);
+    let transfers = FileFormat::of(&path)
+        .read(BufReader::new(file))
+        .map(move |transfer| {
+            transfer.map_err(|e| match e {
+                Error::ValidationFailed(message) => {
+                    Error::ValidationFailed(format!("{}: {}", path.display(), message))
+                }
+                e => e,
+            })
+        });
+
+    positioned(transfers, start, move |offset| Cursor::File {
+        name: name.clone(),
+        offset,
+        done: done.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(ts: u64) -> String {
+        format!(
+            "{{\"ts\":{},\"from\":\"0xA\",\"to\":\"0xB\",\"amount\":1.0,\"usd_price\":1.0}}\n",
+            ts
+        )
+    }
+
+    async fn next_ts(transfers: &mut SourceStream) -> Result<u64> {
+        match transfers.next().await {
+            Some(transfer) => Ok(transfer?.transfer.ts),
+            None => Err(Error::ValidationFailed("source ended".to_string())),
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_files_in_order_then_new_ones() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("2.jsonl"), line(2))?;
+        std::fs::write(dir.path().join("1.jsonl"), line(1))?;
+        std::fs::write(dir.path().join("ignored.tmp"), line(9))?;
+
+        let source = DirectorySource::new(dir.path().to_path_buf(), Duration::from_millis(5));
+        let mut transfers = source.open(None).await?;
+
+        assert_eq!(next_ts(&mut transfers).await?, 1);
+        assert_eq!(next_ts(&mut transfers).await?, 2);
+
+        std::fs::write(dir.path().join("0.jsonl"), format!("{}not json\n", line(3)))?;
+        assert_eq!(next_ts(&mut transfers).await?, 3);
+
+        let Err(Error::ValidationFailed(message)) = next_ts(&mut transfers).await else {
+            anyhow::bail!("expected the malformed line to fail");
+        };
+        assert!(message.contains("0.jsonl"), "{}", message);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resumes_inside_the_cursor_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("a.jsonl"), line(1))?;
+        std::fs::write(
+            dir.path().join("b.jsonl"),
+            format!("{}{}", line(2), line(3)),
+        )?;
+        std::fs::write(
+            dir.path().join("c.csv"),
+            "ts,from,to,amount,usd_price\n4,0xA,0xB,1,1\n",
+        )?;
+
+        let source = DirectorySource::new(dir.path().to_path_buf(), Duration::from_millis(5));
+        let mut transfers = source
+            .open(Some(Cursor::File {
+                name: "b.jsonl".to_string(),
+                offset: 1,
+                done: Arc::new(BTreeSet::from(["a.jsonl".to_string()])),
+            }))
+            .await?;
+
+        assert_eq!(next_ts(&mut transfers).await?, 3);
+        let Some(Ok(last)) = transfers.next().await else {
+            anyhow::bail!("expected the csv transfer");
+        };
+        assert_eq!(last.transfer.ts, 4);
+        assert_eq!(
+            last.cursor,
+            Cursor::File {
+                name: "c.csv".to_string(),
+                offset: 1,
+                done: Arc::new(BTreeSet::from([
+                    "a.jsonl".to_string(),
+                    "b.jsonl".to_string()
+                ])),
+            }
+        );
+
+        Ok(())
+    }
+
+    async fn next_cursor(transfers: &mut SourceStream) -> Result<Cursor> {
+        match transfers.next().await {
+            Some(transfer) => Ok(transfer?.cursor),
+            None => Err(Error::ValidationFailed("source ended".to_string())),
+        }
+    }
+
+    #[tokio::test]
+    async fn restart_after_a_late_file_neither_rereads_nor_skips() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = DirectorySource::new(dir.path().to_path_buf(), Duration::from_millis(5));
+        std::fs::write(dir.path().join("b.jsonl"), line(1))?;
+
+        let mut transfers = source.open(None).await?;
+        next_cursor(&mut transfers).await?;
+
+        // Arrives after b.jsonl was read although its name sorts before it
+        std::fs::write(dir.path().join("a.jsonl"), line(2))?;
+        let cursor = next_cursor(&mut transfers).await?;
+        drop(transfers);
+
+        std::fs::write(dir.path().join("0.jsonl"), line(3))?;
+        let mut transfers = source.open(Some(cursor)).await?;
+        assert_eq!(next_ts(&mut transfers).await?, 3);
+
+        std::fs::write(dir.path().join("c.jsonl"), line(4))?;
+        assert_eq!(next_ts(&mut transfers).await?, 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reads_an_unread_file_that_sorts_before_the_cursor() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = DirectorySource::new(dir.path().to_path_buf(), Duration::from_millis(5));
+        std::fs::write(dir.path().join("b.jsonl"), line(1))?;
+
+        let mut transfers = source.open(None).await?;
+        let cursor = next_cursor(&mut transfers).await?;
+        drop(transfers);
+
+        std::fs::write(
+            dir.path().join("a.jsonl"),
+            format!("{}{}", line(2), line(3)),
+        )?;
+        let mut transfers = source.open(Some(cursor)).await?;
+        assert_eq!(next_ts(&mut transfers).await?, 2);
+        assert_eq!(next_ts(&mut transfers).await?, 3);
+
+        std::fs::write(dir.path().join("c.jsonl"), line(4))?;
+        assert_eq!(next_ts(&mut transfers).await?, 4);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use csv::StringRecord;
+use futures::{stream, Stream, StreamExt};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+use super::{jsonl::read_transfers, offset, positioned, Cursor, SourceStream, TransferSource};
+use crate::{
+    errors::{Error, Result},
+    models::transfer::Transfer,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Jsonl,
+    // With a `ts,from,to,amount,usd_price` header row, in any column order
+    Csv,
+}
+
+impl FileFormat {
+    // CSV for `.csv`, JSON lines otherwise
+    pub fn of(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => FileFormat::Csv,
+            _ => FileFormat::Jsonl,
+        }
+    }
+
+    pub fn read<R>(self, reader: R) -> stream::BoxStream<'static, Result<Transfer>>
+    where
+        R: AsyncBufRead + Unpin + Send + 'static,
+    {
+        match self {
+            FileFormat::Jsonl => read_transfers(reader).boxed(),
+            FileFormat::Csv => read_csv_transfers(reader).boxed(),
+        }
+    }
+}
+
+pub struct FileSource {
+    path: PathBuf,
+    format: FileFormat,
+}
+
+impl FileSource {
+    pub fn new(path: PathBuf) -> Self {
+        FileSource {
+            format: FileFormat::of(&path),
+            path,
+        }
+    }
+}
+
+#[async_trait]
+impl TransferSource for FileSource {
+    async fn open(&self, from: Option<Cursor>) -> Result<SourceStream> {
+        let file = tokio::fs::File::open(&self.path)
+            .await
+            .map_err(|e| Error::io(format!("Could not open {}", self.path.display()), e))?;
+
+        Ok(positioned(
+            self.format.read(BufReader::new(file)),
+            offset(from)?,
+            Cursor::Offset,
+        ))
+    }
+}
+
+// JSON lines. Resuming skips as many lines as were consumed, the same input has to be piped in
+pub struct StdinSource;
+
+#[async_trait]
+impl TransferSource for StdinSource {
+    async fn open(&self, from: Option<Cursor>) -> Result<SourceStream> {
+        Ok(positioned(
+            read_transfers(BufReader::new(tokio::io::stdin())),
+            offset(from)?,
+            Cursor::Offset,
+        ))
+    }
+}
+
+// One record per line, quoted fields may not span lines. Blank lines are skipped, malformed
+// records fail with their line number
+pub fn read_csv_transfers<R>(reader: R) -> impl Stream<Item = Result<Transfer>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    stream::unfold(
+        (reader.lines(), None::<StringRecord>, 0usize),
+        |(mut lines, mut header, mut line_no)| async move {
+            loop {
+                line_no += 1;
+
+                let line = match lines.next_line().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => return None,
+                    Err(e) => {
+                        return Some((
+                            Err(Error::io("Could not read transfers", e)),
+                            (lines, header, line_no),
+                        ))
+                    }
+                };
+
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let invalid = |e: csv::Error| {
+                    Error::ValidationFailed(format!("Invalid transfer on line {}: {}", line_no, e))
+                };
+
+                let record = match parse_record(&line) {
+                    Ok(record) => record,
+                    Err(e) => return Some((Err(invalid(e)), (lines, header, line_no))),
+                };
+
+                let Some(columns) = &header else {
+                    header = Some(record);
+                    continue;
+                };
+
+                let transfer = record.deserialize(Some(columns)).map_err(invalid);
+
+                return Some((transfer, (lines, header, line_no)));
+            }
+        },
+    )
+}
+
+fn parse_record(line: &str) -> std::result::Result<StringRecord, csv::Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_reader(line.as_bytes());
+
+    reader
+        .records()
+        .next()
+        .unwrap_or_else(|| Ok(StringRecord::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_csv_with_a_header_in_any_order() -> Result<()> {
+        let input = "from,to,ts,amount,usd_price\n0xA,0xB,1,2.5,1.0\n\n\"0xB\",0xC,2,1,3\n";
+
+        let transfers: Vec<Transfer> = read_csv_transfers(input.as_bytes()).try_collect().await?;
+
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(
+            transfers[0],
+            Transfer {
+                ts: 1,
+                from: "0xA".to_string(),
+                to: "0xB".to_string(),
+                amount: 2.5,
+                usd_price: 1.0,
+            }
+        );
+        assert_eq!(transfers[1].from, "0xB");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reports_malformed_csv_lines() {
+        let input = "ts,from,to,amount,usd_price\n1,0xA,0xB,lots,1.0\n";
+
+        let res: Result<Vec<Transfer>> = read_csv_transfers(input.as_bytes()).try_collect().await;
+
+        assert!(matches!(res, Err(Error::ValidationFailed(m)) if m.contains("line 2")));
+    }
+
+    #[tokio::test]
+    async fn files_resume_after_the_last_consumed_record() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("transfers.csv");
+        std::fs::write(
+            &path,
+            "ts,from,to,amount,usd_price\n1,0xA,0xB,1,1\n2,0xA,0xB,1,1\n3,0xA,0xB,1,1\n",
+        )?;
+        let source = FileSource::new(path);
+
+        let first: Vec<_> = source.open(None).await?.take(2).try_collect().await?;
+        let rest: Vec<_> = source
+            .open(Some(first[1].cursor.clone()))
+            .await?
+            .try_collect()
+            .await?;
+
+        assert_eq!(first[1].cursor, Cursor::Offset(2));
+        let ts: Vec<u64> = rest.iter().map(|p| p.transfer.ts).collect();
+        assert_eq!(ts, [3]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+
+use super::{offset, positioned, Cursor, SourceStream, TransferSource};
+use crate::{
+    errors::Result, factories::generator::DefaultTransferGenerator,
+    factories::stream::StreamsTransfers,
+};
+
+// Resuming skips the transfers emitted before, which continues the same sequence only for
+// seeded generators
+pub struct GeneratorSource {
+    generator: DefaultTransferGenerator,
+    // Transfers per second of wall time, as fast as they are read when unset
+    pace: Option<f64>,
+}
+
+impl GeneratorSource {
+    pub fn new(generator: DefaultTransferGenerator) -> Self {
+        GeneratorSource {
+            generator,
+            pace: None,
+        }
+    }
+
+    // Emits the configured `stream_rate` per second, stamped with the clock's now
+    pub fn paced(mut self) -> Self {
+        self.pace = Some(self.generator.config.stream_rate);
+        self
+    }
+}
+
+#[async_trait]
+impl TransferSource for GeneratorSource {
+    async fn open(&self, from: Option<Cursor>) -> Result<SourceStream> {
+        let start = offset(from)?;
+
+        // Skipped on the iterator, a paced stream would wait out every skipped transfer
+        let mut transfers = self.generator.stream()?;
+        transfers.by_ref().take(start as usize).for_each(drop);
+
+        let transfers = match self.pace {
+            Some(rate) => transfers.paced(rate)?.boxed(),
+            None => stream::iter(transfers.map(Ok)).boxed(),
+        };
+
+        Ok(positioned(transfers, 0, move |n| Cursor::Offset(start + n)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::factories::defaults::generator;
+    use crate::utils::time::FixedClock;
+
+    #[tokio::test]
+    async fn seeded_generators_resume_where_they_stopped() -> Result<()> {
+        let source = generator()
+            .with_seed(5)
+            .with_clock(FixedClock(1_700_000_000))
+            .source();
+
+        let all: Vec<_> = source.open(None).await?.take(10).try_collect().await?;
+        let resumed: Vec<_> = source
+            .open(Some(all[5].cursor.clone()))
+            .await?
+            .take(4)
+            .try_collect()
+            .await?;
+
+        assert_eq!(resumed, all[6..]);
+        assert_eq!(resumed[3].cursor, Cursor::Offset(10));
+
+        Ok(())
+    }
+}
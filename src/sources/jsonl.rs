@@ -0,0 +1,107 @@
+use std::io::Write;
+
+use futures::{stream, Stream};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::{
+    errors::{Error, Result},
+    models::transfer::Transfer,
+};
+
+pub fn write_transfers(
+    out: &mut impl Write,
+    transfers: impl IntoIterator<Item = Transfer>,
+) -> Result<()> {
+    for transfer in transfers {
+        serde_json::to_writer(&mut *out, &transfer)
+            .map_err(|e| Error::io("Could not write transfers", e.into()))?;
+        out.write_all(b"\n")
+            .map_err(|e| Error::io("Could not write transfers", e))?;
+    }
+
+    out.flush()
+        .map_err(|e| Error::io("Could not write transfers", e))
+}
+
+// One transfer per line, blank lines are skipped. Malformed lines fail with their line number
+pub fn read_transfers<R>(reader: R) -> impl Stream<Item = Result<Transfer>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    stream::unfold(
+        (reader.lines(), 0usize),
+        |(mut lines, mut line_no)| async move {
+            loop {
+                line_no += 1;
+
+                let line = match lines.next_line().await {
+                    Ok(Some(line)) => line,
+                    Ok(None) => return None,
+                    Err(e) => {
+                        return Some((
+                            Err(Error::io("Could not read transfers", e)),
+                            (lines, line_no),
+                        ))
+                    }
+                };
+
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let transfer = serde_json::from_str(&line).map_err(|e| {
+                    Error::ValidationFailed(format!("Invalid transfer on line {}: {}", line_no, e))
+                });
+
+                return Some((transfer, (lines, line_no)));
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn round_trips_transfers() -> Result<()> {
+        let transfers = vec![
+            Transfer {
+                ts: 1,
+                from: "0xA".to_string(),
+                to: "0xB".to_string(),
+                amount: 1.5,
+                usd_price: 2.0,
+            },
+            Transfer::default(),
+        ];
+
+        let mut buffer = vec![];
+        write_transfers(&mut buffer, transfers.clone())?;
+
+        let read: Vec<Transfer> = read_transfers(buffer.as_slice())
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+
+        assert_eq!(read, transfers);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reports_the_malformed_line() {
+        let input = b"{\"ts\":1,\"from\":\"a\",\"to\":\"b\",\"amount\":1.0,\"usd_price\":1.0}\n\nnot json\n";
+
+        let read: Vec<Result<Transfer>> = read_transfers(&input[..]).collect().await;
+
+        assert!(read[0].is_ok());
+        assert!(matches!(
+            &read[1],
+            Err(Error::ValidationFailed(message)) if message.contains("line 3")
+        ));
+    }
+}
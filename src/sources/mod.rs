@@ -0,0 +1,231 @@
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{stream::BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{Error, Result},
+    factories::generator::{DefaultTransferGenerator, TransferGenConfig},
+    models::transfer::Transfer,
+    utils::time::SystemNow,
+};
+
+pub mod chain;
+pub mod directory;
+pub mod files;
+pub mod generator;
+pub mod jsonl;
+pub mod tcp;
+
+pub use chain::{ChainConfig, ChainSource};
+pub use directory::DirectorySource;
+pub use files::{FileFormat, FileSource, StdinSource};
+pub use generator::GeneratorSource;
+pub use tcp::TcpSource;
+
+// How far a source got. Only meaningful to the kind of source that produced it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cursor {
+    // Records consumed from the start of a single stream
+    Offset(u64),
+    // Records consumed from one file of a directory and the files fully read before it,
+    // whatever their names. Shared since every transfer of the file carries it
+    File {
+        name: String,
+        offset: u64,
+        #[serde(default)]
+        done: Arc<BTreeSet<String>>,
+    },
+    // Logs of a chain up to this one, in block and log index order
+    Block {
+        number: u64,
+        log_index: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Positioned {
+    pub transfer: Transfer,
+    // Resumes right after this transfer
+    pub cursor: Cursor,
+}
+
+pub type SourceStream = BoxStream<'static, Result<Positioned>>;
+
+// Anything transfers can be read from. Consumers store the cursor of the last transfer they
+// durably handled and pass it back to `open` to carry on after it
+#[async_trait]
+pub trait TransferSource: Send + Sync {
+    async fn open(&self, from: Option<Cursor>) -> Result<SourceStream>;
+}
+
+#[async_trait]
+impl<T: TransferSource + ?Sized> TransferSource for Box<T> {
+    async fn open(&self, from: Option<Cursor>) -> Result<SourceStream> {
+        (**self).open(from).await
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum SourceConfig {
+    // Paced at the generator's `stream_rate`, never ends
+    #[default]
+    Generator,
+    // JSON lines until end of input
+    Stdin,
+    // JSON lines, or CSV with a header row when the extension is `.csv`
+    File {
+        path: PathBuf,
+    },
+    // Every `*.jsonl` and `*.csv` file in the directory in name order, then new ones as they
+    // appear. Write files under another name and rename them in once complete
+    Directory {
+        path: PathBuf,
+        #[serde(default = "SourceConfig::default_poll_interval_ms")]
+        poll_interval_ms: u64,
+    },
+    // JSON lines from any number of concurrent connections, never ends
+    Tcp {
+        addr: SocketAddr,
+    },
+    // ERC-20 transfer logs from a JSON-RPC node, follows the chain head unless `to_block` is set
+    Chain(ChainConfig),
+}
+
+impl SourceConfig {
+    pub const DEFAULT_POLL_INTERVAL_MS: u64 = 1_000;
+
+    fn default_poll_interval_ms() -> u64 {
+        Self::DEFAULT_POLL_INTERVAL_MS
+    }
+
+    pub fn source(&self, generator: &TransferGenConfig) -> Result<Box<dyn TransferSource>> {
+        Ok(match self {
+            SourceConfig::Generator => Box::new(
+                GeneratorSource::new(DefaultTransferGenerator {
+                    config: generator.clone(),
+                    clock: Arc::new(SystemNow),
+                })
+                .paced(),
+            ),
+            SourceConfig::Stdin => Box::new(StdinSource),
+            SourceConfig::File { path } => Box::new(FileSource::new(path.clone())),
+            SourceConfig::Directory {
+                path,
+                poll_interval_ms,
+            } => Box::new(DirectorySource::new(
+                path.clone(),
+                Duration::from_millis(*poll_interval_ms),
+            )),
+            SourceConfig::Tcp { addr } => Box::new(TcpSource::new(*addr)),
+            SourceConfig::Chain(config) => Box::new(ChainSource::new(config.clone())?),
+        })
+    }
+}
+
+// Where the previous run stopped, `None` before the first checkpoint
+pub fn load_checkpoint(path: &Path) -> Result<Option<Cursor>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::io(format!("Could not read {}", path.display()), e)),
+    };
+
+    serde_json::from_str(&contents).map(Some).map_err(|e| {
+        Error::InvalidConfiguration(format!("Invalid checkpoint {}: {}", path.display(), e))
+    })
+}
+
+// Written aside and renamed over, a crash leaves either the old checkpoint or the new one
+pub fn save_checkpoint(path: &Path, cursor: &Cursor) -> Result<()> {
+    let partial = path.with_extension("partial");
+    let contents = serde_json::to_string(cursor)
+        .map_err(|e| Error::io("Could not serialize checkpoint", e.into()))?;
+
+    std::fs::write(&partial, contents)
+        .map_err(|e| Error::io(format!("Could not write {}", partial.display()), e))?;
+    std::fs::rename(&partial, path)
+        .map_err(|e| Error::io(format!("Could not replace {}", path.display()), e))
+}
+
+// Start of a single stream source
+fn offset(from: Option<Cursor>) -> Result<u64> {
+    match from {
+        None => Ok(0),
+        Some(Cursor::Offset(offset)) => Ok(offset),
+        Some(cursor) => Err(Error::InvalidConfiguration(format!(
+            "Cursor {:?} belongs to another kind of source",
+            cursor
+        ))),
+    }
+}
+
+// Skips the `start` records a previous run consumed and numbers the rest after them.
+// Malformed records count too, so resuming never lands on a different line
+fn positioned<T>(
+    records: T,
+    start: u64,
+    cursor: impl Fn(u64) -> Cursor + Send + 'static,
+) -> SourceStream
+where
+    T: Stream<Item = Result<Transfer>> + Send + 'static,
+{
+    records
+        .skip(start as usize)
+        .enumerate()
+        .map(move |(index, record)| {
+            record.map(|transfer| Positioned {
+                transfer,
+                cursor: cursor(start + index as u64 + 1),
+            })
+        })
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoints_round_trip() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("checkpoint.json");
+
+        assert_eq!(load_checkpoint(&path)?, None);
+
+        let cursor = Cursor::File {
+            name: "b.jsonl".to_string(),
+            offset: 3,
+            done: Arc::new(BTreeSet::from(["a.jsonl".to_string()])),
+        };
+        save_checkpoint(&path, &cursor)?;
+        save_checkpoint(&path, &cursor)?;
+
+        assert_eq!(load_checkpoint(&path)?, Some(cursor));
+        assert!(!path.with_extension("partial").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_cursors_of_other_sources() {
+        let cursor = Cursor::File {
+            name: "a.jsonl".to_string(),
+            offset: 1,
+            done: Default::default(),
+        };
+
+        assert!(matches!(
+            offset(Some(cursor)),
+            Err(Error::InvalidConfiguration(_))
+        ));
+        assert_eq!(offset(Some(Cursor::Offset(4))).ok(), Some(4));
+    }
+}
@@ -0,0 +1,130 @@
+use std::net::SocketAddr;
+
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use tokio::{io::BufReader, net::TcpListener, sync::mpsc};
+use tracing::{debug, warn};
+
+use super::{jsonl::read_transfers, offset, positioned, Cursor, SourceStream, TransferSource};
+use crate::{
+    errors::{Error, Result},
+    models::transfer::Transfer,
+};
+
+// Transfers read from clients but not yet taken by the consumer. Clients are slowed down
+// rather than buffered without bound
+const BUFFER: usize = 10_000;
+
+// JSON lines from any number of concurrent connections. Nothing can be replayed, resuming
+// just carries on counting
+pub struct TcpSource {
+    addr: SocketAddr,
+}
+
+impl TcpSource {
+    pub fn new(addr: SocketAddr) -> Self {
+        TcpSource { addr }
+    }
+}
+
+#[async_trait]
+impl TransferSource for TcpSource {
+    async fn open(&self, from: Option<Cursor>) -> Result<SourceStream> {
+        let listener = TcpListener::bind(self.addr)
+            .await
+            .map_err(|e| Error::io(format!("Could not bind {}", self.addr), e))?;
+
+        Ok(listen(listener, offset(from)?))
+    }
+}
+
+fn listen(listener: TcpListener, received: u64) -> SourceStream {
+    let (sender, receiver) = mpsc::channel(BUFFER);
+
+    tokio::spawn(accept(listener, sender));
+
+    let transfers = stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|transfer| (transfer, receiver))
+    });
+
+    positioned(transfers, 0, move |n| Cursor::Offset(received + n))
+}
+
+// Runs until the stream is dropped. A broken connection only ends that connection
+async fn accept(listener: TcpListener, sender: mpsc::Sender<Result<Transfer>>) {
+    loop {
+        let (socket, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!(error = %e, "could not accept connection");
+                    continue;
+                }
+            },
+            _ = sender.closed() => return,
+        };
+
+        debug!(%peer, "transfers connection opened");
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            let mut transfers = std::pin::pin!(read_transfers(BufReader::new(socket)));
+
+            while let Some(transfer) = transfers.next().await {
+                if let Err(e @ Error::Io { .. }) = &transfer {
+                    warn!(%peer, error = %e, "transfers connection failed");
+                    return;
+                }
+                if sender.send(transfer).await.is_err() {
+                    return;
+                }
+            }
+
+            debug!(%peer, "transfers connection closed");
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    fn line(ts: u64) -> String {
+        format!(
+            "{{\"ts\":{},\"from\":\"0xA\",\"to\":\"0xB\",\"amount\":1.0,\"usd_price\":1.0}}\n",
+            ts
+        )
+    }
+
+    #[tokio::test]
+    async fn merges_connections() -> anyhow::Result<()> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+        let addr = listener.local_addr()?;
+        let mut transfers = listen(listener, 5);
+
+        let mut first = TcpStream::connect(addr).await?;
+        let mut second = TcpStream::connect(addr).await?;
+        first.write_all(line(1).as_bytes()).await?;
+        second.write_all(line(2).as_bytes()).await?;
+        drop(first);
+        drop(second);
+
+        let mut received = vec![];
+        let mut cursors = vec![];
+        for _ in 0..2 {
+            if let Some(positioned) = transfers.next().await {
+                let positioned = positioned?;
+                received.push(positioned.transfer.ts);
+                cursors.push(positioned.cursor);
+            }
+        }
+        received.sort();
+
+        assert_eq!(received, [1, 2]);
+        assert_eq!(cursors, [Cursor::Offset(6), Cursor::Offset(7)]);
+
+        Ok(())
+    }
+}
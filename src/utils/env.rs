@@ -1,7 +1,10 @@
-use anyhow::{Context, Result};
+use crate::errors::{Error, Result};
 
 pub fn env_get(key: &str) -> Result<String> {
-    let v = std::env::var(key).context(format!("Env variable with key `{}` not found. ", key))?;
+    let v = std::env::var(key).map_err(|e| Error::ConfigurationMissing {
+        key: key.to_string(),
+        source: Some(Box::new(e)),
+    })?;
 
     Ok(v)
 }
@@ -1,2 +1,4 @@
 pub mod env;
+pub mod shutdown;
+pub mod telemetry;
 pub mod time;
@@ -0,0 +1,120 @@
+use std::str::FromStr;
+
+use tracing_subscriber::{fmt, fmt::format::FmtSpan, EnvFilter};
+
+use crate::errors::{Error, Result};
+
+const DEFAULT_LEVEL: &str = "info";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" | "text" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(Error::InvalidConfiguration(format!(
+                "Unknown log format `{}`, expected `json` or `pretty`",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryConfig {
+    pub format: LogFormat,
+    // An `EnvFilter` directive such as `info` or `rust_challenge=debug,clickhouse=warn`
+    pub filter: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig {
+            format: LogFormat::default(),
+            filter: DEFAULT_LEVEL.to_string(),
+        }
+    }
+}
+
+impl TelemetryConfig {
+    // LOG_FORMAT picks the output, RUST_LOG wins over the plainer LOG_LEVEL
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let format = match var("LOG_FORMAT") {
+            Some(format) => format.parse()?,
+            None => LogFormat::default(),
+        };
+
+        let filter = var("RUST_LOG")
+            .or_else(|| var("LOG_LEVEL"))
+            .unwrap_or_else(|| DEFAULT_LEVEL.to_string());
+
+        Ok(TelemetryConfig { format, filter })
+    }
+}
+
+// Installs the global subscriber, fails if one is already set
+pub fn init(config: &TelemetryConfig) -> Result<()> {
+    let filter = EnvFilter::try_new(&config.filter).map_err(|e| {
+        Error::InvalidConfiguration(format!("Invalid log filter `{}`: {}", config.filter, e))
+    })?;
+
+    // Closing spans report their busy and idle time, which is what we want when a run is slow
+    let builder = fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+
+    let res = match config.format {
+        LogFormat::Pretty => builder.try_init(),
+        LogFormat::Json => builder.json().flatten_event(true).try_init(),
+    };
+
+    res.map_err(|e| Error::InvalidConfiguration(format!("Could not install logging: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn from(vars: &[(&str, &str)]) -> Result<TelemetryConfig> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        TelemetryConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn reads_format_and_level_from_env() -> Result<()> {
+        assert_eq!(from(&[])?, TelemetryConfig::default());
+
+        let config = from(&[("LOG_FORMAT", "JSON"), ("LOG_LEVEL", "debug")])?;
+        assert_eq!(config.format, LogFormat::Json);
+        assert_eq!(config.filter, "debug");
+
+        let config = from(&[("LOG_LEVEL", "debug"), ("RUST_LOG", "rust_challenge=trace")])?;
+        assert_eq!(config.filter, "rust_challenge=trace");
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(from(&[("LOG_FORMAT", "xml")]).is_err());
+    }
+}
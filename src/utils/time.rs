@@ -1,19 +1,27 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::anyhow;
+use crate::errors::{Error, Result};
 
-pub trait Now {
-    fn now_unix() -> anyhow::Result<u64>;
+pub trait Now: Send + Sync {
+    fn now_unix(&self) -> Result<u64>;
 }
 
 pub struct SystemNow;
 
 impl Now for SystemNow {
-    fn now_unix() -> anyhow::Result<u64> {
+    fn now_unix(&self) -> Result<u64> {
         let duration = SystemTime::now()
             .duration_since(UNIX_EPOCH)
-            .map_err(|_| anyhow!("System time is misconfiged"))?;
+            .map_err(|_| Error::InvalidConfiguration("System time is misconfiged".to_string()))?;
 
         Ok(duration.as_secs())
     }
 }
+
+pub struct FixedClock(pub u64);
+
+impl Now for FixedClock {
+    fn now_unix(&self) -> Result<u64> {
+        Ok(self.0)
+    }
+}
@@ -1,19 +1,19 @@
 use anyhow::Result;
 use rust_challenge::{
     app::App, factories::defaults::generator, repositories::mock::MockStorage,
-    services::stats::calculator::StatsCalculator,
+    services::stats::calculator::StatsCalculator, sources::FileSource,
 };
 
 #[tokio::test]
 async fn app_runs() -> Result<()> {
-    let generator = generator().build();
+    let source = Box::new(generator().source());
     let storage = MockStorage::default();
     let calculator = StatsCalculator::new();
 
     let app = App {
         storage,
         calculator,
-        generator,
+        source,
     };
 
     let stats = app.run(10).await?;
@@ -22,3 +22,28 @@ async fn app_runs() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn app_runs_from_any_source() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("transfers.csv");
+    std::fs::write(
+        &path,
+        "ts,from,to,amount,usd_price\n1,0xA,0xB,2,1.5\n2,0xB,0xC,1,2\n3,0xC,0xA,1,3\n",
+    )?;
+
+    let app = App {
+        storage: MockStorage::default(),
+        calculator: StatsCalculator::new(),
+        source: Box::new(FileSource::new(path)),
+    };
+
+    let mut stats = app.run(2).await?;
+    stats.sort_by(|a, b| a.address.cmp(&b.address));
+
+    let addresses: Vec<&str> = stats.iter().map(|s| s.address.as_str()).collect();
+    assert_eq!(addresses, ["0xA", "0xB", "0xC"]);
+    assert_eq!(stats[1].max_balance, 2.0);
+
+    Ok(())
+}
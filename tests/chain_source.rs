@@ -0,0 +1,292 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use futures::{StreamExt, TryStreamExt};
+use rust_challenge::{
+    chain::{parse_logs, parse_quantity, rpc::RpcClient, Log},
+    errors::Error,
+    repositories::retry::RetryPolicy,
+    sources::{ChainConfig, ChainSource, Cursor, TransferSource},
+};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+const BLOCK: u64 = 19_000_000;
+
+// Stands in for an Ethereum node, answering from recorded responses
+struct Node {
+    logs: Vec<Log>,
+    blocks: Vec<Value>,
+    head: AtomicU64,
+    // Refuses `eth_getLogs` ranges with more logs than this, like Infura does past 10k
+    max_logs: usize,
+    // Requests answered with 503 before the node starts working
+    outages: AtomicUsize,
+    // `eth_getLogs` calls answered with Infura's rate limit error before the node lets them through
+    throttled: AtomicUsize,
+    ranges: Mutex<Vec<(u64, u64)>>,
+}
+
+impl Node {
+    fn new(max_logs: usize) -> Result<Self> {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let blocks: Value =
+            serde_json::from_str(&std::fs::read_to_string(fixtures.join("eth_blocks.json"))?)?;
+
+        Ok(Node {
+            logs: parse_logs(&std::fs::read_to_string(fixtures.join("erc20_logs.json"))?)?,
+            blocks: blocks.as_array().cloned().unwrap_or_default(),
+            head: AtomicU64::new(BLOCK + 4),
+            max_logs,
+            outages: AtomicUsize::new(0),
+            throttled: AtomicUsize::new(0),
+            ranges: Mutex::new(vec![]),
+        })
+    }
+
+    fn ranges(&self) -> Vec<(u64, u64)> {
+        self.ranges.lock().unwrap().clone()
+    }
+
+    fn answer(&self, method: &str, params: &Value) -> Result<Value, Value> {
+        match method {
+            "eth_blockNumber" => Ok(json!(format!("{:#x}", self.head.load(Ordering::SeqCst)))),
+            "eth_getBlockByNumber" => Ok(self
+                .blocks
+                .iter()
+                .map(|response| &response["result"])
+                .find(|block| block["number"] == params[0])
+                .cloned()
+                .unwrap_or(Value::Null)),
+            "eth_getLogs" if countdown(&self.throttled) => Err(json!({
+                "code": -32005,
+                "message": "project ID request rate exceeded",
+            })),
+            "eth_getLogs" => self.logs(&params[0]),
+            _ => Err(json!({"code": -32601, "message": "method not found"})),
+        }
+    }
+
+    fn logs(&self, filter: &Value) -> Result<Value, Value> {
+        let block = |field: &str| parse_quantity(field, filter[field].as_str().unwrap_or(""));
+        let (Ok(from), Ok(to)) = (block("fromBlock"), block("toBlock")) else {
+            return Err(json!({"code": -32602, "message": "invalid block range"}));
+        };
+        self.ranges.lock().unwrap().push((from, to));
+
+        let address = filter["address"].as_str().unwrap_or("");
+        let topic = &filter["topics"][0];
+        let logs: Vec<&Log> = self
+            .logs
+            .iter()
+            .filter(|log| {
+                let number =
+                    parse_quantity("blockNumber", log.block_number.as_deref().unwrap()).unwrap();
+                log.address.eq_ignore_ascii_case(address)
+                    && json!(log.topics[0]) == *topic
+                    && (from..=to).contains(&number)
+            })
+            .collect();
+
+        if logs.len() > self.max_logs {
+            return Err(json!({
+                "code": -32005,
+                "message": format!("query returned more than {} results", self.max_logs),
+            }));
+        }
+
+        Ok(json!(logs))
+    }
+}
+
+async fn rpc(
+    State(node): State<Arc<Node>>,
+    Json(request): Json<Value>,
+) -> (StatusCode, Json<Value>) {
+    if countdown(&node.outages) {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(Value::Null));
+    }
+
+    let id = request["id"].clone();
+    let method = request["method"].as_str().unwrap_or("");
+    let response = match node.answer(method, &request["params"]) {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(error) => json!({"jsonrpc": "2.0", "id": id, "error": error}),
+    };
+
+    (StatusCode::OK, Json(response))
+}
+
+// Takes one off a positive counter
+fn countdown(counter: &AtomicUsize) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+// Serves `node` on an ephemeral port for the lifetime of the test runtime
+async fn serve(node: Arc<Node>) -> Result<String> {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+    let addr = listener.local_addr()?;
+    let router = Router::new().route("/", post(rpc)).with_state(node);
+    tokio::spawn(async move { axum::serve(listener, router).await });
+
+    Ok(format!("http://{}", addr))
+}
+
+fn config(url: String) -> ChainConfig {
+    ChainConfig {
+        url,
+        token: USDC.to_string(),
+        decimals: 6,
+        from_block: BLOCK,
+        to_block: Some(BLOCK + 2),
+        max_block_range: 10,
+        confirmations: 2,
+        poll_interval_ms: 10,
+        ..ChainConfig::default()
+    }
+}
+
+fn quick_retries(url: &str) -> Result<RpcClient> {
+    Ok(RpcClient::new(url)?.with_retry(RetryPolicy {
+        initial_backoff: Duration::from_millis(1),
+        jitter: 0.0,
+        ..RetryPolicy::default()
+    }))
+}
+
+#[tokio::test]
+async fn reads_transfers_narrowing_refused_ranges() -> Result<()> {
+    let node = Arc::new(Node::new(2)?);
+    let source = ChainSource::new(config(serve(node.clone()).await?))?;
+
+    let read: Vec<_> = source.open(None).await?.try_collect().await?;
+
+    let transfers: Vec<_> = read
+        .iter()
+        .map(|p| (p.transfer.ts, p.transfer.amount))
+        .collect();
+    assert_eq!(
+        transfers,
+        [
+            (1_705_305_995, 2_500.5),
+            (1_705_305_995, 1_000_000.0),
+            // Timestamp of a block the logs did not carry one for
+            (1_705_306_019, 0.000001),
+        ]
+    );
+    assert_eq!(
+        read.last().map(|p| p.cursor.clone()),
+        Some(Cursor::Block {
+            number: BLOCK + 2,
+            log_index: 0
+        })
+    );
+
+    assert_eq!(
+        node.ranges(),
+        [
+            (BLOCK, BLOCK + 2),
+            (BLOCK, BLOCK),
+            (BLOCK + 1, BLOCK + 2),
+            (BLOCK + 1, BLOCK + 1),
+            (BLOCK + 2, BLOCK + 2),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn resumes_after_the_last_read_log() -> Result<()> {
+    let node = Arc::new(Node::new(100)?);
+    let source = ChainSource::new(config(serve(node.clone()).await?))?;
+
+    let from = Cursor::Block {
+        number: BLOCK,
+        log_index: 12,
+    };
+    let read: Vec<_> = source.open(Some(from)).await?.try_collect().await?;
+
+    let amounts: Vec<_> = read.iter().map(|p| p.transfer.amount).collect();
+    assert_eq!(amounts, [1_000_000.0, 0.000001]);
+    assert_eq!(node.ranges(), [(BLOCK, BLOCK + 2)]);
+
+    assert!(matches!(
+        source.open(Some(Cursor::Offset(3))).await,
+        Err(Error::InvalidConfiguration(_))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn follows_the_chain_head() -> Result<()> {
+    let node = Arc::new(Node::new(100)?);
+    node.head.store(BLOCK, Ordering::SeqCst);
+    let source = ChainSource::new(ChainConfig {
+        to_block: None,
+        confirmations: 0,
+        ..config(serve(node.clone()).await?)
+    })?;
+
+    let mut transfers = source.open(None).await?;
+    for _ in 0..2 {
+        assert!(transfers.next().await.transpose()?.is_some());
+    }
+
+    node.head.store(BLOCK + 2, Ordering::SeqCst);
+    let next = transfers.next().await.transpose()?;
+
+    assert_eq!(next.map(|p| p.transfer.ts), Some(1_705_306_019));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn retries_unavailable_nodes() -> Result<()> {
+    let node = Arc::new(Node::new(100)?);
+    node.outages.store(2, Ordering::SeqCst);
+    let url = serve(node.clone()).await?;
+    let source = ChainSource::new(config(url.clone()))?.with_client(quick_retries(&url)?);
+
+    let read: Vec<_> = source.open(None).await?.try_collect().await?;
+
+    assert_eq!(read.len(), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn backs_off_throttled_nodes_without_narrowing() -> Result<()> {
+    let node = Arc::new(Node::new(100)?);
+    node.throttled.store(2, Ordering::SeqCst);
+    let url = serve(node.clone()).await?;
+    let source = ChainSource::new(config(url.clone()))?.with_client(quick_retries(&url)?);
+
+    let read: Vec<_> = source.open(None).await?.try_collect().await?;
+
+    assert_eq!(read.len(), 3);
+    assert_eq!(node.ranges(), [(BLOCK, BLOCK + 2)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fails_when_a_single_block_is_refused() -> Result<()> {
+    let node = Arc::new(Node::new(1)?);
+    let source = ChainSource::new(config(serve(node.clone()).await?))?;
+
+    let read: Vec<_> = source.open(None).await?.collect().await;
+
+    assert!(matches!(read.as_slice(), [Err(Error::Io { .. })]));
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use rust_challenge::{
+    chain::{
+        erc20::{Erc20Decoder, TransferLog},
+        parse_logs, Log,
+    },
+    errors::Error,
+};
+
+const FIXTURE: &str = "tests/fixtures/erc20_logs.json";
+const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+fn fixture() -> Result<Vec<Log>> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(FIXTURE);
+
+    Ok(parse_logs(&std::fs::read_to_string(path)?)?)
+}
+
+fn decode_all(decoder: &Erc20Decoder, logs: &[Log]) -> Result<Vec<TransferLog>> {
+    // What `eth_getBlockByNumber` returns for the blocks lacking `blockTimestamp`
+    let timestamps = HashMap::from([(19_000_001, 1_705_306_007), (19_000_002, 1_705_306_019)]);
+
+    let mut decoded = vec![];
+    for log in logs {
+        let block = log
+            .block_number
+            .as_deref()
+            .map(|b| rust_challenge::chain::parse_quantity("blockNumber", b))
+            .transpose()?;
+        let timestamp = block.and_then(|b| timestamps.get(&b).copied());
+
+        decoded.extend(decoder.decode(log, timestamp)?);
+    }
+
+    Ok(decoded)
+}
+
+#[test]
+fn decodes_saved_usdc_logs() -> Result<()> {
+    let decoder = Erc20Decoder::new(USDC, 6)?.with_usd_price(0.9998);
+
+    let decoded = decode_all(&decoder, &fixture()?)?;
+
+    // Approval, ERC-721, removed and DAI logs are left out
+    assert_eq!(decoded.len(), 3);
+
+    let first = &decoded[0];
+    assert_eq!(first.token, USDC.to_ascii_lowercase());
+    assert_eq!(first.block_number, 19_000_000);
+    assert_eq!(first.log_index, 12);
+    assert_eq!(
+        first.tx_hash,
+        "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060"
+    );
+    assert_eq!(first.transfer.ts, 1_705_305_995);
+    assert_eq!(
+        first.transfer.from,
+        "0x28c6c06298d514db089934071355e5743bf21d60"
+    );
+    assert_eq!(
+        first.transfer.to,
+        "0x4a14347083b80e5216ca31350a2d21702ac3650d"
+    );
+    assert_eq!(first.transfer.amount, 2_500.5);
+    assert_eq!(first.transfer.usd_price, 0.9998);
+
+    let mint = &decoded[1];
+    assert_eq!(mint.transfer.from, format!("0x{}", "0".repeat(40)));
+    assert_eq!(mint.transfer.amount, 1_000_000.0);
+
+    let dust = &decoded[2];
+    assert_eq!(dust.transfer.ts, 1_705_306_019);
+    assert_eq!(dust.transfer.amount, 0.000001);
+
+    Ok(())
+}
+
+#[test]
+fn other_tokens_use_their_own_decoder() -> Result<()> {
+    let dai = Erc20Decoder::new("0x6b175474e89094c44da98b954eedeac495271d0f", 18)?;
+
+    let decoded = decode_all(&dai, &fixture()?)?;
+
+    assert_eq!(decoded.len(), 1);
+    assert_eq!(decoded[0].transfer.amount, 5.0);
+    assert_eq!(decoded[0].transfer.ts, 1_705_306_007);
+
+    Ok(())
+}
+
+#[test]
+fn rejects_malformed_transfer_logs() -> Result<()> {
+    let decoder = Erc20Decoder::new(USDC, 6)?;
+    let logs = fixture()?;
+
+    let mut short_data = logs[0].clone();
+    short_data.data = "0x01".to_string();
+    let mut no_timestamp = logs[0].clone();
+    no_timestamp.block_timestamp = None;
+    let mut no_block = logs[0].clone();
+    no_block.block_number = None;
+
+    for log in [short_data, no_timestamp, no_block] {
+        assert!(matches!(
+            decoder.decode(&log, None),
+            Err(Error::ValidationFailed(_))
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use anyhow::Result;
+use rust_challenge::{factories::defaults::generator, utils::time::FixedClock};
+
+const GOLDEN: &str = "tests/fixtures/transfers_seed_42.csv";
+
+// Regenerate the fixture with `UPDATE_GOLDEN=1 cargo test --test generator_golden`
+#[test]
+fn seeded_generator_matches_golden_file() -> Result<()> {
+    let transfers = generator()
+        .with_seed(42)
+        .with_clock(FixedClock(1_700_000_000))
+        .build()
+        .generate(25)?;
+
+    let rendered: String = transfers
+        .iter()
+        .map(|t| {
+            format!(
+                "{},{},{},{:?},{:?}\n",
+                t.ts, t.from, t.to, t.amount, t.usd_price
+            )
+        })
+        .collect();
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &rendered)?;
+    }
+
+    assert_eq!(rendered, std::fs::read_to_string(&path)?);
+
+    Ok(())
+}
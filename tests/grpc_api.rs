@@ -0,0 +1,169 @@
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use futures::{stream, TryStreamExt};
+use rust_challenge::{
+    grpc::{
+        proto::{self, analytics_client::AnalyticsClient},
+        GrpcService,
+    },
+    models::{transfer::Transfer, user_stats::UserStats},
+    repositories::mock::MockStorage,
+    services::stats::calculator::StatsCalculator,
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{transport::Channel, Code};
+
+fn transfer(ts: u64, from: &str, to: &str, amount: f64) -> Transfer {
+    Transfer {
+        ts,
+        from: from.to_string(),
+        to: to.to_string(),
+        amount,
+        usd_price: 1.0,
+    }
+}
+
+// Serves the mock storage on an ephemeral port for the lifetime of the test runtime
+async fn client(transfers: Vec<Transfer>) -> Result<AnalyticsClient<Channel>> {
+    let storage = MockStorage {
+        transfers,
+        append: true,
+        ..Default::default()
+    };
+    let service = GrpcService::new(storage, StatsCalculator::new()).with_batch_size(2);
+
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(service.into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    Ok(AnalyticsClient::connect(format!("http://{}", addr)).await?)
+}
+
+fn range(from_ts: Option<u64>, to_ts: Option<u64>) -> Option<proto::TimeRange> {
+    Some(proto::TimeRange { from_ts, to_ts })
+}
+
+#[tokio::test]
+async fn ingested_transfers_show_up_in_stats() -> Result<()> {
+    let mut client = client(Vec::new()).await?;
+
+    let transfers = vec![
+        transfer(100, "0xMint", "0xA", 50.0),
+        transfer(200, "0xMint", "0xB", 20.0),
+        transfer(300, "0xA", "0xC", 10.0),
+    ];
+    let summary = client
+        .ingest_transfers(stream::iter(
+            transfers.into_iter().map(proto::Transfer::from),
+        ))
+        .await?
+        .into_inner();
+    assert_eq!(summary.ingested, 3);
+
+    let stats: UserStats = client
+        .get_user_stats(proto::GetUserStatsRequest {
+            address: "0xA".to_string(),
+            range: None,
+        })
+        .await?
+        .into_inner()
+        .into();
+    assert_eq!(stats.total_volume, 60.0);
+    assert_eq!(stats.max_balance, 50.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejects_invalid_batches() -> Result<()> {
+    let mut client = client(Vec::new()).await?;
+
+    let transfers = vec![
+        transfer(100, "0xMint", "0xA", 50.0),
+        transfer(200, "0xMint", "0xB", 20.0),
+        transfer(300, "0xA", "0xA", 10.0),
+    ];
+    let res = client
+        .ingest_transfers(stream::iter(
+            transfers.into_iter().map(proto::Transfer::from),
+        ))
+        .await;
+    let Err(status) = res else {
+        anyhow::bail!("self-transfer must be rejected");
+    };
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    // Batches of two, the first one was already stored
+    let stats = client
+        .get_user_stats(proto::GetUserStatsRequest {
+            address: "0xB".to_string(),
+            range: None,
+        })
+        .await?
+        .into_inner();
+    assert_eq!(stats.total_volume, 20.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_addresses_are_not_found() -> Result<()> {
+    let mut client = client(vec![transfer(100, "0xMint", "0xA", 50.0)]).await?;
+
+    let res = client
+        .get_user_stats(proto::GetUserStatsRequest {
+            address: "0xA".to_string(),
+            range: range(Some(200), None),
+        })
+        .await;
+
+    assert_eq!(res.map(|_| ()).map_err(|s| s.code()), Err(Code::NotFound));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn streams_ranked_stats() -> Result<()> {
+    let mut client = client(vec![
+        transfer(100, "0xMint", "0xA", 50.0),
+        transfer(200, "0xMint", "0xB", 20.0),
+        transfer(300, "0xA", "0xC", 10.0),
+    ])
+    .await?;
+
+    let stats: Vec<proto::UserStats> = client
+        .stream_stats(proto::StreamStatsRequest {
+            range: range(None, Some(250)),
+            sort: proto::RankBy::Volume.into(),
+            ascending: true,
+            limit: None,
+        })
+        .await?
+        .into_inner()
+        .try_collect()
+        .await?;
+    let addresses: Vec<&str> = stats.iter().map(|s| s.address.as_str()).collect();
+    assert_eq!(addresses, ["0xB", "0xA", "0xMint"]);
+
+    let stats: Vec<proto::UserStats> = client
+        .stream_stats(proto::StreamStatsRequest {
+            range: None,
+            sort: proto::RankBy::MaxBalance.into(),
+            ascending: false,
+            limit: Some(2),
+        })
+        .await?
+        .into_inner()
+        .try_collect()
+        .await?;
+    let addresses: Vec<&str> = stats.iter().map(|s| s.address.as_str()).collect();
+    assert_eq!(addresses, ["0xA", "0xB"]);
+
+    Ok(())
+}
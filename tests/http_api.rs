@@ -0,0 +1,257 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use http_body_util::BodyExt;
+use rust_challenge::{
+    api::{self, ApiState, Ingested, Page},
+    errors::Error,
+    models::{
+        balance_checkpoint::BalanceCheckpoint,
+        transfer::{Transfer, TransferFilter, TransferOrdering},
+        user_stats::UserStats,
+    },
+    repositories::{mock::MockStorage, storage::Storage},
+    services::stats::calculator::StatsCalculator,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+fn transfer(ts: u64, from: &str, to: &str, amount: f64) -> Transfer {
+    Transfer {
+        ts,
+        from: from.to_string(),
+        to: to.to_string(),
+        amount,
+        usd_price: 1.0,
+    }
+}
+
+fn app() -> Router {
+    let storage = MockStorage {
+        transfers: vec![
+            transfer(100, "0xMint", "0xA", 50.0),
+            transfer(200, "0xMint", "0xB", 20.0),
+            transfer(300, "0xA", "0xC", 10.0),
+        ],
+        append: true,
+        ..Default::default()
+    };
+
+    api::router(ApiState::new(storage, StatsCalculator::new()))
+}
+
+async fn send(app: &Router, request: Request<Body>) -> Result<(StatusCode, Vec<u8>)> {
+    let response = app.clone().oneshot(request).await?;
+    let status = response.status();
+    let body = response.into_body().collect().await?.to_bytes().to_vec();
+
+    Ok((status, body))
+}
+
+async fn get<T: DeserializeOwned>(app: &Router, uri: &str) -> Result<(StatusCode, T)> {
+    let (status, body) = send(app, Request::get(uri).body(Body::empty())?).await?;
+
+    Ok((status, serde_json::from_slice(&body)?))
+}
+
+async fn post(app: &Router, uri: &str, body: Value) -> Result<(StatusCode, Value)> {
+    let request = Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+    let (status, body) = send(app, request).await?;
+
+    Ok((status, serde_json::from_slice(&body)?))
+}
+
+#[tokio::test]
+async fn pages_through_ranked_stats() -> Result<()> {
+    let app = app();
+
+    let (status, page): (_, Page<UserStats>) =
+        get(&app, "/stats?sort=volume&limit=2&offset=1").await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((page.total, page.offset, page.limit), (4, 1, 2));
+    let addresses: Vec<&str> = page.items.iter().map(|s| s.address.as_str()).collect();
+    assert_eq!(addresses, ["0xA", "0xB"]);
+
+    let (_, page): (_, Page<UserStats>) = get(&app, "/stats?from=150&order=ascending").await?;
+    let addresses: Vec<&str> = page.items.iter().map(|s| s.address.as_str()).collect();
+    assert_eq!(addresses, ["0xA", "0xC", "0xB", "0xMint"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn looks_up_single_address() -> Result<()> {
+    let app = app();
+
+    let (status, stats): (_, UserStats) = get(&app, "/stats/0xA").await?;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats.total_volume, 60.0);
+    assert_eq!(stats.max_balance, 50.0);
+
+    let (status, body): (_, Value) = get(&app, "/stats/0xNobody").await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"], "not_found");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn filters_transfers() -> Result<()> {
+    let app = app();
+
+    let (status, page): (_, Page<Transfer>) = get(&app, "/transfers?address=0xA&to=250").await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items, [transfer(100, "0xMint", "0xA", 50.0)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejects_bad_query_parameters() -> Result<()> {
+    let app = app();
+
+    let (status, body): (_, Value) = get(&app, "/stats?limit=5000").await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "bad_request");
+
+    let (status, _) = send(&app, Request::get("/stats?sort=luck").body(Body::empty())?).await?;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ingested_transfers_show_up_in_stats() -> Result<()> {
+    let app = app();
+
+    let (status, body) = post(
+        &app,
+        "/transfers",
+        json!([{"ts": 400, "from": "0xC", "to": "0xD", "amount": 4.0, "usd_price": 2.0}]),
+    )
+    .await?;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        serde_json::from_value::<Ingested>(body)?,
+        Ingested { ingested: 1 }
+    );
+
+    let (_, stats): (_, UserStats) = get(&app, "/stats/0xD").await?;
+    assert_eq!(stats.avg_buy_price, 2.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn backfills_history_older_than_what_was_ingested() -> Result<()> {
+    let app = app();
+
+    for ts in [400, 150] {
+        let (status, _) = post(
+            &app,
+            "/transfers",
+            json!([{"ts": ts, "from": "0xMint", "to": "0xD", "amount": 1.0, "usd_price": 2.0}]),
+        )
+        .await?;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let (_, page): (_, Value) = get(&app, "/transfers").await?;
+    assert_eq!(page["total"], 5);
+
+    let (_, stats): (_, UserStats) = get(&app, "/stats/0xD").await?;
+    assert_eq!(stats.total_volume, 2.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejects_invalid_transfers_as_a_batch() -> Result<()> {
+    let app = app();
+
+    let (status, body) = post(
+        &app,
+        "/transfers",
+        json!([
+            {"ts": 400, "from": "0xC", "to": "0xD", "amount": 4.0, "usd_price": 2.0},
+            {"ts": 401, "from": "0xD", "to": "0xD", "amount": 1.0, "usd_price": 2.0},
+        ]),
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "validation_failed");
+
+    let (status, _): (_, Value) = get(&app, "/stats/0xD").await?;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+struct Unavailable;
+
+fn unavailable<T>() -> rust_challenge::errors::Result<T> {
+    Err(Error::StorageUnavailable {
+        context: "ClickHouse is down".to_string(),
+        source: Box::new(std::io::Error::other("connection refused")),
+    })
+}
+
+#[async_trait]
+impl Storage for Unavailable {
+    async fn get_sorted(
+        &self,
+        _: TransferOrdering,
+    ) -> rust_challenge::errors::Result<Vec<Transfer>> {
+        unavailable()
+    }
+
+    async fn get_filtered(
+        &self,
+        _: &TransferFilter,
+    ) -> rust_challenge::errors::Result<Vec<Transfer>> {
+        unavailable()
+    }
+
+    async fn insert_all(&mut self, _: &[Transfer]) -> rust_challenge::errors::Result<()> {
+        unavailable()
+    }
+
+    async fn get_checkpoint(
+        &self,
+        _: &str,
+        _: u64,
+    ) -> rust_challenge::errors::Result<Option<BalanceCheckpoint>> {
+        unavailable()
+    }
+
+    async fn insert_checkpoints(
+        &mut self,
+        _: &[BalanceCheckpoint],
+    ) -> rust_challenge::errors::Result<()> {
+        unavailable()
+    }
+}
+
+#[tokio::test]
+async fn maps_storage_outages_to_service_unavailable() -> Result<()> {
+    let app = api::router(ApiState::new(Unavailable, StatsCalculator::new()));
+
+    let (status, body): (_, Value) = get(&app, "/stats").await?;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["error"], "storage_unavailable");
+    assert!(!body["message"].as_str().unwrap_or("").contains("refused"));
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use std::net::SocketAddr;
+
+use anyhow::{anyhow, Result};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    Router,
+};
+use futures::{SinkExt, StreamExt};
+use rust_challenge::{
+    api::{self, live::LiveMessage, ApiState},
+    repositories::mock::MockStorage,
+    services::stats::calculator::StatsCalculator,
+};
+use serde_json::json;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tower::ServiceExt;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// The router is served on an ephemeral port and also returned for in-process requests,
+// both share one live feed
+async fn serve() -> Result<(Router, SocketAddr)> {
+    let app = api::router(ApiState::new(
+        MockStorage::appending(),
+        StatsCalculator::new(),
+    ));
+
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+    let addr = listener.local_addr()?;
+    let served = app.clone();
+    tokio::spawn(async move { axum::serve(listener, served).await });
+
+    Ok((app, addr))
+}
+
+async fn ingest(app: &Router, transfers: serde_json::Value) -> Result<()> {
+    let request = Request::post("/transfers")
+        .header("content-type", "application/json")
+        .body(Body::from(transfers.to_string()))?;
+    let response = app.clone().oneshot(request).await?;
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    Ok(())
+}
+
+async fn next(socket: &mut Socket) -> Result<LiveMessage> {
+    loop {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => return Ok(serde_json::from_str(&text)?),
+            Some(Ok(_)) => continue,
+            Some(Err(e)) => return Err(e.into()),
+            None => return Err(anyhow!("socket closed")),
+        }
+    }
+}
+
+fn addresses(message: &LiveMessage) -> Vec<&str> {
+    match message {
+        LiveMessage::Snapshot { stats } | LiveMessage::Update { stats } => {
+            stats.iter().map(|s| s.address.as_str()).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn pushes_updates_matching_the_subscription() -> Result<()> {
+    let (app, addr) = serve().await?;
+    let (mut socket, _) =
+        connect_async(format!("ws://{}/stats/live?addresses=0xB,0xC", addr)).await?;
+
+    assert_eq!(
+        next(&mut socket).await?,
+        LiveMessage::Snapshot { stats: vec![] }
+    );
+
+    ingest(
+        &app,
+        json!([
+            {"ts": 1, "from": "0xMint", "to": "0xA", "amount": 5.0, "usd_price": 1.0},
+            {"ts": 2, "from": "0xMint", "to": "0xB", "amount": 3.0, "usd_price": 2.0},
+        ]),
+    )
+    .await?;
+
+    let update = next(&mut socket).await?;
+    assert!(matches!(update, LiveMessage::Update { .. }));
+    assert_eq!(addresses(&update), ["0xB"]);
+
+    ingest(
+        &app,
+        json!([{"ts": 3, "from": "0xB", "to": "0xC", "amount": 1.0, "usd_price": 4.0}]),
+    )
+    .await?;
+
+    let LiveMessage::Update { stats } = next(&mut socket).await? else {
+        return Err(anyhow!("expected an update"));
+    };
+    assert_eq!(stats.len(), 2);
+    assert_eq!(
+        (stats[0].address.as_str(), stats[0].total_volume),
+        ("0xB", 4.0)
+    );
+    assert_eq!(stats[0].avg_sell_price, 4.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn resubscribing_sends_a_fresh_snapshot() -> Result<()> {
+    let (app, addr) = serve().await?;
+    let (mut socket, _) = connect_async(format!("ws://{}/stats/live", addr)).await?;
+    next(&mut socket).await?;
+
+    ingest(
+        &app,
+        json!([
+            {"ts": 1, "from": "0xMint", "to": "0xA", "amount": 5.0, "usd_price": 1.0},
+            {"ts": 2, "from": "0xMint", "to": "0xB", "amount": 3.0, "usd_price": 1.0},
+        ]),
+    )
+    .await?;
+    assert_eq!(
+        addresses(&next(&mut socket).await?),
+        ["0xA", "0xB", "0xMint"]
+    );
+
+    socket
+        .send(Message::text(json!({"min_volume": 4.0}).to_string()))
+        .await?;
+    let snapshot = next(&mut socket).await?;
+    assert!(matches!(snapshot, LiveMessage::Snapshot { .. }));
+    assert_eq!(addresses(&snapshot), ["0xA", "0xMint"]);
+
+    socket.send(Message::text("{\"volume\": 1}")).await?;
+    assert!(matches!(
+        next(&mut socket).await?,
+        LiveMessage::Error { .. }
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rebuilds_live_stats_for_backfilled_history() -> Result<()> {
+    let (app, addr) = serve().await?;
+    let (mut socket, _) = connect_async(format!("ws://{}/stats/live?addresses=0xA", addr)).await?;
+    next(&mut socket).await?;
+
+    ingest(
+        &app,
+        json!([{"ts": 10, "from": "0xA", "to": "0xB", "amount": 5.0, "usd_price": 1.0}]),
+    )
+    .await?;
+    next(&mut socket).await?;
+
+    ingest(
+        &app,
+        json!([{"ts": 5, "from": "0xMint", "to": "0xA", "amount": 8.0, "usd_price": 1.0}]),
+    )
+    .await?;
+
+    let LiveMessage::Update { stats } = next(&mut socket).await? else {
+        return Err(anyhow!("expected an update"));
+    };
+    assert_eq!(stats.len(), 1);
+    assert_eq!(
+        (stats[0].total_volume, stats[0].max_balance),
+        (13.0, 8.0),
+        "the backfilled transfer comes first"
+    );
+
+    Ok(())
+}